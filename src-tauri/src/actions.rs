@@ -0,0 +1,783 @@
+use crate::bus::ObjectChange;
+use crate::motion::{ActiveMotion, Motion};
+use crate::state::{
+    AppState, ContainerSlot, ControlValue, FrameMode, ObjectBounds, ObjectData, ObjectGeometry,
+    ObjectKind, ObjectLink, ObjectSymbol, PointerType, Pose3D, ServerStatus, TuioObject,
+};
+
+/// Validate normalized coordinates
+pub fn validate_position(x: f32, y: f32) -> Result<(), String> {
+    if !(0.0..=1.0).contains(&x) || !(0.0..=1.0).contains(&y) {
+        return Err("Coordinates must be in range [0.0, 1.0]".to_string());
+    }
+
+    Ok(())
+}
+
+/// Validate the size of a bounding box
+pub fn validate_bounds(bounds: &ObjectBounds) -> Result<(), String> {
+    let valid_size = |size: f32| size > 0.0 && size <= 1.0;
+    if !valid_size(bounds.width) || !valid_size(bounds.height) {
+        return Err("Width and height must be in range (0.0, 1.0]".to_string());
+    }
+
+    if !(0.0..=bounds.width * bounds.height).contains(&bounds.area) {
+        return Err("Area must be in range [0.0, width * height]".to_string());
+    }
+
+    Ok(())
+}
+
+/// Validate a frame rate
+pub fn validate_frame_rate(fps: u32) -> Result<(), String> {
+    if !(1..=120).contains(&fps) {
+        return Err("FPS must be in range [1, 120]".to_string());
+    }
+
+    Ok(())
+}
+
+/// Validate the canvas dimensions sent in FRM
+pub fn validate_canvas_dimensions(width: u16, height: u16) -> Result<(), String> {
+    if width == 0 || height == 0 {
+        return Err("Width and height must be greater than 0".to_string());
+    }
+
+    Ok(())
+}
+
+/// Validate the component ID of a token
+pub fn validate_component_id(component_id: u16) -> Result<(), String> {
+    if !(1..=24).contains(&component_id) {
+        return Err("Component ID must be in range [1, 24]".to_string());
+    }
+
+    Ok(())
+}
+
+/// Validate a symbol
+pub fn validate_symbol(symbol: &ObjectSymbol) -> Result<(), String> {
+    if symbol.group.is_empty() {
+        return Err("Symbol group must not be empty".to_string());
+    }
+
+    Ok(())
+}
+
+/// Validate the values of CTL messages
+pub fn validate_controls(controls: &[ControlValue]) -> Result<(), String> {
+    for control in controls {
+        if let ControlValue::Float(value) = control {
+            if !(-1.0..=1.0).contains(value) {
+                return Err(format!(
+                    "Invalid control value {}: must be between -1 and 1",
+                    value
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate the payloads of DAT messages
+pub fn validate_data(data: &[ObjectData]) -> Result<(), String> {
+    if data.iter().any(|data| data.mime.is_empty()) {
+        return Err("Data MIME type must not be empty".to_string());
+    }
+
+    Ok(())
+}
+
+/// Validate outline, inner contours and skeleton of an object
+pub fn validate_geometry(geometry: &ObjectGeometry) -> Result<(), String> {
+    let points = geometry
+        .outline
+        .iter()
+        .chain(geometry.inner_contours.iter().flatten())
+        .chain(geometry.skeleton.iter().flatten());
+    if points.flatten().any(|v| !v.is_finite()) {
+        return Err("Geometry points must be finite".to_string());
+    }
+
+    // An empty outline is allowed for objects with inner contours only
+    let outline_too_short = !geometry.outline.is_empty() && geometry.outline.len() < 3;
+    if outline_too_short || geometry.inner_contours.iter().any(|c| c.len() < 3) {
+        return Err("Contours need at least 3 points".to_string());
+    }
+    if geometry.skeleton.iter().any(|branch| branch.len() < 2) {
+        return Err("Skeleton branches need at least 2 points".to_string());
+    }
+
+    Ok(())
+}
+
+/// Validate the z position and depth of a 3D pose
+pub fn validate_pose_3d(z: f32, depth: f32) -> Result<(), String> {
+    if !(0.0..=1.0).contains(&z) {
+        return Err(format!("Invalid z {}: must be between 0 and 1", z));
+    }
+    if depth.is_nan() || depth < 0.0 {
+        return Err(format!("Invalid depth {}: must not be negative", depth));
+    }
+
+    Ok(())
+}
+
+/// Validate placing an object inside a container
+///
+/// `container_of` returns the container an object is placed in. The
+/// containers are walked up to reject cycles.
+pub fn validate_container(
+    session_id: u32,
+    container_id: u32,
+    container_of: impl Fn(u32) -> Option<u32>,
+) -> Result<(), String> {
+    let mut current = Some(container_id);
+    while let Some(id) = current {
+        if id == session_id {
+            return Err("An object cannot be placed inside itself".to_string());
+        }
+        current = container_of(id);
+    }
+
+    Ok(())
+}
+
+/// Validate a link between two objects
+pub fn validate_link(session_id: u32, target_id: u32) -> Result<(), String> {
+    if session_id == target_id {
+        return Err("An object cannot be linked to itself".to_string());
+    }
+
+    Ok(())
+}
+
+/// Add a token, returning its session ID
+pub fn add_object(state: &AppState, component_id: u16, x: f32, y: f32) -> Result<u32, String> {
+    validate_component_id(component_id)?;
+    validate_position(x, y)?;
+
+    // Check if component_id is already in use
+    {
+        let objects = state.objects.lock();
+        if objects
+            .values()
+            .any(|obj| obj.kind == ObjectKind::Token && obj.component_id == component_id)
+        {
+            return Err(format!("Component ID {} is already in use", component_id));
+        }
+    }
+
+    let session_id = state.allocate_session_id();
+    let timestamp = chrono::Utc::now().timestamp_millis();
+
+    // Use component_id as type_id for color mapping
+    let object = TuioObject::new(session_id, component_id, component_id, x, y, timestamp);
+
+    state.objects.lock().insert(session_id, object);
+    state.object_changed(session_id, ObjectChange::Added);
+
+    Ok(session_id)
+}
+
+/// Add a pointer, returning its session ID
+pub fn add_pointer(
+    state: &AppState,
+    pointer_type: PointerType,
+    x: f32,
+    y: f32,
+) -> Result<u32, String> {
+    validate_position(x, y)?;
+
+    let session_id = state.allocate_session_id();
+    let timestamp = chrono::Utc::now().timestamp_millis();
+
+    let object = TuioObject::new_pointer(session_id, pointer_type, x, y, timestamp);
+
+    state.objects.lock().insert(session_id, object);
+    state.object_changed(session_id, ObjectChange::Added);
+
+    Ok(session_id)
+}
+
+/// Add a blob, returning its session ID
+pub fn add_blob(state: &AppState, x: f32, y: f32, width: f32, height: f32) -> Result<u32, String> {
+    validate_position(x, y)?;
+
+    let bounds = ObjectBounds::new(width, height);
+    validate_bounds(&bounds)?;
+
+    let session_id = state.allocate_session_id();
+    let timestamp = chrono::Utc::now().timestamp_millis();
+
+    let object = TuioObject::new_blob(session_id, x, y, bounds, timestamp);
+
+    state.objects.lock().insert(session_id, object);
+    state.object_changed(session_id, ObjectChange::Added);
+
+    Ok(session_id)
+}
+
+/// Move and rotate an object
+pub fn update_object(
+    state: &AppState,
+    session_id: u32,
+    x: f32,
+    y: f32,
+    angle: f32,
+) -> Result<(), String> {
+    validate_position(x, y)?;
+
+    let timestamp = chrono::Utc::now().timestamp_millis();
+
+    let mut objects = state.objects.lock();
+    if let Some(object) = objects.get_mut(&session_id) {
+        object.x = x;
+        object.y = y;
+        object.angle = angle;
+        object.last_update = timestamp;
+        state.object_changed(session_id, ObjectChange::Updated);
+        Ok(())
+    } else {
+        Err(format!("Object with session_id {} not found", session_id))
+    }
+}
+
+/// Move a pointer and update its pointer properties
+#[allow(clippy::too_many_arguments)]
+pub fn update_pointer(
+    state: &AppState,
+    session_id: u32,
+    x: f32,
+    y: f32,
+    angle: f32,
+    shear: f32,
+    radius: f32,
+    pressure: f32,
+) -> Result<(), String> {
+    validate_position(x, y)?;
+
+    // Validate pointer properties
+    if radius < 0.0 {
+        return Err("Radius must not be negative".to_string());
+    }
+    if !(-1.0..=1.0).contains(&pressure) {
+        return Err("Pressure must be in range [-1.0, 1.0]".to_string());
+    }
+
+    let timestamp = chrono::Utc::now().timestamp_millis();
+
+    let mut objects = state.objects.lock();
+    match objects.get_mut(&session_id) {
+        Some(object) if object.kind == ObjectKind::Pointer => {
+            object.x = x;
+            object.y = y;
+            object.angle = angle;
+            object.shear = shear;
+            object.radius = radius;
+            object.pressure = pressure;
+            object.last_update = timestamp;
+            state.object_changed(session_id, ObjectChange::Updated);
+            Ok(())
+        }
+        Some(_) => Err(format!(
+            "Object with session_id {} is not a pointer",
+            session_id
+        )),
+        None => Err(format!("Object with session_id {} not found", session_id)),
+    }
+}
+
+/// Set or clear the bounding box, blobs always keep one
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn set_object_bounds(
+    state: &AppState,
+    session_id: u32,
+    bounds: Option<ObjectBounds>,
+) -> Result<(), String> {
+    if let Some(bounds) = &bounds {
+        validate_bounds(bounds)?;
+    }
+
+    let mut objects = state.objects.lock();
+    match objects.get_mut(&session_id) {
+        Some(object) if object.kind == ObjectKind::Blob && bounds.is_none() => Err(format!(
+            "Object with session_id {} is a blob and needs bounds",
+            session_id
+        )),
+        Some(object) => {
+            object.bounds = bounds;
+            state.object_changed(session_id, ObjectChange::Updated);
+            Ok(())
+        }
+        None => Err(format!("Object with session_id {} not found", session_id)),
+    }
+}
+
+/// Set or clear the symbol sent in SYM
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn set_object_symbol(
+    state: &AppState,
+    session_id: u32,
+    symbol: Option<ObjectSymbol>,
+) -> Result<(), String> {
+    if let Some(symbol) = &symbol {
+        validate_symbol(symbol)?;
+    }
+
+    let mut objects = state.objects.lock();
+    if let Some(object) = objects.get_mut(&session_id) {
+        object.symbol = symbol;
+        state.object_changed(session_id, ObjectChange::Updated);
+        Ok(())
+    } else {
+        Err(format!("Object with session_id {} not found", session_id))
+    }
+}
+
+/// Replace the values sent in CTL
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn set_object_controls(
+    state: &AppState,
+    session_id: u32,
+    controls: Vec<ControlValue>,
+) -> Result<(), String> {
+    validate_controls(&controls)?;
+
+    let mut objects = state.objects.lock();
+    if let Some(object) = objects.get_mut(&session_id) {
+        if object.controls != controls {
+            object.controls = controls;
+            state.changed_controls.lock().insert(session_id);
+        }
+        state.object_changed(session_id, ObjectChange::Updated);
+        Ok(())
+    } else {
+        Err(format!("Object with session_id {} not found", session_id))
+    }
+}
+
+/// Replace the payloads sent in DAT
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn set_object_data(
+    state: &AppState,
+    session_id: u32,
+    data: Vec<ObjectData>,
+) -> Result<(), String> {
+    validate_data(&data)?;
+
+    let mut objects = state.objects.lock();
+    if let Some(object) = objects.get_mut(&session_id) {
+        if object.data != data {
+            object.data = data;
+            state.changed_controls.lock().insert(session_id);
+        }
+        state.object_changed(session_id, ObjectChange::Updated);
+        Ok(())
+    } else {
+        Err(format!("Object with session_id {} not found", session_id))
+    }
+}
+
+/// Set or clear the geometry sent in OCG, ICG and SKG
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn set_object_geometry(
+    state: &AppState,
+    session_id: u32,
+    geometry: Option<ObjectGeometry>,
+) -> Result<(), String> {
+    if let Some(geometry) = &geometry {
+        validate_geometry(geometry)?;
+    }
+
+    let mut objects = state.objects.lock();
+    if let Some(object) = objects.get_mut(&session_id) {
+        object.geometry = geometry;
+        state.object_changed(session_id, ObjectChange::Updated);
+        Ok(())
+    } else {
+        Err(format!("Object with session_id {} not found", session_id))
+    }
+}
+
+/// Set or clear the 3D pose of an object
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn set_object_pose_3d(
+    state: &AppState,
+    session_id: u32,
+    pose: Option<Pose3D>,
+) -> Result<(), String> {
+    if let Some(pose) = &pose {
+        validate_pose_3d(pose.z, pose.depth)?;
+    }
+
+    let mut objects = state.objects.lock();
+    if let Some(object) = objects.get_mut(&session_id) {
+        object.pose_3d = pose.map(|pose| match object.pose_3d {
+            // Keep the motion history so velocities stay continuous
+            Some(current) => Pose3D {
+                z: pose.z,
+                roll: pose.roll,
+                pitch: pose.pitch,
+                depth: pose.depth,
+                ..current
+            },
+            None => Pose3D::new(pose.z, pose.roll, pose.pitch, pose.depth),
+        });
+        state.object_changed(session_id, ObjectChange::Updated);
+        Ok(())
+    } else {
+        Err(format!("Object with session_id {} not found", session_id))
+    }
+}
+
+/// Place an object in a slot of a container
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn associate_container(
+    state: &AppState,
+    session_id: u32,
+    container_id: u32,
+    slot: u32,
+) -> Result<(), String> {
+    let mut objects = state.objects.lock();
+    if !objects.contains_key(&container_id) {
+        return Err(format!(
+            "Container with session_id {} not found",
+            container_id
+        ));
+    }
+
+    validate_container(session_id, container_id, |id| {
+        objects
+            .get(&id)
+            .and_then(|object| object.container)
+            .map(|c| c.container_id)
+    })?;
+
+    if let Some(object) = objects.get_mut(&session_id) {
+        object.container = Some(ContainerSlot { container_id, slot });
+        state.object_changed(session_id, ObjectChange::Updated);
+        Ok(())
+    } else {
+        Err(format!("Object with session_id {} not found", session_id))
+    }
+}
+
+/// Take an object out of its container
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn dissociate_container(state: &AppState, session_id: u32) -> Result<(), String> {
+    let mut objects = state.objects.lock();
+    if let Some(object) = objects.get_mut(&session_id) {
+        object.container = None;
+        state.object_changed(session_id, ObjectChange::Updated);
+        Ok(())
+    } else {
+        Err(format!("Object with session_id {} not found", session_id))
+    }
+}
+
+/// Link an object to another, replacing an existing link to it
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn link_objects(
+    state: &AppState,
+    session_id: u32,
+    target_id: u32,
+    physical: bool,
+    out_port: u16,
+    in_port: u16,
+) -> Result<(), String> {
+    validate_link(session_id, target_id)?;
+
+    let mut objects = state.objects.lock();
+    if !objects.contains_key(&target_id) {
+        return Err(format!("Object with session_id {} not found", target_id));
+    }
+
+    if let Some(object) = objects.get_mut(&session_id) {
+        // Replace an existing link to the same target
+        object.links.retain(|link| link.target_id != target_id);
+        object.links.push(ObjectLink {
+            target_id,
+            physical,
+            out_port,
+            in_port,
+        });
+        state.object_changed(session_id, ObjectChange::Updated);
+        Ok(())
+    } else {
+        Err(format!("Object with session_id {} not found", session_id))
+    }
+}
+
+/// Remove the link from an object to another
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn unlink_objects(state: &AppState, session_id: u32, target_id: u32) -> Result<(), String> {
+    let mut objects = state.objects.lock();
+    if let Some(object) = objects.get_mut(&session_id) {
+        object.links.retain(|link| link.target_id != target_id);
+        state.object_changed(session_id, ObjectChange::Updated);
+        Ok(())
+    } else {
+        Err(format!("Object with session_id {} not found", session_id))
+    }
+}
+
+/// Remove an object and all associations to it
+pub fn remove_object(state: &AppState, session_id: u32) -> Result<(), String> {
+    if state.remove_object(session_id).is_some() {
+        state.object_changed(session_id, ObjectChange::Removed);
+        Ok(())
+    } else {
+        Err(format!("Object with session_id {} not found", session_id))
+    }
+}
+
+/// Set the frame rate of the frame loop
+pub fn set_frame_rate(state: &AppState, fps: u32) -> Result<(), String> {
+    validate_frame_rate(fps)?;
+
+    state.config.lock().fps = fps;
+
+    // The frame loop picks up the new rate without waiting for its next frame
+    state.frame_rate.send_replace(fps);
+
+    Ok(())
+}
+
+/// Set the canvas dimensions sent in FRM
+pub fn set_canvas_dimensions(state: &AppState, width: u16, height: u16) -> Result<(), String> {
+    validate_canvas_dimensions(width, height)?;
+
+    let mut config = state.config.lock();
+    config.width = width;
+    config.height = height;
+
+    Ok(())
+}
+
+/// Hold the frame loop while keeping all connections open
+pub fn pause_frames(state: &AppState) {
+    state.frame_control.lock().paused = true;
+}
+
+/// Continue generating frames at the configured rate
+pub fn resume_frames(state: &AppState) {
+    let mut control = state.frame_control.lock();
+    control.paused = false;
+    control.pending_steps = 0;
+    state.frame_trigger.notify_one();
+}
+
+/// Generate the given number of frames while paused
+pub fn step_frame(state: &AppState, count: u32) -> Result<(), String> {
+    if count == 0 {
+        return Err("Step count must be at least 1".to_string());
+    }
+
+    let mut control = state.frame_control.lock();
+    if !control.paused {
+        return Err("Frame generation is not paused".to_string());
+    }
+    control.pending_steps = control.pending_steps.saturating_add(count);
+    state.frame_trigger.notify_one();
+
+    Ok(())
+}
+
+/// Choose between continuous and on-change frame generation
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn set_frame_mode(
+    state: &AppState,
+    mode: FrameMode,
+    min_interval_ms: u64,
+    keep_alive_ms: u64,
+    skip_unmoved_tokens: bool,
+) -> Result<(), String> {
+    if keep_alive_ms == 0 {
+        return Err("Keep-alive period must be greater than 0".to_string());
+    }
+    if min_interval_ms > keep_alive_ms {
+        return Err("Minimum interval must not exceed the keep-alive period".to_string());
+    }
+
+    {
+        let mut config = state.config.lock();
+        config.frame_mode = mode;
+        config.min_frame_interval_ms = min_interval_ms;
+        config.keep_alive_ms = keep_alive_ms;
+        config.skip_unmoved_tokens = skip_unmoved_tokens;
+    }
+
+    // Wake the frame loop, so it switches modes right away
+    state.frame_trigger.notify_one();
+
+    Ok(())
+}
+
+/// Attach a motion to an object, replacing its current motion
+pub fn attach_motion(state: &AppState, session_id: u32, motion: Motion) -> Result<(), String> {
+    motion.validate()?;
+
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    let active = {
+        let objects = state.objects.lock();
+        let object = objects
+            .get(&session_id)
+            .ok_or_else(|| format!("Object with session_id {} not found", session_id))?;
+        ActiveMotion::new(object, motion, timestamp)
+    };
+    state.motions.lock().insert(session_id, active);
+
+    // Wake the frame loop, so objects start moving in on-change mode
+    state.frame_trigger.notify_one();
+
+    Ok(())
+}
+
+/// Detach the motion of an object, leaving it where it is
+pub fn detach_motion(state: &AppState, session_id: u32) -> Result<(), String> {
+    match state.motions.lock().remove(&session_id) {
+        Some(_) => Ok(()),
+        None => Err(format!("No motion attached to object {}", session_id)),
+    }
+}
+
+/// Attached motions, ordered by session ID
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn list_motions(state: &AppState) -> Vec<ActiveMotion> {
+    let mut motions: Vec<ActiveMotion> = state.motions.lock().values().cloned().collect();
+    motions.sort_by_key(|motion| motion.session_id);
+    motions
+}
+
+/// Current status of the server, its sinks and the HTTP API
+pub fn server_status(state: &AppState) -> ServerStatus {
+    let running = *state.server_running.lock();
+    let config = state.config.lock();
+    let connected_clients = state.get_connected_clients();
+    let frame_count = *state.frame_counter.lock();
+    let object_count = state.objects.lock().len();
+    let sinks = state.sinks.statuses(&config.enabled_sinks);
+    let frame_control = *state.frame_control.lock();
+    let frame_stats = state.frame_stats.lock();
+
+    ServerStatus {
+        running,
+        port: config.port,
+        fps: config.fps,
+        connected_clients,
+        frame_count,
+        object_count,
+        sinks,
+        paused: frame_control.paused,
+        pending_steps: frame_control.pending_steps,
+        achieved_fps: frame_stats.achieved_fps(),
+        frame_jitter_ms: frame_stats.jitter_ms(),
+        http_api_address: state.api_server.local_addr(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_object_properties() {
+        let state = AppState::new();
+        let token = add_object(&state, 1, 0.5, 0.5).unwrap();
+        let blob = add_blob(&state, 0.5, 0.5, 0.2, 0.1).unwrap();
+
+        assert!(set_object_bounds(&state, blob, None).is_err());
+        assert!(set_object_bounds(&state, token, Some(ObjectBounds::new(0.0, 0.1))).is_err());
+        set_object_bounds(&state, token, Some(ObjectBounds::new(0.1, 0.1))).unwrap();
+
+        let symbol = ObjectSymbol {
+            type_id: 0,
+            group: String::new(),
+            data: "42".to_string(),
+        };
+        assert!(set_object_symbol(&state, token, Some(symbol.clone())).is_err());
+        let symbol = ObjectSymbol {
+            group: "barcode".to_string(),
+            ..symbol
+        };
+        set_object_symbol(&state, token, Some(symbol)).unwrap();
+
+        let pose = Pose3D::new(1.5, 0.0, 0.0, 0.1);
+        assert!(set_object_pose_3d(&state, token, Some(pose)).is_err());
+        set_object_pose_3d(&state, token, Some(Pose3D::new(0.5, 0.0, 0.0, 0.1))).unwrap();
+
+        let geometry = ObjectGeometry {
+            outline: vec![[0.0, 0.0], [1.0, 0.0]],
+            inner_contours: vec![],
+            skeleton: vec![],
+        };
+        assert!(set_object_geometry(&state, token, Some(geometry)).is_err());
+
+        assert!(set_object_symbol(&state, 99, None).is_err());
+
+        let objects = state.objects.lock();
+        let object = &objects[&token];
+        assert_eq!(object.bounds.unwrap().width, 0.1);
+        assert_eq!(object.symbol.as_ref().unwrap().group, "barcode");
+        assert_eq!(object.pose_3d.unwrap().z, 0.5);
+        assert!(object.geometry.is_none());
+    }
+
+    #[test]
+    fn test_set_object_controls_marks_changes() {
+        let state = AppState::new();
+        let token = add_object(&state, 1, 0.5, 0.5).unwrap();
+
+        assert!(set_object_controls(&state, token, vec![ControlValue::Float(1.5)]).is_err());
+        let controls = vec![ControlValue::Bool(true), ControlValue::Float(-0.5)];
+        set_object_controls(&state, token, controls.clone()).unwrap();
+        assert!(state.changed_controls.lock().remove(&token));
+
+        // Setting the same values again is not a change
+        set_object_controls(&state, token, controls).unwrap();
+        assert!(state.changed_controls.lock().is_empty());
+
+        let data = ObjectData {
+            mime: String::new(),
+            data: vec![1],
+        };
+        assert!(set_object_data(&state, token, vec![data.clone()]).is_err());
+        let data = ObjectData {
+            mime: "text/plain".to_string(),
+            ..data
+        };
+        set_object_data(&state, token, vec![data]).unwrap();
+        assert!(state.changed_controls.lock().contains(&token));
+    }
+
+    #[test]
+    fn test_associations() {
+        let state = AppState::new();
+        let outer = add_object(&state, 1, 0.5, 0.5).unwrap();
+        let inner = add_object(&state, 2, 0.5, 0.5).unwrap();
+
+        associate_container(&state, inner, outer, 1).unwrap();
+        assert!(associate_container(&state, outer, inner, 0).is_err());
+        assert!(associate_container(&state, outer, outer, 0).is_err());
+        assert!(associate_container(&state, inner, 99, 0).is_err());
+
+        assert!(link_objects(&state, outer, outer, true, 0, 0).is_err());
+        assert!(link_objects(&state, outer, 99, true, 0, 0).is_err());
+        link_objects(&state, outer, inner, true, 0, 1).unwrap();
+        link_objects(&state, outer, inner, false, 2, 3).unwrap();
+        {
+            let objects = state.objects.lock();
+            assert_eq!(objects[&inner].container.unwrap().container_id, outer);
+            assert_eq!(objects[&outer].links.len(), 1);
+            assert!(!objects[&outer].links[0].physical);
+        }
+
+        dissociate_container(&state, inner).unwrap();
+        unlink_objects(&state, outer, inner).unwrap();
+        let objects = state.objects.lock();
+        assert!(objects[&inner].container.is_none());
+        assert!(objects[&outer].links.is_empty());
+    }
+}
@@ -0,0 +1,70 @@
+use crate::engine::FrameInfo;
+use crate::playback::PlaybackStatus;
+use crate::recording::RecordingStatus;
+use crate::transport::clients::ClientInfo;
+use crate::transport::SinkError;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// How an object was changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ObjectChange {
+    Added,
+    Updated,
+    Removed,
+}
+
+/// Something that happened in the simulator
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub enum SimulatorEvent {
+    /// A frame was sent to all running sinks
+    FrameSent(FrameInfo),
+    /// A client connected to a server sink
+    ClientConnected(ClientInfo),
+    /// A client disconnected, with its final statistics
+    ClientDisconnected(ClientInfo),
+    /// An object was added, changed or removed
+    ObjectChanged {
+        session_id: u32,
+        change: ObjectChange,
+    },
+    /// Sending a frame to a single sink target failed
+    TransportError(SinkError),
+    /// A recording made progress or ended
+    RecordingProgress(RecordingStatus),
+    /// A playback started, changed or ended
+    PlaybackProgress(PlaybackStatus),
+}
+
+/// Broadcasts simulator events to any number of subscribers
+///
+/// Publishing never blocks. Subscribers that fall behind skip the oldest
+/// events and see `RecvError::Lagged`.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<SimulatorEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(256);
+        Self { tx }
+    }
+
+    /// Publish an event, dropped if nobody is subscribed
+    pub fn publish(&self, event: SimulatorEvent) {
+        let _ = self.tx.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<SimulatorEvent> {
+        self.tx.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
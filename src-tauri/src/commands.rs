@@ -1,285 +1,577 @@
-use crate::events;
-use crate::state::{AppState, ServerStatus, TuioObject};
-use crate::tuio::frame::generate_frame;
+use crate::actions;
+use crate::bus::SimulatorEvent;
+use crate::engine;
+use crate::motion::{ActiveMotion, Motion};
+use crate::playback::{self, PlaybackStatus};
+use crate::recording::{self, RecordingInfo, RecordingLimits, RecordingStatus};
+use crate::rest;
+use crate::scene::{self, LoadMode};
+use crate::script;
+use crate::state::{
+    AppState, ControlSendMode, ControlValue, FrameMode, ObjectBounds, ObjectData, ObjectGeometry,
+    ObjectSymbol, PointerType, Pose3D, ServerStatus,
+};
+use crate::tcp::TcpFraming;
+use crate::transport::bind::{BindAddress, StartError};
+use crate::transport::clients::ClientInfo;
+use crate::transport::SinkKind;
+use crate::tuio::encoder::ProtocolVersion;
+use crate::udp;
+use parking_lot::Mutex;
+use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
-use tauri::{AppHandle, State};
+use tauri::State;
 
 #[tauri::command]
 pub async fn start_server(
-    app: AppHandle,
     state: State<'_, AppState>,
     port: u16,
+    bind_address: Option<BindAddress>,
+) -> Result<(), StartError> {
+    engine::start_server(&state, port, bind_address).await
+}
+
+#[tauri::command]
+pub async fn stop_server(state: State<'_, AppState>) -> Result<(), String> {
+    engine::stop_server(&state).await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn add_object(
+    state: State<'_, AppState>,
+    component_id: u16,
+    x: f32,
+    y: f32,
+) -> Result<u32, String> {
+    actions::add_object(&state, component_id, x, y)
+}
+
+#[tauri::command]
+pub async fn add_pointer(
+    state: State<'_, AppState>,
+    pointer_type: PointerType,
+    x: f32,
+    y: f32,
+) -> Result<u32, String> {
+    actions::add_pointer(&state, pointer_type, x, y)
+}
+
+#[tauri::command]
+pub async fn add_blob(
+    state: State<'_, AppState>,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+) -> Result<u32, String> {
+    actions::add_blob(&state, x, y, width, height)
+}
+
+#[tauri::command]
+pub async fn update_object(
+    state: State<'_, AppState>,
+    session_id: u32,
+    x: f32,
+    y: f32,
+    angle: f32,
 ) -> Result<(), String> {
-    // Check if already running
-    {
-        let running = state.server_running.lock();
-        if *running {
-            return Err("Server is already running".to_string());
-        }
+    actions::update_object(&state, session_id, x, y, angle)
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn update_pointer(
+    state: State<'_, AppState>,
+    session_id: u32,
+    x: f32,
+    y: f32,
+    angle: f32,
+    shear: f32,
+    radius: f32,
+    pressure: f32,
+) -> Result<(), String> {
+    actions::update_pointer(&state, session_id, x, y, angle, shear, radius, pressure)
+}
+
+#[tauri::command]
+pub async fn set_object_bounds(
+    state: State<'_, AppState>,
+    session_id: u32,
+    bounds: Option<ObjectBounds>,
+) -> Result<(), String> {
+    actions::set_object_bounds(&state, session_id, bounds)
+}
+
+#[tauri::command]
+pub async fn set_object_symbol(
+    state: State<'_, AppState>,
+    session_id: u32,
+    symbol: Option<ObjectSymbol>,
+) -> Result<(), String> {
+    actions::set_object_symbol(&state, session_id, symbol)
+}
+
+#[tauri::command]
+pub async fn set_object_controls(
+    state: State<'_, AppState>,
+    session_id: u32,
+    controls: Vec<ControlValue>,
+) -> Result<(), String> {
+    actions::set_object_controls(&state, session_id, controls)
+}
+
+#[tauri::command]
+pub async fn set_object_data(
+    state: State<'_, AppState>,
+    session_id: u32,
+    data: Vec<ObjectData>,
+) -> Result<(), String> {
+    actions::set_object_data(&state, session_id, data)
+}
+
+#[tauri::command]
+pub async fn set_object_geometry(
+    state: State<'_, AppState>,
+    session_id: u32,
+    geometry: Option<ObjectGeometry>,
+) -> Result<(), String> {
+    actions::set_object_geometry(&state, session_id, geometry)
+}
+
+#[tauri::command]
+pub async fn set_object_pose_3d(
+    state: State<'_, AppState>,
+    session_id: u32,
+    pose: Option<Pose3D>,
+) -> Result<(), String> {
+    actions::set_object_pose_3d(&state, session_id, pose)
+}
+
+#[tauri::command]
+pub async fn associate_container(
+    state: State<'_, AppState>,
+    session_id: u32,
+    container_id: u32,
+    slot: u32,
+) -> Result<(), String> {
+    actions::associate_container(&state, session_id, container_id, slot)
+}
+
+#[tauri::command]
+pub async fn dissociate_container(
+    state: State<'_, AppState>,
+    session_id: u32,
+) -> Result<(), String> {
+    actions::dissociate_container(&state, session_id)
+}
+
+#[tauri::command]
+pub async fn link_objects(
+    state: State<'_, AppState>,
+    session_id: u32,
+    target_id: u32,
+    physical: bool,
+    out_port: u16,
+    in_port: u16,
+) -> Result<(), String> {
+    actions::link_objects(&state, session_id, target_id, physical, out_port, in_port)
+}
+
+#[tauri::command]
+pub async fn unlink_objects(
+    state: State<'_, AppState>,
+    session_id: u32,
+    target_id: u32,
+) -> Result<(), String> {
+    actions::unlink_objects(&state, session_id, target_id)
+}
+
+#[tauri::command]
+pub async fn remove_object(state: State<'_, AppState>, session_id: u32) -> Result<(), String> {
+    actions::remove_object(&state, session_id)
+}
+
+#[tauri::command]
+pub async fn set_frame_rate(state: State<'_, AppState>, fps: u32) -> Result<(), String> {
+    actions::set_frame_rate(&state, fps)
+}
+
+#[tauri::command]
+pub async fn get_server_status(state: State<'_, AppState>) -> Result<ServerStatus, String> {
+    Ok(actions::server_status(&state))
+}
+
+#[tauri::command]
+pub async fn list_clients(state: State<'_, AppState>) -> Result<Vec<ClientInfo>, String> {
+    Ok(state.sinks.clients().list())
+}
+
+#[tauri::command]
+pub async fn disconnect_client(
+    state: State<'_, AppState>,
+    client_id: String,
+) -> Result<(), String> {
+    if state.sinks.clients().disconnect(&client_id) {
+        Ok(())
+    } else {
+        Err(format!("Client {} not found", client_id))
     }
+}
 
-    // Update config
-    {
-        let mut config = state.config.lock();
-        config.port = port;
+#[tauri::command]
+pub async fn set_canvas_dimensions(
+    state: State<'_, AppState>,
+    width: u16,
+    height: u16,
+) -> Result<(), String> {
+    actions::set_canvas_dimensions(&state, width, height)
+}
+
+/// Restart a sink with the current config if it is enabled and the server is running
+async fn restart_sink(state: &AppState, kind: SinkKind) -> Result<(), String> {
+    let running = *state.server_running.lock();
+    let config = state.config.lock().clone();
+    if !running || !config.enabled_sinks.contains(&kind) {
+        return Ok(());
     }
 
-    // Start WebSocket server
-    state
-        .websocket_server
-        .start(port)
+    let sink = state.sinks.get(kind).map_err(|e| e.to_string())?;
+    sink.start(&config)
         .await
-        .map_err(|e| format!("Failed to start WebSocket server: {}", e))?;
-
-    // Start frame generation task
-    let state_clone = state.inner().clone();
-    let app_clone = app.clone();
-    let task = tokio::spawn(async move {
-        frame_generation_loop(state_clone, app_clone).await;
-    });
+        .map_err(|e| StartError::from_sink(kind, e).to_string())
+}
 
-    // Store task handle
+#[tauri::command]
+pub async fn set_sink_enabled(
+    state: State<'_, AppState>,
+    kind: SinkKind,
+    enabled: bool,
+) -> Result<(), String> {
     {
-        let mut frame_task = state.frame_task.lock();
-        *frame_task = Some(task);
+        let mut config = state.config.lock();
+        config.enabled_sinks.retain(|k| *k != kind);
+        if enabled {
+            config.enabled_sinks.push(kind);
+        }
     }
 
-    // Set server running flag
-    {
-        let mut running = state.server_running.lock();
-        *running = true;
+    if enabled {
+        // Start the sink right away if frames are already being sent
+        if let Err(e) = restart_sink(state.inner(), kind).await {
+            state.config.lock().enabled_sinks.retain(|k| *k != kind);
+            return Err(e);
+        }
+    } else {
+        let sink = state.sinks.get(kind).map_err(|e| e.to_string())?;
+        sink.stop().await;
     }
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn stop_server(state: State<'_, AppState>) -> Result<(), String> {
-    // Set server running flag (this will stop the frame generation loop)
+pub async fn set_udp_targets(
+    state: State<'_, AppState>,
+    targets: Vec<String>,
+) -> Result<(), String> {
+    // Resolve up front so invalid targets are rejected before they are stored
+    udp::resolve_targets(&targets)
+        .await
+        .map_err(|e| format!("Invalid UDP target: {}", e))?;
+
     {
-        let mut running = state.server_running.lock();
-        *running = false;
+        let mut config = state.config.lock();
+        config.udp_targets = targets;
     }
 
-    // Abort the frame generation task
+    restart_sink(state.inner(), SinkKind::Udp).await
+}
+
+#[tauri::command]
+pub async fn set_tcp_options(
+    state: State<'_, AppState>,
+    port: u16,
+    framing: TcpFraming,
+) -> Result<(), String> {
     {
-        let mut frame_task = state.frame_task.lock();
-        if let Some(task) = frame_task.take() {
-            task.abort();
-        }
+        let mut config = state.config.lock();
+        config.tcp_port = port;
+        config.tcp_framing = framing;
     }
 
-    // Stop the WebSocket server
-    state.websocket_server.stop().await;
+    restart_sink(state.inner(), SinkKind::Tcp).await
+}
+
+#[tauri::command]
+pub async fn set_file_sink_path(
+    state: State<'_, AppState>,
+    path: Option<String>,
+) -> Result<(), String> {
+    {
+        let mut config = state.config.lock();
+        config.file_sink_path = path;
+    }
+
+    restart_sink(state.inner(), SinkKind::File).await
+}
+
+#[tauri::command]
+pub async fn set_protocol_version(
+    state: State<'_, AppState>,
+    version: ProtocolVersion,
+) -> Result<(), String> {
+    let mut config = state.config.lock();
+    config.protocol_version = version;
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn add_object(
+pub async fn set_3d_output(
     state: State<'_, AppState>,
-    component_id: u16,
-    x: f32,
-    y: f32,
-) -> Result<u32, String> {
-    // Validate component_id range (1-24)
-    if !(1..=24).contains(&component_id) {
-        return Err("Component ID must be in range [1, 24]".to_string());
-    }
+    enabled: bool,
+    depth: u16,
+) -> Result<(), String> {
+    let mut config = state.config.lock();
+    config.enable_3d = enabled;
+    config.depth = depth;
 
-    // Validate coordinates
-    if !(0.0..=1.0).contains(&x) || !(0.0..=1.0).contains(&y) {
-        return Err("Coordinates must be in range [0.0, 1.0]".to_string());
-    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_control_send_mode(
+    state: State<'_, AppState>,
+    mode: ControlSendMode,
+) -> Result<(), String> {
+    let mut config = state.config.lock();
+    config.control_send_mode = mode;
+
+    Ok(())
+}
 
-    // Check if component_id is already in use
+#[tauri::command]
+pub async fn set_control_token(
+    state: State<'_, AppState>,
+    token: Option<String>,
+) -> Result<(), String> {
+    let mut config = state.config.lock();
+    config.control_token = token.filter(|token| !token.is_empty());
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_http_api(
+    state: State<'_, AppState>,
+    enabled: bool,
+    port: u16,
+) -> Result<(), String> {
     {
-        let objects = state.objects.lock();
-        if objects.values().any(|obj| obj.component_id == component_id) {
-            return Err(format!("Component ID {} is already in use", component_id));
-        }
+        let mut config = state.config.lock();
+        config.http_api_enabled = enabled;
+        config.http_api_port = port;
     }
 
-    let session_id = state.allocate_session_id();
-    let timestamp = chrono::Utc::now().timestamp_millis();
-
-    let object = TuioObject {
-        session_id,
-        type_id: component_id, // Use component_id as type_id for color mapping
-        user_id: 0,
-        component_id,
-        x,
-        y,
-        angle: 0.0,
-        x_vel: 0.0,
-        y_vel: 0.0,
-        angle_vel: 0.0,
-        last_x: x,
-        last_y: y,
-        last_angle: 0.0,
-        last_update: timestamp,
-    };
+    rest::apply_config(&state).await.map_err(|e| e.to_string())
+}
 
-    let mut objects = state.objects.lock();
-    objects.insert(session_id, object);
+#[tauri::command]
+pub async fn pause_frames(state: State<'_, AppState>) -> Result<(), String> {
+    actions::pause_frames(&state);
 
-    Ok(session_id)
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn update_object(
+pub async fn resume_frames(state: State<'_, AppState>) -> Result<(), String> {
+    actions::resume_frames(&state);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn step_frame(state: State<'_, AppState>, count: Option<u32>) -> Result<(), String> {
+    actions::step_frame(&state, count.unwrap_or(1))
+}
+
+#[tauri::command]
+pub async fn resend_frame(state: State<'_, AppState>) -> Result<(), String> {
+    engine::resend_frame(&state).await
+}
+
+#[tauri::command]
+pub async fn set_frame_mode(
     state: State<'_, AppState>,
-    session_id: u32,
-    x: f32,
-    y: f32,
-    angle: f32,
+    mode: FrameMode,
+    min_interval_ms: u64,
+    keep_alive_ms: u64,
+    skip_unmoved_tokens: bool,
 ) -> Result<(), String> {
-    // Validate coordinates
-    if !(0.0..=1.0).contains(&x) || !(0.0..=1.0).contains(&y) {
-        return Err("Coordinates must be in range [0.0, 1.0]".to_string());
-    }
+    actions::set_frame_mode(
+        &state,
+        mode,
+        min_interval_ms,
+        keep_alive_ms,
+        skip_unmoved_tokens,
+    )
+}
 
-    let timestamp = chrono::Utc::now().timestamp_millis();
+#[tauri::command]
+pub async fn save_scene(state: State<'_, AppState>, path: String) -> Result<(), String> {
+    scene::save(&state, Path::new(&path)).map_err(|e| format!("{:#}", e))
+}
 
-    let mut objects = state.objects.lock();
-    if let Some(object) = objects.get_mut(&session_id) {
-        object.x = x;
-        object.y = y;
-        object.angle = angle;
-        object.last_update = timestamp;
-        Ok(())
-    } else {
-        Err(format!("Object with session_id {} not found", session_id))
-    }
+#[tauri::command]
+pub async fn load_scene(
+    state: State<'_, AppState>,
+    path: String,
+    mode: LoadMode,
+) -> Result<Vec<u32>, String> {
+    scene::load(&state, Path::new(&path), mode).map_err(|e| format!("{:#}", e))
 }
 
 #[tauri::command]
-pub async fn remove_object(state: State<'_, AppState>, session_id: u32) -> Result<(), String> {
-    let mut objects = state.objects.lock();
-    if objects.remove(&session_id).is_some() {
-        Ok(())
-    } else {
-        Err(format!("Object with session_id {} not found", session_id))
-    }
+pub async fn start_recording(
+    state: State<'_, AppState>,
+    path: String,
+    max_bytes: Option<u64>,
+    max_duration_ms: Option<u64>,
+) -> Result<(), String> {
+    let limits = RecordingLimits {
+        max_bytes,
+        max_duration_ms,
+    };
+    recording::start(&state, Path::new(&path), limits).map_err(|e| format!("{:#}", e))
 }
 
 #[tauri::command]
-pub async fn set_frame_rate(state: State<'_, AppState>, fps: u32) -> Result<(), String> {
-    if !(1..=120).contains(&fps) {
-        return Err("FPS must be in range [1, 120]".to_string());
+pub async fn stop_recording(state: State<'_, AppState>) -> Result<Option<RecordingStatus>, String> {
+    let status = state.recorder.stop().await;
+    if let Some(status) = &status {
+        state
+            .events
+            .publish(SimulatorEvent::RecordingProgress(status.clone()));
     }
+    Ok(status)
+}
 
-    let mut config = state.config.lock();
-    config.fps = fps;
+#[tauri::command]
+pub async fn get_recording_status(
+    state: State<'_, AppState>,
+) -> Result<Option<RecordingStatus>, String> {
+    Ok(state.recorder.status())
+}
 
-    // TODO: Update the frame generation interval
-    // This will be implemented in the tuio module
+#[tauri::command]
+pub async fn get_recording_info(path: String) -> Result<RecordingInfo, String> {
+    recording::info(Path::new(&path)).map_err(|e| format!("{:#}", e))
+}
 
-    Ok(())
+#[tauri::command]
+pub async fn start_playback(
+    state: State<'_, AppState>,
+    path: String,
+    speed: Option<f64>,
+    looping: Option<bool>,
+) -> Result<PlaybackStatus, String> {
+    playback::start(
+        &state,
+        Path::new(&path),
+        speed.unwrap_or(1.0),
+        looping.unwrap_or(false),
+    )
+    .map_err(|e| format!("{:#}", e))
 }
 
 #[tauri::command]
-pub async fn get_server_status(state: State<'_, AppState>) -> Result<ServerStatus, String> {
-    let running = *state.server_running.lock();
-    let config = state.config.lock();
-    let connected_clients = state.get_connected_clients();
-    let frame_count = *state.frame_counter.lock();
-    let object_count = state.objects.lock().len();
-
-    Ok(ServerStatus {
-        running,
-        port: config.port,
-        fps: config.fps,
-        connected_clients,
-        frame_count,
-        object_count,
-    })
+pub async fn stop_playback(state: State<'_, AppState>) -> Result<Option<PlaybackStatus>, String> {
+    Ok(playback::stop(&state))
 }
 
-/// Frame generation loop that runs continuously while the server is running
-async fn frame_generation_loop(state: AppState, app: AppHandle) {
-    // Get initial FPS for interval calculation
-    let mut fps = {
-        let config = state.config.lock();
-        config.fps
-    };
-    let mut interval = tokio::time::interval(Duration::from_millis((1000 / fps.max(1)) as u64));
-    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+#[tauri::command]
+pub async fn pause_playback(state: State<'_, AppState>) -> Result<PlaybackStatus, String> {
+    state.player.pause().map_err(|e| e.to_string())
+}
 
-    loop {
-        interval.tick().await;
+#[tauri::command]
+pub async fn resume_playback(state: State<'_, AppState>) -> Result<PlaybackStatus, String> {
+    state.player.resume().map_err(|e| e.to_string())
+}
 
-        // Check if server is still running
-        let running = *state.server_running.lock();
-        if !running {
-            break;
-        }
+#[tauri::command]
+pub async fn seek_playback(
+    state: State<'_, AppState>,
+    position_ms: u64,
+) -> Result<PlaybackStatus, String> {
+    state
+        .player
+        .seek(Duration::from_millis(position_ms))
+        .map_err(|e| e.to_string())
+}
 
-        // Skip frame generation if no clients are connected (optimization)
-        let connected_clients = state.get_connected_clients();
-        if connected_clients == 0 {
-            continue;
-        }
+#[tauri::command]
+pub async fn set_playback_speed(
+    state: State<'_, AppState>,
+    speed: f64,
+) -> Result<PlaybackStatus, String> {
+    state.player.set_speed(speed).map_err(|e| e.to_string())
+}
 
-        // Check if FPS changed and update interval if needed
-        let current_fps = {
-            let config = state.config.lock();
-            config.fps
-        };
-        if current_fps != fps {
-            fps = current_fps;
-            interval = tokio::time::interval(Duration::from_millis((1000 / fps.max(1)) as u64));
-            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
-        }
+#[tauri::command]
+pub async fn set_playback_looping(
+    state: State<'_, AppState>,
+    looping: bool,
+) -> Result<PlaybackStatus, String> {
+    state.player.set_looping(looping).map_err(|e| e.to_string())
+}
 
-        // Generate frame
-        match generate_frame(&state) {
-            Ok(frame_data) => {
-                // Batch lock acquisitions for debug info
-                let (frame_id, object_count) = {
-                    let frame_id = *state.frame_counter.lock();
-                    let object_count = state.objects.lock().len();
-                    (frame_id, object_count)
-                };
-
-                let message_size = frame_data.len();
-                let timestamp = chrono::Utc::now().timestamp_millis();
-
-                // Emit OSC message debug event
-                events::emit_osc_message(
-                    &app,
-                    frame_id,
-                    timestamp,
-                    object_count,
-                    message_size,
-                    connected_clients,
-                );
-
-                // Broadcast to all connected clients (non-blocking)
-                if let Err(e) = state.websocket_server.broadcast(frame_data).await {
-                    eprintln!("Error broadcasting frame: {}", e);
-                }
-            }
-            Err(e) => {
-                eprintln!("Error generating frame: {}", e);
-            }
-        }
-    }
+#[tauri::command]
+pub async fn get_playback_status(
+    state: State<'_, AppState>,
+) -> Result<Option<PlaybackStatus>, String> {
+    Ok(state.player.status())
 }
 
 #[tauri::command]
-pub async fn set_canvas_dimensions(
+pub async fn attach_motion(
     state: State<'_, AppState>,
-    width: u16,
-    height: u16,
+    session_id: u32,
+    motion: Motion,
 ) -> Result<(), String> {
-    if width == 0 || height == 0 {
-        return Err("Width and height must be greater than 0".to_string());
-    }
+    actions::attach_motion(&state, session_id, motion)
+}
 
-    let mut config = state.config.lock();
-    config.width = width;
-    config.height = height;
+#[tauri::command]
+pub async fn detach_motion(state: State<'_, AppState>, session_id: u32) -> Result<(), String> {
+    actions::detach_motion(&state, session_id)
+}
 
+#[tauri::command]
+pub async fn list_motions(state: State<'_, AppState>) -> Result<Vec<ActiveMotion>, String> {
+    Ok(actions::list_motions(&state))
+}
+
+/// Run a script file, returning what it printed
+#[tauri::command]
+pub async fn run_script(state: State<'_, AppState>, path: String) -> Result<Vec<String>, String> {
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let lines = output.clone();
+    script::run_file(&state, Path::new(&path), move |line| {
+        lines.lock().push(line.to_string())
+    })
+    .await
+    .map_err(|e| format!("{:#}", e))?;
+
+    let output = std::mem::take(&mut *output.lock());
+    Ok(output)
+}
+
+#[tauri::command]
+pub async fn stop_scripts(state: State<'_, AppState>) -> Result<(), String> {
+    state.scripts.stop_all();
     Ok(())
 }
@@ -0,0 +1,486 @@
+use crate::actions;
+use crate::bus::SimulatorEvent;
+use crate::state::{AppState, PointerType, TuioObject};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tokio::sync::broadcast::error::RecvError;
+
+/// The message is not valid JSON
+const PARSE_ERROR: i64 = -32700;
+/// The message is not a JSON-RPC 2.0 request
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The operation was rejected, e.g. for an unknown session ID
+const OPERATION_FAILED: i64 = -32000;
+/// The method changes the scene and the client has not authenticated
+const UNAUTHORIZED: i64 = -32001;
+
+/// Methods that change the simulator and require the control token
+const WRITE_METHODS: &[&str] = &[
+    "add_object",
+    "add_pointer",
+    "add_blob",
+    "update_object",
+    "update_pointer",
+    "remove_object",
+    "set_frame_rate",
+    "pause_frames",
+    "resume_frames",
+    "step_frame",
+];
+
+/// Control state of a single client connection
+#[derive(Debug, Clone, Default)]
+pub struct ControlSession {
+    /// Token the client authenticated with
+    token: Option<String>,
+}
+
+impl ControlSession {
+    /// Whether the client may change the simulator
+    ///
+    /// Without a configured token every client has write access. The token
+    /// is checked on every call, so changing it revokes earlier sessions.
+    fn can_write(&self, state: &AppState) -> bool {
+        match &state.config.lock().control_token {
+            Some(token) => self.token.as_ref() == Some(token),
+            None => true,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// Missing for notifications, which get no response
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Errors of the shared actions are reported as failed operations
+impl From<String> for RpcError {
+    fn from(message: String) -> Self {
+        Self::new(OPERATION_FAILED, message)
+    }
+}
+
+#[derive(Deserialize)]
+struct AuthenticateParams {
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct AddObjectParams {
+    component_id: u16,
+    x: f32,
+    y: f32,
+}
+
+#[derive(Deserialize)]
+struct AddPointerParams {
+    pointer_type: PointerType,
+    x: f32,
+    y: f32,
+}
+
+#[derive(Deserialize)]
+struct AddBlobParams {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+#[derive(Deserialize)]
+struct UpdateObjectParams {
+    session_id: u32,
+    x: f32,
+    y: f32,
+    angle: f32,
+}
+
+#[derive(Deserialize)]
+struct UpdatePointerParams {
+    session_id: u32,
+    x: f32,
+    y: f32,
+    angle: f32,
+    shear: f32,
+    radius: f32,
+    pressure: f32,
+}
+
+#[derive(Deserialize)]
+struct SessionParams {
+    session_id: u32,
+}
+
+#[derive(Deserialize)]
+struct FrameRateParams {
+    fps: u32,
+}
+
+#[derive(Deserialize)]
+struct StepParams {
+    #[serde(default)]
+    count: Option<u32>,
+}
+
+/// Parse named parameters, treating missing parameters as an empty object
+fn parse_params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+/// Call a method with named parameters
+fn call(
+    state: &AppState,
+    session: &mut ControlSession,
+    method: &str,
+    params: Value,
+) -> Result<Value, RpcError> {
+    match method {
+        "authenticate" => {
+            let params: AuthenticateParams = parse_params(params)?;
+            let expected = state.config.lock().control_token.clone();
+            if expected.is_some_and(|token| token != params.token) {
+                return Err(RpcError::new(UNAUTHORIZED, "Invalid control token"));
+            }
+            session.token = Some(params.token);
+            Ok(json!(true))
+        }
+        "get_objects" => {
+            let mut objects: Vec<TuioObject> = state.objects.lock().values().cloned().collect();
+            objects.sort_by_key(|object| object.session_id);
+            Ok(json!(objects))
+        }
+        _ if !WRITE_METHODS.contains(&method) => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method {}", method),
+        )),
+        _ if !session.can_write(state) => Err(RpcError::new(
+            UNAUTHORIZED,
+            "Authenticate with the control token first",
+        )),
+        "add_object" => {
+            let params: AddObjectParams = parse_params(params)?;
+            let session_id = actions::add_object(state, params.component_id, params.x, params.y)?;
+            Ok(json!(session_id))
+        }
+        "add_pointer" => {
+            let params: AddPointerParams = parse_params(params)?;
+            let session_id = actions::add_pointer(state, params.pointer_type, params.x, params.y)?;
+            Ok(json!(session_id))
+        }
+        "add_blob" => {
+            let params: AddBlobParams = parse_params(params)?;
+            let session_id =
+                actions::add_blob(state, params.x, params.y, params.width, params.height)?;
+            Ok(json!(session_id))
+        }
+        "update_object" => {
+            let params: UpdateObjectParams = parse_params(params)?;
+            actions::update_object(state, params.session_id, params.x, params.y, params.angle)?;
+            Ok(Value::Null)
+        }
+        "update_pointer" => {
+            let params: UpdatePointerParams = parse_params(params)?;
+            actions::update_pointer(
+                state,
+                params.session_id,
+                params.x,
+                params.y,
+                params.angle,
+                params.shear,
+                params.radius,
+                params.pressure,
+            )?;
+            Ok(Value::Null)
+        }
+        "remove_object" => {
+            let params: SessionParams = parse_params(params)?;
+            actions::remove_object(state, params.session_id)?;
+            Ok(Value::Null)
+        }
+        "set_frame_rate" => {
+            let params: FrameRateParams = parse_params(params)?;
+            actions::set_frame_rate(state, params.fps)?;
+            Ok(Value::Null)
+        }
+        "pause_frames" => {
+            actions::pause_frames(state);
+            Ok(Value::Null)
+        }
+        "resume_frames" => {
+            actions::resume_frames(state);
+            Ok(Value::Null)
+        }
+        "step_frame" => {
+            let params: StepParams = parse_params(params)?;
+            actions::step_frame(state, params.count.unwrap_or(1))?;
+            Ok(Value::Null)
+        }
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method {}", method),
+        )),
+    }
+}
+
+fn error_response(id: Value, error: RpcError) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+    .to_string()
+}
+
+/// Handle a JSON-RPC 2.0 request sent by a client as a text message
+///
+/// Methods take named parameters, matching the arguments of the Tauri
+/// commands: `add_object`, `add_pointer`, `add_blob`, `update_object`,
+/// `update_pointer`, `remove_object`, `set_frame_rate`, `pause_frames`,
+/// `resume_frames` and `step_frame` change the simulator, `get_objects`
+/// and `authenticate` (with `token`) are always available.
+///
+/// Returns the response to send back, `None` for notifications.
+pub fn handle_message(
+    state: &AppState,
+    session: &mut ControlSession,
+    text: &str,
+) -> Option<String> {
+    let value: Value = match serde_json::from_str(text) {
+        Ok(value) => value,
+        Err(e) => {
+            return Some(error_response(
+                Value::Null,
+                RpcError::new(PARSE_ERROR, e.to_string()),
+            ))
+        }
+    };
+
+    let request: Request = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(e) => {
+            return Some(error_response(
+                Value::Null,
+                RpcError::new(INVALID_REQUEST, e.to_string()),
+            ))
+        }
+    };
+
+    if request.jsonrpc != "2.0" {
+        return Some(error_response(
+            request.id.unwrap_or(Value::Null),
+            RpcError::new(INVALID_REQUEST, "Only JSON-RPC 2.0 is supported"),
+        ));
+    }
+
+    let result = call(state, session, &request.method, request.params);
+    let id = request.id?;
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string(),
+        Err(error) => error_response(id, error),
+    })
+}
+
+/// Answer the control requests of all connected clients
+///
+/// Runs for as long as the client registry of the state exists.
+pub async fn serve(state: AppState) {
+    let clients = state.sinks.clients().clone();
+    let mut messages = clients.receive_messages();
+    let mut events = state.events.subscribe();
+    let mut sessions: HashMap<String, ControlSession> = HashMap::new();
+
+    loop {
+        tokio::select! {
+            message = messages.recv() => {
+                let Some(message) = message else {
+                    break;
+                };
+
+                let session = sessions.entry(message.client_id).or_default();
+                let reply = handle_message(&state, session, &message.text);
+                let _ = message.reply_tx.send(reply);
+            }
+            event = events.recv() => match event {
+                Ok(SimulatorEvent::ClientDisconnected(client)) => {
+                    sessions.remove(&client.client_id);
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::Config;
+    use crate::transport::SinkKind;
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message;
+
+    fn request(state: &AppState, session: &mut ControlSession, text: &str) -> Value {
+        let reply = handle_message(state, session, text).expect("Expected a response");
+        serde_json::from_str(&reply).unwrap()
+    }
+
+    #[test]
+    fn test_add_and_update_object() {
+        let state = AppState::new();
+        let mut session = ControlSession::default();
+
+        let response = request(
+            &state,
+            &mut session,
+            r#"{"jsonrpc":"2.0","id":1,"method":"add_object","params":{"component_id":3,"x":0.5,"y":0.5}}"#,
+        );
+        assert_eq!(response["id"], 1);
+        let session_id = response["result"].as_u64().unwrap() as u32;
+
+        let update = format!(
+            r#"{{"jsonrpc":"2.0","id":2,"method":"update_object","params":{{"session_id":{},"x":0.25,"y":0.75,"angle":1.0}}}}"#,
+            session_id
+        );
+        let response = request(&state, &mut session, &update);
+        assert_eq!(response["result"], Value::Null);
+        assert_eq!(state.objects.lock()[&session_id].x, 0.25);
+
+        // Errors of the action are passed on
+        let response = request(
+            &state,
+            &mut session,
+            r#"{"jsonrpc":"2.0","id":3,"method":"remove_object","params":{"session_id":99}}"#,
+        );
+        assert_eq!(response["error"]["code"], OPERATION_FAILED);
+        assert_eq!(
+            response["error"]["message"],
+            "Object with session_id 99 not found"
+        );
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        let state = AppState::new();
+        let mut session = ControlSession::default();
+
+        let response = request(&state, &mut session, "not json");
+        assert_eq!(response["error"]["code"], PARSE_ERROR);
+        assert_eq!(response["id"], Value::Null);
+
+        let response = request(
+            &state,
+            &mut session,
+            r#"{"jsonrpc":"2.0","id":1,"method":"fly"}"#,
+        );
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+
+        let response = request(
+            &state,
+            &mut session,
+            r#"{"jsonrpc":"2.0","id":1,"method":"set_frame_rate","params":{}}"#,
+        );
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+
+        // Notifications are executed without a response
+        let notification = r#"{"jsonrpc":"2.0","method":"set_frame_rate","params":{"fps":30}}"#;
+        assert!(handle_message(&state, &mut session, notification).is_none());
+        assert_eq!(state.config.lock().fps, 30);
+    }
+
+    #[test]
+    fn test_control_token() {
+        let state = AppState::new();
+        state.config.lock().control_token = Some("secret".to_string());
+        let mut session = ControlSession::default();
+
+        let pause = r#"{"jsonrpc":"2.0","id":1,"method":"pause_frames"}"#;
+        let response = request(&state, &mut session, pause);
+        assert_eq!(response["error"]["code"], UNAUTHORIZED);
+        assert!(!state.frame_control.lock().paused);
+
+        // Reading does not require the token
+        let response = request(
+            &state,
+            &mut session,
+            r#"{"jsonrpc":"2.0","id":2,"method":"get_objects"}"#,
+        );
+        assert_eq!(response["result"], json!([]));
+
+        let response = request(
+            &state,
+            &mut session,
+            r#"{"jsonrpc":"2.0","id":3,"method":"authenticate","params":{"token":"wrong"}}"#,
+        );
+        assert_eq!(response["error"]["code"], UNAUTHORIZED);
+
+        request(
+            &state,
+            &mut session,
+            r#"{"jsonrpc":"2.0","id":4,"method":"authenticate","params":{"token":"secret"}}"#,
+        );
+        let response = request(&state, &mut session, pause);
+        assert_eq!(response["result"], Value::Null);
+        assert!(state.frame_control.lock().paused);
+
+        // Changing the token revokes the session
+        state.config.lock().control_token = Some("other".to_string());
+        let response = request(&state, &mut session, pause);
+        assert_eq!(response["error"]["code"], UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_requests_over_websocket() {
+        let state = AppState::new();
+        let config = Config {
+            port: 0,
+            enabled_sinks: vec![SinkKind::WebSocket],
+            ..Config::default()
+        };
+        state.sinks.start(&config).await.unwrap();
+        let addr = state.sinks.statuses(&config.enabled_sinks)[0].addresses[0];
+
+        tokio::spawn(serve(state.clone()));
+        tokio::task::yield_now().await;
+
+        let (mut client, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr))
+            .await
+            .unwrap();
+        let add = r#"{"jsonrpc":"2.0","id":"a","method":"add_pointer","params":{"pointer_type":"finger","x":0.1,"y":0.2}}"#;
+        client.send(Message::Text(add.to_string())).await.unwrap();
+
+        let reply = match client.next().await {
+            Some(Ok(Message::Text(reply))) => reply,
+            other => panic!("Expected a text reply, got {:?}", other),
+        };
+        let response: Value = serde_json::from_str(&reply).unwrap();
+        assert_eq!(response["id"], "a");
+        assert_eq!(state.objects.lock().len(), 1);
+
+        state.sinks.stop().await;
+    }
+}
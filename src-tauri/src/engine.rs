@@ -0,0 +1,528 @@
+use crate::bus::{ObjectChange, SimulatorEvent};
+use crate::motion::advance_motions;
+use crate::state::{AppState, FrameMode};
+use crate::transport::bind::{BindAddress, StartError};
+use crate::tuio::frame::{generate_frame, generate_full_frame};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// A frame that was generated and handed to the sinks
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub struct FrameInfo {
+    pub frame_id: u32,
+    pub timestamp: i64,
+    pub object_count: usize,
+    pub message_size: usize,
+    pub connected_clients: usize,
+}
+
+/// Start the transport sinks and the frame loop
+///
+/// Sent frames and transport errors are published on `AppState::events`.
+pub async fn start_server(
+    state: &AppState,
+    port: u16,
+    bind_address: Option<BindAddress>,
+) -> Result<(), StartError> {
+    // Check if already running
+    {
+        let running = state.server_running.lock();
+        if *running {
+            return Err(StartError::AlreadyRunning);
+        }
+    }
+
+    // Update config, keeping the bind address if none is given
+    let config = {
+        let mut config = state.config.lock();
+        config.port = port;
+        if let Some(bind_address) = bind_address {
+            config.bind_address = bind_address;
+        }
+        config.clone()
+    };
+
+    // Start all enabled transport sinks
+    state.sinks.start(&config).await?;
+
+    // Start frame generation task
+    let state_clone = state.clone();
+    let task = tokio::spawn(async move {
+        frame_generation_loop(state_clone).await;
+    });
+
+    // Store task handle
+    {
+        let mut frame_task = state.frame_task.lock();
+        *frame_task = Some(task);
+    }
+
+    // Set server running flag
+    {
+        let mut running = state.server_running.lock();
+        *running = true;
+    }
+
+    Ok(())
+}
+
+/// Stop the frame loop and all transport sinks
+pub async fn stop_server(state: &AppState) {
+    // Set server running flag (this will stop the frame generation loop)
+    {
+        let mut running = state.server_running.lock();
+        *running = false;
+    }
+
+    // Abort the frame generation task
+    {
+        let mut frame_task = state.frame_task.lock();
+        if let Some(task) = frame_task.take() {
+            task.abort();
+        }
+    }
+
+    // Stop all transport sinks
+    state.sinks.stop().await;
+}
+
+/// Send the most recent frame again to all running sinks
+///
+/// Works while paused, so clients that connected in the meantime receive
+/// the current state.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub async fn resend_frame(state: &AppState) -> Result<(), String> {
+    if !*state.server_running.lock() {
+        return Err("Server is not running".to_string());
+    }
+
+    let frame_data = state
+        .last_bundle
+        .lock()
+        .clone()
+        .ok_or_else(|| "No frame has been generated yet".to_string())?;
+
+    for error in state.sinks.send(&frame_data).await {
+        state.events.publish(SimulatorEvent::TransportError(error));
+    }
+
+    Ok(())
+}
+
+/// Time between two frames at the given rate
+pub fn frame_period(fps: u32) -> Duration {
+    Duration::from_secs_f64(1.0 / fps.max(1) as f64)
+}
+
+/// Move objects along their attached motions, returning whether any moved
+///
+/// Called once per frame period in both frame modes, whether or not a
+/// frame is sent, so objects keep moving while nobody listens or the loop
+/// is paused rather than jumping ahead once frames resume. Moves are
+/// published without waking the loop, which decides itself whether they
+/// need a frame. Objects are left alone while a recording is played back.
+fn advance_motions_tick(state: &AppState) -> bool {
+    if state.player.is_playing() {
+        return false;
+    }
+
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    let moved = {
+        let mut objects = state.objects.lock();
+        advance_motions(&mut state.motions.lock(), &mut objects, timestamp)
+    };
+    for &session_id in &moved {
+        state.events.publish(SimulatorEvent::ObjectChanged {
+            session_id,
+            change: ObjectChange::Updated,
+        });
+    }
+    !moved.is_empty()
+}
+
+async fn frame_generation_loop(state: AppState) {
+    let mut frame_rate = state.frame_rate.subscribe();
+    let mut period = frame_period(*frame_rate.borrow_and_update());
+    state.frame_stats.lock().reset();
+
+    // The first frame is sent right away
+    let mut deadline = Instant::now();
+    let mut last_frame: Option<Instant> = None;
+    // Earliest time on-change mode tries again after a skipped frame
+    let mut retry_at: Option<Instant> = None;
+    // Next time on-change mode moves objects with an attached motion
+    let mut motion_at = Instant::now();
+
+    loop {
+        let (frame_mode, min_interval, keep_alive) = {
+            let config = state.config.lock();
+            (
+                config.frame_mode,
+                Duration::from_millis(config.min_frame_interval_ms),
+                Duration::from_millis(config.keep_alive_ms),
+            )
+        };
+
+        let keep_alive_due = match frame_mode {
+            FrameMode::Continuous => {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(deadline) => {}
+                    changed = frame_rate.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+
+                        // Reschedule the pending frame relative to the previous one
+                        let previous = deadline.checked_sub(period).unwrap_or(deadline);
+                        period = frame_period(*frame_rate.borrow_and_update());
+                        deadline = previous + period;
+                        state.frame_stats.lock().reset();
+                        continue;
+                    }
+                }
+
+                // Advance from the deadline rather than from now, so the schedule
+                // does not drift. Frames missed by more than a period are skipped.
+                deadline += period;
+                let now = Instant::now();
+                if deadline < now {
+                    deadline = now + period;
+                }
+                advance_motions_tick(&state);
+                false
+            }
+            FrameMode::OnChange => {
+                // Motions move objects on the frame period of continuous mode
+                period = frame_period(*frame_rate.borrow());
+                let motions_running = !state.motions.lock().is_empty();
+
+                // Wait for a change, sending a keep-alive if none comes in time
+                let keep_alive_at = last_frame.map_or_else(Instant::now, |last| last + keep_alive);
+                let keep_alive_at =
+                    retry_at.map_or(keep_alive_at, |retry| retry.max(keep_alive_at));
+                // A pending change wins over a due keep-alive, so it is not
+                // left behind to trigger a second frame
+                let changed = tokio::select! {
+                    biased;
+                    _ = state.frame_trigger.notified() => true,
+                    _ = tokio::time::sleep_until(motion_at), if motions_running => {
+                        motion_at += period;
+                        let now = Instant::now();
+                        if motion_at < now {
+                            motion_at = now + period;
+                        }
+
+                        // Only objects that actually moved need a frame
+                        if !advance_motions_tick(&state) {
+                            continue;
+                        }
+                        true
+                    }
+                    _ = tokio::time::sleep_until(keep_alive_at) => false,
+                };
+
+                // Changes coming in faster than the minimum interval are
+                // sent together
+                if let Some(last) = last_frame {
+                    tokio::time::sleep_until(last + min_interval).await;
+                }
+
+                // Stay on schedule when switching back to continuous mode
+                deadline = Instant::now() + period;
+                !changed
+            }
+        };
+        let now = Instant::now();
+        // Cleared once a frame is sent, so skipped frames do not spin the loop
+        retry_at = Some(now + period);
+
+        // Check if server is still running
+        let running = *state.server_running.lock();
+        if !running {
+            break;
+        }
+
+        // Skip frame generation if nobody is listening (optimization)
+        let connected_clients = state.get_connected_clients();
+        if connected_clients == 0 && !state.recorder.is_recording() {
+            state.frame_stats.lock().reset();
+            continue;
+        }
+
+        // Hold the loop while paused, unless a step was requested, and
+        // while a recording is played back
+        if state.player.is_playing() || !state.frame_control.lock().take_frame() {
+            state.frame_stats.lock().reset();
+            continue;
+        }
+        state.frame_stats.lock().record(now.into_std());
+
+        // Keep-alives carry every token, so late clients catch up
+        let frame = if keep_alive_due {
+            generate_full_frame(&state)
+        } else {
+            generate_frame(&state)
+        };
+
+        match frame {
+            Ok(frame_data) => {
+                *state.last_bundle.lock() = Some(frame_data.clone());
+
+                // Batch lock acquisitions for debug info
+                let (frame_id, object_count) = {
+                    let frame_id = *state.frame_counter.lock();
+                    let object_count = state.objects.lock().len();
+                    (frame_id, object_count)
+                };
+
+                if let Some(status) = state.recorder.record(frame_id, &frame_data).await {
+                    state
+                        .events
+                        .publish(SimulatorEvent::RecordingProgress(status));
+                }
+
+                state.events.publish(SimulatorEvent::FrameSent(FrameInfo {
+                    frame_id,
+                    timestamp: chrono::Utc::now().timestamp_millis(),
+                    object_count,
+                    message_size: frame_data.len(),
+                    connected_clients,
+                }));
+
+                // Send to all running sinks, reporting per-target errors
+                for error in state.sinks.send(&frame_data).await {
+                    state.events.publish(SimulatorEvent::TransportError(error));
+                }
+
+                // Keep-alive and minimum interval count from frames actually sent
+                last_frame = Some(now);
+                retry_at = None;
+            }
+            Err(e) => {
+                eprintln!("Error generating frame: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions;
+    use crate::motion::{Motion, MotionPattern, MotionStep};
+    use crate::transport::SinkKind;
+    use tokio::net::UdpSocket;
+    use tokio::sync::broadcast;
+
+    /// State sending to a UDP socket, so the loop has a listener
+    async fn udp_state() -> (AppState, UdpSocket) {
+        let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let state = AppState::new();
+        {
+            let mut config = state.config.lock();
+            config.enabled_sinks = vec![SinkKind::Udp];
+            config.udp_targets = vec![receiver.local_addr().unwrap().to_string()];
+        }
+        (state, receiver)
+    }
+
+    async fn next_frame(events: &mut broadcast::Receiver<SimulatorEvent>) -> FrameInfo {
+        loop {
+            if let SimulatorEvent::FrameSent(frame) = events.recv().await.unwrap() {
+                return frame;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_frames_are_published() {
+        let (state, receiver) = udp_state().await;
+        let mut events = state.events.subscribe();
+
+        start_server(&state, 0, None).await.unwrap();
+        let frame = next_frame(&mut events).await;
+        stop_server(&state).await;
+
+        assert_eq!(frame.connected_clients, 1);
+        assert!(frame.message_size > 0);
+        assert!(state.last_bundle.lock().is_some());
+
+        let mut buf = [0u8; 1024];
+        assert!(receiver.recv(&mut buf).await.unwrap() > 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_step_while_paused() {
+        let (state, receiver) = udp_state().await;
+        let mut events = state.events.subscribe();
+        actions::pause_frames(&state);
+
+        start_server(&state, 0, None).await.unwrap();
+        assert!(resend_frame(&state).await.is_err());
+
+        actions::step_frame(&state, 2).unwrap();
+        let first = next_frame(&mut events).await;
+        let second = next_frame(&mut events).await;
+        assert_eq!(second.frame_id, first.frame_id + 1);
+
+        // No further frames are generated until the next step
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(events.try_recv().is_err());
+        assert_eq!(state.frame_control.lock().pending_steps, 0);
+
+        // The last frame can be sent again while paused
+        let mut buf = [0u8; 1024];
+        receiver.recv(&mut buf).await.unwrap();
+        let size = receiver.recv(&mut buf).await.unwrap();
+        let last = buf[..size].to_vec();
+        resend_frame(&state).await.unwrap();
+        let size = receiver.recv(&mut buf).await.unwrap();
+        assert_eq!(buf[..size], last[..]);
+
+        stop_server(&state).await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_frame_rate_change_applies_immediately() {
+        let (state, _receiver) = udp_state().await;
+        actions::set_frame_rate(&state, 1).unwrap();
+        let mut events = state.events.subscribe();
+
+        start_server(&state, 0, None).await.unwrap();
+        next_frame(&mut events).await;
+
+        // At 1 fps the next frame would be due in a second
+        actions::set_frame_rate(&state, 100).unwrap();
+        let next = tokio::time::timeout(Duration::from_millis(500), next_frame(&mut events)).await;
+        stop_server(&state).await;
+
+        assert!(next.is_ok());
+    }
+
+    #[test]
+    fn test_frame_period() {
+        assert_eq!(frame_period(60), Duration::from_secs_f64(1.0 / 60.0));
+        assert_eq!(frame_period(0), Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_on_change_frames() {
+        let (state, _receiver) = udp_state().await;
+        actions::set_frame_mode(&state, FrameMode::OnChange, 0, 200, false).unwrap();
+        let mut events = state.events.subscribe();
+
+        start_server(&state, 0, None).await.unwrap();
+        next_frame(&mut events).await;
+
+        // Nothing is sent until an object changes
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(events.try_recv().is_err());
+        actions::add_object(&state, 1, 0.5, 0.5).unwrap();
+        let frame = tokio::time::timeout(Duration::from_millis(100), next_frame(&mut events))
+            .await
+            .unwrap();
+        assert_eq!(frame.object_count, 1);
+
+        // Without changes a keep-alive follows
+        let keep_alive =
+            tokio::time::timeout(Duration::from_millis(400), next_frame(&mut events)).await;
+        stop_server(&state).await;
+
+        assert!(keep_alive.is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_skipped_frames_do_not_delay_keep_alive() {
+        let (state, _receiver) = udp_state().await;
+        actions::set_frame_mode(&state, FrameMode::OnChange, 0, 200, false).unwrap();
+        let mut events = state.events.subscribe();
+
+        start_server(&state, 0, None).await.unwrap();
+        next_frame(&mut events).await;
+
+        // The keep-alive falls due while paused and is skipped
+        actions::pause_frames(&state);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(events.try_recv().is_err());
+
+        // Unpaused without waking the loop, the overdue keep-alive follows
+        // within a frame period
+        state.frame_control.lock().paused = false;
+        let keep_alive =
+            tokio::time::timeout(Duration::from_millis(50), next_frame(&mut events)).await;
+        stop_server(&state).await;
+
+        assert!(keep_alive.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_motions_advance_while_paused() {
+        for mode in [FrameMode::Continuous, FrameMode::OnChange] {
+            let (state, _receiver) = udp_state().await;
+            actions::set_frame_mode(&state, mode, 0, 200, false).unwrap();
+            let session_id = actions::add_object(&state, 1, 0.5, 0.5).unwrap();
+            let motion = Motion {
+                steps: vec![MotionStep {
+                    pattern: MotionPattern::Linear { to: [1.0, 0.5] },
+                    duration_ms: 50,
+                }],
+                looping: false,
+            };
+            actions::attach_motion(&state, session_id, motion).unwrap();
+            actions::pause_frames(&state);
+            let mut events = state.events.subscribe();
+
+            // Moves are reported without any frame being sent
+            start_server(&state, 0, None).await.unwrap();
+            let moved = tokio::time::timeout(Duration::from_secs(5), async {
+                loop {
+                    match events.recv().await.unwrap() {
+                        SimulatorEvent::ObjectChanged { change, .. } => break change,
+                        SimulatorEvent::FrameSent(_) => panic!("frame sent while paused"),
+                        _ => {}
+                    }
+                }
+            })
+            .await
+            .unwrap();
+            assert_eq!(moved, ObjectChange::Updated);
+
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            stop_server(&state).await;
+
+            assert!(actions::list_motions(&state).is_empty(), "{:?}", mode);
+            assert_eq!(state.objects.lock()[&session_id].x, 1.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_on_change_motions_follow_frame_rate() {
+        let (state, _receiver) = udp_state().await;
+        actions::set_frame_rate(&state, 20).unwrap();
+        actions::set_frame_mode(&state, FrameMode::OnChange, 0, 10_000, false).unwrap();
+        let session_id = actions::add_object(&state, 1, 0.0, 0.5).unwrap();
+        let motion = Motion {
+            steps: vec![MotionStep {
+                pattern: MotionPattern::Linear { to: [1.0, 0.5] },
+                duration_ms: 60_000,
+            }],
+            looping: false,
+        };
+        actions::attach_motion(&state, session_id, motion).unwrap();
+
+        start_server(&state, 0, None).await.unwrap();
+        let mut events = state.events.subscribe();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        stop_server(&state).await;
+
+        // A moving object is sent at 20 fps, not as fast as frames allow
+        let mut frames = 0;
+        while let Ok(event) = events.try_recv() {
+            if let SimulatorEvent::FrameSent(_) = event {
+                frames += 1;
+            }
+        }
+        assert!((5..=12).contains(&frames), "{} frames", frames);
+    }
+}
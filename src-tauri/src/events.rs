@@ -1,3 +1,7 @@
+use crate::bus::{ObjectChange, SimulatorEvent};
+use crate::playback::PlaybackStatus;
+use crate::recording::RecordingStatus;
+use crate::transport::clients::ClientInfo;
 use serde::Serialize;
 use tauri::{AppHandle, Emitter};
 
@@ -10,6 +14,19 @@ pub struct OscMessageEvent {
     pub connected_clients: usize,
 }
 
+#[derive(Clone, Serialize)]
+pub struct TransportErrorEvent {
+    pub transport: String,
+    pub target: String,
+    pub message: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ObjectChangedEvent {
+    pub session_id: u32,
+    pub change: ObjectChange,
+}
+
 /// Emit an OSC message debug event
 pub fn emit_osc_message(
     app: &AppHandle,
@@ -28,3 +45,62 @@ pub fn emit_osc_message(
     };
     let _ = app.emit("osc_message", event);
 }
+
+/// Emit a transport error event for a single send target
+pub fn emit_transport_error(app: &AppHandle, transport: &str, target: &str, message: &str) {
+    let event = TransportErrorEvent {
+        transport: transport.to_string(),
+        target: target.to_string(),
+        message: message.to_string(),
+    };
+    let _ = app.emit("transport_error", event);
+}
+
+/// Emit a client connected event
+pub fn emit_client_connected(app: &AppHandle, client: ClientInfo) {
+    let _ = app.emit("client_connected", client);
+}
+
+/// Emit a client disconnected event with the final statistics of the client
+pub fn emit_client_disconnected(app: &AppHandle, client: ClientInfo) {
+    let _ = app.emit("client_disconnected", client);
+}
+
+/// Emit an object added, changed or removed event
+pub fn emit_object_changed(app: &AppHandle, session_id: u32, change: ObjectChange) {
+    let _ = app.emit("object_changed", ObjectChangedEvent { session_id, change });
+}
+
+/// Emit a recording progress event
+pub fn emit_recording_progress(app: &AppHandle, status: RecordingStatus) {
+    let _ = app.emit("recording_progress", status);
+}
+
+/// Emit a playback state event
+pub fn emit_playback_progress(app: &AppHandle, status: PlaybackStatus) {
+    let _ = app.emit("playback_progress", status);
+}
+
+/// Forward a simulator event to the frontend
+pub fn emit_simulator_event(app: &AppHandle, event: SimulatorEvent) {
+    match event {
+        SimulatorEvent::FrameSent(frame) => emit_osc_message(
+            app,
+            frame.frame_id,
+            frame.timestamp,
+            frame.object_count,
+            frame.message_size,
+            frame.connected_clients,
+        ),
+        SimulatorEvent::ClientConnected(client) => emit_client_connected(app, client),
+        SimulatorEvent::ClientDisconnected(client) => emit_client_disconnected(app, client),
+        SimulatorEvent::ObjectChanged { session_id, change } => {
+            emit_object_changed(app, session_id, change)
+        }
+        SimulatorEvent::TransportError(error) => {
+            emit_transport_error(app, error.kind.as_str(), &error.target, &error.message)
+        }
+        SimulatorEvent::RecordingProgress(status) => emit_recording_progress(app, status),
+        SimulatorEvent::PlaybackProgress(status) => emit_playback_progress(app, status),
+    }
+}
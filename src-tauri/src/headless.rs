@@ -0,0 +1,336 @@
+use crate::bus::SimulatorEvent;
+use crate::engine;
+use crate::scene::LoadMode;
+use crate::state::AppState;
+use crate::transport::bind::BindAddress;
+use crate::{actions, control, rest, scene, script};
+use anyhow::{anyhow, Result};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use tokio::sync::broadcast::error::RecvError;
+
+const USAGE: &str = "\
+Usage: tuio-simulator-headless [OPTIONS]
+
+Runs the simulator without a window until SIGINT or SIGTERM, or until
+the script given with --script ends.
+
+Options:
+  --port <PORT>          WebSocket port [default: 3343]
+  --bind <ADDRESS>       localhost, all or an IP address [default: localhost]
+  --fps <FPS>            Frame rate, 1 to 120 [default: 60]
+  --width <PIXELS>       Canvas width [default: 1920]
+  --height <PIXELS>      Canvas height [default: 1080]
+  --udp <HOST:PORT>      Also send to a UDP target, may be repeated
+  --scene <FILE>         Load a scene file, the options above override its settings
+  --control-token <TOKEN>
+                         Token required to change the scene over WebSocket
+                         or the HTTP API
+  --http-port <PORT>     Serve the HTTP API on 127.0.0.1 at this port
+  --script <FILE>        Run a Rhai script once the server started
+  -h, --help             Print this help";
+
+/// Command line options of the headless mode
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub port: u16,
+    pub bind_address: BindAddress,
+    pub fps: Option<u32>,
+    pub width: Option<u16>,
+    pub height: Option<u16>,
+    pub udp_targets: Vec<String>,
+    pub scene: Option<PathBuf>,
+    pub control_token: Option<String>,
+    pub http_port: Option<u16>,
+    pub script: Option<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            port: 3343,
+            bind_address: BindAddress::default(),
+            fps: None,
+            width: None,
+            height: None,
+            udp_targets: Vec::new(),
+            scene: None,
+            control_token: None,
+            http_port: None,
+            script: None,
+        }
+    }
+}
+
+impl Options {
+    /// Parse the arguments following the program name
+    ///
+    /// Returns `None` if help was requested.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Self>> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            if arg == "-h" || arg == "--help" {
+                return Ok(None);
+            }
+
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| anyhow!("Missing value for {}", arg))
+            };
+            match arg.as_str() {
+                "--port" => options.port = parse_number(&arg, value()?)?,
+                "--bind" => options.bind_address = parse_bind_address(&value()?)?,
+                "--fps" => options.fps = Some(parse_number(&arg, value()?)?),
+                "--width" => options.width = Some(parse_number(&arg, value()?)?),
+                "--height" => options.height = Some(parse_number(&arg, value()?)?),
+                "--udp" => options.udp_targets.push(value()?),
+                "--scene" => options.scene = Some(PathBuf::from(value()?)),
+                "--control-token" => options.control_token = Some(value()?),
+                "--http-port" => options.http_port = Some(parse_number(&arg, value()?)?),
+                "--script" => options.script = Some(PathBuf::from(value()?)),
+                _ => return Err(anyhow!("Unknown argument {}", arg)),
+            }
+        }
+
+        Ok(Some(options))
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(arg: &str, value: String) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| anyhow!("Invalid value for {}: {}", arg, value))
+}
+
+fn parse_bind_address(value: &str) -> Result<BindAddress> {
+    match value {
+        "localhost" => Ok(BindAddress::Localhost),
+        "all" => Ok(BindAddress::AllInterfaces),
+        _ => value
+            .parse()
+            .map(BindAddress::Address)
+            .map_err(|_| anyhow!("Invalid bind address {}", value)),
+    }
+}
+
+/// Log transport errors, frames are not reported
+async fn log_errors(state: AppState) {
+    let mut events = state.events.subscribe();
+    loop {
+        match events.recv().await {
+            Ok(SimulatorEvent::TransportError(error)) => eprintln!(
+                "Error sending to {} target {}: {}",
+                error.kind, error.target, error.message
+            ),
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Run the simulator until a shutdown signal is received
+pub async fn run(options: Options) -> Result<()> {
+    let state = AppState::new();
+
+    if let Some(path) = &options.scene {
+        let session_ids = scene::load(&state, path, LoadMode::Replace)?;
+        println!(
+            "Loaded {} objects from {}",
+            session_ids.len(),
+            path.display()
+        );
+    }
+
+    if let Some(fps) = options.fps {
+        actions::set_frame_rate(&state, fps).map_err(|e| anyhow!(e))?;
+    }
+    let (width, height) = {
+        let config = state.config.lock();
+        (
+            options.width.unwrap_or(config.width),
+            options.height.unwrap_or(config.height),
+        )
+    };
+    actions::set_canvas_dimensions(&state, width, height).map_err(|e| anyhow!(e))?;
+    {
+        let mut config = state.config.lock();
+        config.udp_targets = options.udp_targets;
+        config.control_token = options.control_token;
+        if let Some(port) = options.http_port {
+            config.http_api_enabled = true;
+            config.http_api_port = port;
+        }
+    }
+
+    // Answer control requests sent over the WebSocket connections
+    tokio::spawn(control::serve(state.clone()));
+    tokio::spawn(log_errors(state.clone()));
+
+    engine::start_server(&state, options.port, Some(options.bind_address)).await?;
+    rest::apply_config(&state).await?;
+
+    for status in state.sinks.statuses(&state.config.lock().enabled_sinks) {
+        for address in &status.addresses {
+            println!("{} listening on {}", status.kind, address);
+        }
+    }
+    if let Some(address) = state.api_server.local_addr() {
+        println!("HTTP API listening on {}", address);
+    }
+
+    // A failing script fails the run, so it can be used in CI
+    let result = match &options.script {
+        Some(path) => run_script(&state, path, shutdown_signal()).await,
+        None => shutdown_signal().await,
+    };
+
+    println!("Shutting down");
+    engine::stop_server(&state).await;
+
+    result
+}
+
+/// Run a script until it ends or a shutdown is requested
+///
+/// The script runs on a blocking thread, which keeps going when its
+/// future is dropped, so it is stopped and waited for on shutdown.
+async fn run_script(
+    state: &AppState,
+    path: &Path,
+    shutdown: impl Future<Output = Result<()>>,
+) -> Result<()> {
+    let script = script::run_file(state, path, |line| println!("{}", line));
+    tokio::pin!(script);
+
+    tokio::select! {
+        result = &mut script => result,
+        result = shutdown => {
+            state.scripts.stop_all();
+            let _ = script.await;
+            result
+        }
+    }
+}
+
+/// Wait for SIGINT, or SIGTERM on Unix
+async fn shutdown_signal() -> Result<()> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result?,
+            _ = terminate.recv() => {}
+        }
+    }
+
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await?;
+
+    Ok(())
+}
+
+/// Entry point of the headless binary
+pub fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to start runtime: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match runtime.block_on(run(options)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn parse(args: &[&str]) -> Result<Option<Options>> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = parse(&[
+            "--port",
+            "4000",
+            "--fps",
+            "30",
+            "--bind",
+            "all",
+            "--udp",
+            "127.0.0.1:3333",
+            "--scene",
+            "scene.json",
+            "--http-port",
+            "3380",
+            "--script",
+            "scenario.rhai",
+        ])
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(options.port, 4000);
+        assert_eq!(options.fps, Some(30));
+        assert_eq!(options.bind_address, BindAddress::AllInterfaces);
+        assert_eq!(options.udp_targets, vec!["127.0.0.1:3333".to_string()]);
+        assert_eq!(options.scene, Some(PathBuf::from("scene.json")));
+        assert_eq!(options.width, None);
+        assert_eq!(options.http_port, Some(3380));
+        assert_eq!(options.script, Some(PathBuf::from("scenario.rhai")));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(&["--help"]).unwrap().is_none());
+        assert!(parse(&["--port"]).is_err());
+        assert!(parse(&["--port", "high"]).is_err());
+        assert!(parse(&["--bind", "nowhere"]).is_err());
+        assert!(parse(&["--verbose"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_script() {
+        let path = std::env::temp_dir().join(format!(
+            "tuio-simulator-script-{}.rhai",
+            uuid::Uuid::new_v4()
+        ));
+        std::fs::write(&path, "loop { wait(10); }").unwrap();
+
+        let state = AppState::new();
+        let shutdown = async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(())
+        };
+        let result =
+            tokio::time::timeout(Duration::from_secs(5), run_script(&state, &path, shutdown)).await;
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(result.unwrap().is_ok());
+    }
+}
@@ -1,28 +1,122 @@
+mod actions;
+mod bus;
+// Items only reached through the Tauri commands are marked with
+// `#[cfg_attr(not(feature = "gui"), allow(dead_code))]`
+#[cfg(feature = "gui")]
 mod commands;
+mod control;
+mod engine;
+#[cfg(feature = "gui")]
 mod events;
+mod headless;
+mod motion;
+mod playback;
+mod recording;
+mod rest;
+mod scene;
+mod script;
 mod state;
+mod tcp;
+mod transport;
 mod tuio;
+mod udp;
 mod websocket;
 
-use state::AppState;
+use std::process::ExitCode;
 
+#[cfg(feature = "gui")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    use state::AppState;
+    use tokio::sync::broadcast::error::RecvError;
+
     let app_state = AppState::new();
+    let control_state = app_state.clone();
+    let mut simulator_events = app_state.events.subscribe();
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .manage(app_state)
+        .setup(|app| {
+            // Forward simulator events to the frontend
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    match simulator_events.recv().await {
+                        Ok(event) => events::emit_simulator_event(&handle, event),
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            });
+
+            // Answer control requests sent over the WebSocket connections
+            tauri::async_runtime::spawn(control::serve(control_state));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::start_server,
             commands::stop_server,
             commands::add_object,
+            commands::add_pointer,
+            commands::add_blob,
             commands::update_object,
+            commands::update_pointer,
+            commands::set_object_bounds,
+            commands::set_object_symbol,
+            commands::set_object_controls,
+            commands::set_object_data,
+            commands::set_object_geometry,
+            commands::set_object_pose_3d,
+            commands::associate_container,
+            commands::dissociate_container,
+            commands::link_objects,
+            commands::unlink_objects,
             commands::remove_object,
             commands::set_frame_rate,
             commands::get_server_status,
+            commands::list_clients,
+            commands::disconnect_client,
             commands::set_canvas_dimensions,
+            commands::set_protocol_version,
+            commands::set_3d_output,
+            commands::set_control_send_mode,
+            commands::set_control_token,
+            commands::set_http_api,
+            commands::pause_frames,
+            commands::resume_frames,
+            commands::step_frame,
+            commands::resend_frame,
+            commands::set_frame_mode,
+            commands::save_scene,
+            commands::load_scene,
+            commands::start_recording,
+            commands::stop_recording,
+            commands::get_recording_status,
+            commands::get_recording_info,
+            commands::start_playback,
+            commands::stop_playback,
+            commands::pause_playback,
+            commands::resume_playback,
+            commands::seek_playback,
+            commands::set_playback_speed,
+            commands::set_playback_looping,
+            commands::get_playback_status,
+            commands::attach_motion,
+            commands::detach_motion,
+            commands::list_motions,
+            commands::run_script,
+            commands::stop_scripts,
+            commands::set_sink_enabled,
+            commands::set_udp_targets,
+            commands::set_tcp_options,
+            commands::set_file_sink_path,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+/// Run the simulator without the Tauri window
+pub fn run_headless() -> ExitCode {
+    headless::main()
+}
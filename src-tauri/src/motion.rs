@@ -0,0 +1,401 @@
+use crate::state::TuioObject;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, TAU};
+
+/// Path an object follows, in normalized coordinates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MotionPattern {
+    /// Circle around a center, starting at the angle of the object to it
+    Circular {
+        center: [f32; 2],
+        radius: f32,
+        /// Revolutions per second, negative for clockwise
+        speed: f32,
+    },
+    /// Straight line from the current position
+    Linear { to: [f32; 2] },
+    /// Bezier curve from the current position, ending at the last point
+    Bezier { points: Vec<[f32; 2]> },
+    /// Random walk bouncing off the edges
+    RandomWalk {
+        /// Distance per second
+        speed: f32,
+    },
+}
+
+/// A pattern followed for a fixed time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MotionStep {
+    pub pattern: MotionPattern,
+    pub duration_ms: u64,
+}
+
+/// Chain of patterns, each starting where the previous one ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Motion {
+    pub steps: Vec<MotionStep>,
+    /// Start over with the first step after the last one
+    #[serde(default)]
+    pub looping: bool,
+}
+
+fn validate_point(point: [f32; 2]) -> Result<(), String> {
+    if !point.iter().all(|c| (0.0..=1.0).contains(c)) {
+        return Err("Motion points must be in range [0.0, 1.0]".to_string());
+    }
+    Ok(())
+}
+
+impl Motion {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.steps.is_empty() {
+            return Err("A motion needs at least one step".to_string());
+        }
+
+        for step in &self.steps {
+            if step.duration_ms == 0 {
+                return Err("Motion steps must have a duration".to_string());
+            }
+            match &step.pattern {
+                MotionPattern::Circular {
+                    center,
+                    radius,
+                    speed,
+                } => {
+                    validate_point(*center)?;
+                    if !(*radius > 0.0 && *radius <= 1.0) {
+                        return Err("Radius must be in range (0.0, 1.0]".to_string());
+                    }
+                    if !speed.is_finite() {
+                        return Err("Speed must be a finite number".to_string());
+                    }
+                }
+                MotionPattern::Linear { to } => validate_point(*to)?,
+                MotionPattern::Bezier { points } => {
+                    if points.is_empty() {
+                        return Err("A Bezier path needs at least one point".to_string());
+                    }
+                    points.iter().try_for_each(|point| validate_point(*point))?;
+                }
+                MotionPattern::RandomWalk { speed } => {
+                    if !(speed.is_finite() && *speed > 0.0) {
+                        return Err("Speed must be a positive number".to_string());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn duration_ms(&self) -> i64 {
+        self.steps.iter().map(|step| step.duration_ms as i64).sum()
+    }
+}
+
+fn lerp(a: [f32; 2], b: [f32; 2], t: f32) -> [f32; 2] {
+    [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t]
+}
+
+/// Point and direction of a Bezier curve at t
+fn bezier(points: &[[f32; 2]], t: f32) -> ([f32; 2], f32) {
+    let mut points = points.to_vec();
+    while points.len() > 2 {
+        points = points.windows(2).map(|w| lerp(w[0], w[1], t)).collect();
+    }
+    let [a, b] = [points[0], points[points.len() - 1]];
+    (lerp(a, b, t), heading(a, b))
+}
+
+fn heading(from: [f32; 2], to: [f32; 2]) -> f32 {
+    (to[1] - from[1]).atan2(to[0] - from[0])
+}
+
+/// A motion attached to an object, with its progress
+#[derive(Debug, Clone, Serialize)]
+pub struct ActiveMotion {
+    pub session_id: u32,
+    pub motion: Motion,
+    /// Index of the current step
+    pub step: usize,
+    /// Number of times the motion started over
+    pub loop_count: u32,
+    /// Start of the current step in milliseconds since the epoch
+    #[serde(skip)]
+    started: i64,
+    /// Position of the object at the start of the current step
+    #[serde(skip)]
+    origin: [f32; 2],
+    /// Direction of a random walk
+    #[serde(skip)]
+    walk_heading: f32,
+    #[serde(skip)]
+    last_update: i64,
+    #[serde(skip)]
+    rng: u64,
+}
+
+impl ActiveMotion {
+    pub fn new(object: &TuioObject, motion: Motion, timestamp: i64) -> Self {
+        Self {
+            session_id: object.session_id,
+            motion,
+            step: 0,
+            loop_count: 0,
+            started: timestamp,
+            origin: [object.x, object.y],
+            walk_heading: object.angle,
+            last_update: timestamp,
+            rng: ((object.session_id as u64) << 32 ^ timestamp as u64) | 1,
+        }
+    }
+
+    /// Uniformly distributed number in [0, 1), from a xorshift generator
+    fn random(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Move the object along the current step, at t from 0 to 1
+    fn apply(&mut self, object: &mut TuioObject, t: f32, timestamp: i64) {
+        let step = &self.motion.steps[self.step];
+        let elapsed = t * step.duration_ms as f32 / 1000.0;
+
+        let (position, angle) = match &step.pattern {
+            MotionPattern::Circular {
+                center,
+                radius,
+                speed,
+            } => {
+                let start = heading(*center, self.origin);
+                let phase = start + TAU * speed * elapsed;
+                let position = [
+                    center[0] + radius * phase.cos(),
+                    center[1] + radius * phase.sin(),
+                ];
+                (position, phase + FRAC_PI_2.copysign(*speed))
+            }
+            MotionPattern::Linear { to } => (lerp(self.origin, *to, t), heading(self.origin, *to)),
+            MotionPattern::Bezier { points } => {
+                let mut path = vec![self.origin];
+                path.extend_from_slice(points);
+                bezier(&path, t)
+            }
+            MotionPattern::RandomWalk { speed } => {
+                let speed = *speed;
+                let delta = (timestamp - self.last_update).max(0) as f32 / 1000.0;
+                self.walk_heading += (self.random() - 0.5) * FRAC_PI_4;
+
+                let mut x = object.x + self.walk_heading.cos() * speed * delta;
+                let mut y = object.y + self.walk_heading.sin() * speed * delta;
+                if !(0.0..=1.0).contains(&x) {
+                    x = x.clamp(0.0, 1.0) * 2.0 - x;
+                    self.walk_heading = std::f32::consts::PI - self.walk_heading;
+                }
+                if !(0.0..=1.0).contains(&y) {
+                    y = y.clamp(0.0, 1.0) * 2.0 - y;
+                    self.walk_heading = -self.walk_heading;
+                }
+                ([x, y], self.walk_heading)
+            }
+        };
+
+        object.x = position[0].clamp(0.0, 1.0);
+        object.y = position[1].clamp(0.0, 1.0);
+        object.angle = angle.rem_euclid(TAU);
+        self.last_update = timestamp;
+    }
+
+    /// Move the object to where it is at the given time, returning false
+    /// once the motion is complete
+    fn advance(&mut self, object: &mut TuioObject, timestamp: i64) -> bool {
+        loop {
+            let duration = self.motion.steps[self.step].duration_ms as i64;
+            let elapsed = timestamp - self.started;
+            if elapsed < duration {
+                self.apply(object, elapsed as f32 / duration as f32, timestamp);
+                return true;
+            }
+
+            // Finish the step at its end, the next one starts from there
+            self.apply(object, 1.0, self.started + duration);
+            self.started += duration;
+            self.origin = [object.x, object.y];
+            self.step += 1;
+
+            if self.step == self.motion.steps.len() {
+                if !self.motion.looping {
+                    return false;
+                }
+                self.step = 0;
+                self.loop_count += 1;
+
+                // Do not catch up on whole loops, e.g. after a long pause
+                if timestamp - self.started > self.motion.duration_ms() {
+                    self.started = timestamp;
+                    self.last_update = timestamp;
+                }
+            }
+        }
+    }
+}
+
+/// Move all objects with an attached motion, dropping completed motions
+/// and those of removed objects
+///
+/// Returns the session IDs of the objects whose position or angle changed.
+pub fn advance_motions(
+    motions: &mut HashMap<u32, ActiveMotion>,
+    objects: &mut HashMap<u32, TuioObject>,
+    timestamp: i64,
+) -> Vec<u32> {
+    let mut moved = Vec::new();
+    motions.retain(|session_id, motion| match objects.get_mut(session_id) {
+        Some(object) => {
+            let before = (object.x, object.y, object.angle);
+            let running = motion.advance(object, timestamp);
+            if (object.x, object.y, object.angle) != before {
+                moved.push(*session_id);
+            }
+            running
+        }
+        None => false,
+    });
+    moved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(pattern: MotionPattern, duration_ms: u64) -> MotionStep {
+        MotionStep {
+            pattern,
+            duration_ms,
+        }
+    }
+
+    fn run(motion: Motion, times: &[i64]) -> (Vec<[f32; 3]>, HashMap<u32, ActiveMotion>) {
+        let mut objects = HashMap::new();
+        objects.insert(1, TuioObject::new(1, 0, 0, 0.5, 0.5, 0));
+        let mut motions = HashMap::new();
+        motions.insert(1, ActiveMotion::new(&objects[&1], motion, 0));
+
+        let positions = times
+            .iter()
+            .map(|&time| {
+                advance_motions(&mut motions, &mut objects, time);
+                let object = &objects[&1];
+                [object.x, object.y, object.angle]
+            })
+            .collect();
+        (positions, motions)
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_linear_chain() {
+        let motion = Motion {
+            steps: vec![
+                step(MotionPattern::Linear { to: [1.0, 0.5] }, 1000),
+                step(MotionPattern::Linear { to: [1.0, 1.0] }, 1000),
+            ],
+            looping: false,
+        };
+        let (positions, motions) = run(motion, &[500, 1500, 2500]);
+
+        assert_near(positions[0][0], 0.75);
+        assert_near(positions[0][2], 0.0);
+        assert_near(positions[1][0], 1.0);
+        assert_near(positions[1][1], 0.75);
+        assert_near(positions[1][2], FRAC_PI_2);
+        assert_eq!(positions[2][..2], [1.0, 1.0]);
+        assert!(motions.is_empty());
+    }
+
+    #[test]
+    fn test_circular_loop() {
+        let motion = Motion {
+            steps: vec![step(
+                MotionPattern::Circular {
+                    center: [0.25, 0.5],
+                    radius: 0.25,
+                    speed: 1.0,
+                },
+                1000,
+            )],
+            looping: true,
+        };
+        let (positions, motions) = run(motion, &[250, 500, 1250]);
+
+        assert_near(positions[0][0], 0.25);
+        assert_near(positions[0][1], 0.75);
+        assert_near(positions[1][0], 0.0);
+        assert_near(positions[2][1], 0.75);
+        assert_eq!(motions[&1].loop_count, 1);
+    }
+
+    #[test]
+    fn test_bezier_and_random_walk() {
+        let motion = Motion {
+            steps: vec![
+                step(
+                    MotionPattern::Bezier {
+                        points: vec![[0.5, 1.0], [1.0, 1.0]],
+                    },
+                    1000,
+                ),
+                step(MotionPattern::RandomWalk { speed: 2.0 }, 5000),
+            ],
+            looping: false,
+        };
+        let times: Vec<i64> = (1..=60).map(|i| i * 100).collect();
+        let (positions, _) = run(motion, &times);
+
+        assert_near(positions[9][0], 1.0);
+        assert_near(positions[9][1], 1.0);
+        for position in &positions[10..] {
+            assert!((0.0..=1.0).contains(&position[0]));
+            assert!((0.0..=1.0).contains(&position[1]));
+        }
+        assert_ne!(positions[20], positions[30]);
+    }
+
+    #[test]
+    fn test_validate() {
+        let motion = |pattern| Motion {
+            steps: vec![step(pattern, 1000)],
+            looping: false,
+        };
+        assert!(motion(MotionPattern::Linear { to: [0.5, 0.5] })
+            .validate()
+            .is_ok());
+        assert!(motion(MotionPattern::Linear { to: [1.5, 0.5] })
+            .validate()
+            .is_err());
+        assert!(motion(MotionPattern::Bezier { points: vec![] })
+            .validate()
+            .is_err());
+        assert!(motion(MotionPattern::RandomWalk { speed: 0.0 })
+            .validate()
+            .is_err());
+
+        let empty = Motion {
+            steps: vec![],
+            looping: true,
+        };
+        assert!(empty.validate().is_err());
+    }
+}
@@ -0,0 +1,627 @@
+use crate::bus::{ObjectChange, SimulatorEvent};
+use crate::engine::{frame_period, FrameInfo};
+use crate::recording::{self, RecordedFrame};
+use crate::state::{AppState, TuioObject};
+use crate::tuio::decoder::{apply_packet, rewrite_frame};
+use anyhow::{anyhow, bail, Result};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub const MIN_SPEED: f64 = 0.1;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub const MAX_SPEED: f64 = 10.0;
+
+/// State of a playback, reported when it changes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlaybackStatus {
+    pub path: String,
+    /// False once the playback was stopped or reached the end
+    pub active: bool,
+    pub paused: bool,
+    pub position_ms: u64,
+    pub duration_ms: u64,
+    pub frame_count: u32,
+    pub speed: f64,
+    pub looping: bool,
+    /// Number of times the playback wrapped around to the start
+    pub loop_count: u32,
+}
+
+/// Maps wall clock time to a position in the recording
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+struct Clock {
+    anchor: Instant,
+    position: Duration,
+    speed: f64,
+    paused: bool,
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+impl Clock {
+    fn position(&self) -> Duration {
+        if self.paused {
+            self.position
+        } else {
+            self.position + self.anchor.elapsed().mul_f64(self.speed)
+        }
+    }
+
+    /// Move the anchor to now, keeping the current position
+    fn reanchor(&mut self) {
+        self.position = self.position();
+        self.anchor = Instant::now();
+    }
+
+    fn seek(&mut self, position: Duration) {
+        self.position = position;
+        self.anchor = Instant::now();
+    }
+
+    /// When the given position is reached, never while paused
+    fn due(&self, position: Duration) -> Option<Instant> {
+        if self.paused {
+            return None;
+        }
+        let remaining = position.saturating_sub(self.position);
+        Some(self.anchor + remaining.div_f64(self.speed))
+    }
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+struct ActivePlayback {
+    path: String,
+    duration: Duration,
+    /// Length of a pass when looping, a frame period past the last frame
+    loop_length: Duration,
+    frame_count: u32,
+    clock: Clock,
+    looping: bool,
+    loop_count: u32,
+    /// Position to continue from, applied by the playback task
+    seek: Option<Duration>,
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+impl ActivePlayback {
+    fn status(&self, active: bool) -> PlaybackStatus {
+        PlaybackStatus {
+            path: self.path.clone(),
+            active,
+            paused: self.clock.paused,
+            position_ms: self.clock.position().min(self.duration).as_millis() as u64,
+            duration_ms: self.duration.as_millis() as u64,
+            frame_count: self.frame_count,
+            speed: self.clock.speed,
+            looping: self.looping,
+            loop_count: self.loop_count,
+        }
+    }
+}
+
+/// Replays a session file to the running sinks
+///
+/// While playing, the frame loop is held and the replayed objects replace
+/// the objects on the canvas. The canvas is restored once the playback
+/// ends. Frame IDs continue the frame counter and FRM times are set to
+/// the time of sending, so both stay monotonic across loops and seeks.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub struct Player {
+    active: Mutex<Option<ActivePlayback>>,
+    task: Mutex<Option<JoinHandle<()>>>,
+    /// Wakes the playback task when the playback is changed
+    changed: Notify,
+    /// Objects on the canvas before the playback started
+    saved_objects: Mutex<Option<HashMap<u32, TuioObject>>>,
+}
+
+impl Player {
+    pub fn new() -> Self {
+        Self {
+            active: Mutex::new(None),
+            task: Mutex::new(None),
+            changed: Notify::new(),
+            saved_objects: Mutex::new(None),
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.active.lock().is_some()
+    }
+
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn status(&self) -> Option<PlaybackStatus> {
+        self.active
+            .lock()
+            .as_ref()
+            .map(|playback| playback.status(true))
+    }
+
+    /// Change the running playback and wake the playback task
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    fn update(&self, f: impl FnOnce(&mut ActivePlayback)) -> Result<PlaybackStatus> {
+        let mut active = self.active.lock();
+        let playback = active
+            .as_mut()
+            .ok_or_else(|| anyhow!("No playback is running"))?;
+        f(playback);
+        self.changed.notify_one();
+        Ok(playback.status(true))
+    }
+
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn pause(&self) -> Result<PlaybackStatus> {
+        self.update(|playback| {
+            playback.clock.reanchor();
+            playback.clock.paused = true;
+        })
+    }
+
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn resume(&self) -> Result<PlaybackStatus> {
+        self.update(|playback| {
+            playback.clock.reanchor();
+            playback.clock.paused = false;
+        })
+    }
+
+    /// Continue playing at the given time since the start of the recording
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn seek(&self, position: Duration) -> Result<PlaybackStatus> {
+        self.update(|playback| {
+            let position = position.min(playback.duration);
+            playback.clock.seek(position);
+            playback.seek = Some(position);
+        })
+    }
+
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn set_speed(&self, speed: f64) -> Result<PlaybackStatus> {
+        validate_speed(speed)?;
+        self.update(|playback| {
+            playback.clock.reanchor();
+            playback.clock.speed = speed;
+        })
+    }
+
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn set_looping(&self, looping: bool) -> Result<PlaybackStatus> {
+        if let Some(playback) = self.active.lock().as_ref() {
+            validate_looping(looping, playback.duration)?;
+        }
+        self.update(|playback| playback.looping = looping)
+    }
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn validate_speed(speed: f64) -> Result<()> {
+    if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+        bail!(
+            "Playback speed must be between {} and {}",
+            MIN_SPEED,
+            MAX_SPEED
+        );
+    }
+    Ok(())
+}
+
+/// A recording whose frames are all at its start cannot be looped
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn validate_looping(looping: bool, duration: Duration) -> Result<()> {
+    if looping && duration.is_zero() {
+        bail!("A recording without duration cannot be looped");
+    }
+    Ok(())
+}
+
+/// Start playing a session file, replacing a running playback
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn start(state: &AppState, path: &Path, speed: f64, looping: bool) -> Result<PlaybackStatus> {
+    validate_speed(speed)?;
+    let frames = recording::read(path)?;
+    let Some(last) = frames.last() else {
+        bail!("The session file contains no frames");
+    };
+    validate_looping(looping, last.offset)?;
+
+    stop(state);
+
+    let period = frame_period(state.config.lock().fps);
+    let playback = ActivePlayback {
+        path: path.display().to_string(),
+        duration: last.offset,
+        loop_length: last.offset + period,
+        frame_count: frames.len() as u32,
+        clock: Clock {
+            anchor: Instant::now(),
+            position: Duration::ZERO,
+            speed,
+            paused: false,
+        },
+        looping,
+        loop_count: 0,
+        seek: None,
+    };
+    let status = playback.status(true);
+
+    let player = &state.player;
+    *player.saved_objects.lock() = Some(state.objects.lock().clone());
+    *player.active.lock() = Some(playback);
+    *player.task.lock() = Some(tokio::spawn(run(state.clone(), frames)));
+
+    state
+        .events
+        .publish(SimulatorEvent::PlaybackProgress(status.clone()));
+    Ok(status)
+}
+
+/// Stop the playback and restore the canvas, returning the final status
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn stop(state: &AppState) -> Option<PlaybackStatus> {
+    let player = &state.player;
+    if let Some(task) = player.task.lock().take() {
+        task.abort();
+    }
+    finish(state)
+}
+
+/// End the playback without touching its task
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn finish(state: &AppState) -> Option<PlaybackStatus> {
+    let player = &state.player;
+    let status = player.active.lock().take()?.status(false);
+
+    if let Some(objects) = player.saved_objects.lock().take() {
+        replace_objects(state, objects);
+    }
+
+    state
+        .events
+        .publish(SimulatorEvent::PlaybackProgress(status.clone()));
+    Some(status)
+}
+
+/// Replace the objects on the canvas, reporting every change
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn replace_objects(state: &AppState, objects: HashMap<u32, TuioObject>) {
+    let mut changes = Vec::new();
+    {
+        let mut current = state.objects.lock();
+        for id in current.keys() {
+            if !objects.contains_key(id) {
+                changes.push((*id, ObjectChange::Removed));
+            }
+        }
+        for id in objects.keys() {
+            let change = if current.contains_key(id) {
+                ObjectChange::Updated
+            } else {
+                ObjectChange::Added
+            };
+            changes.push((*id, change));
+        }
+        *current = objects;
+    }
+
+    for (session_id, change) in changes {
+        state.object_changed(session_id, change);
+    }
+}
+
+/// Objects after playing the given frames
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn objects_after(frames: &[RecordedFrame]) -> HashMap<u32, TuioObject> {
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    let mut objects = HashMap::new();
+    for frame in frames {
+        if let Ok((_, packet)) = rosc::decoder::decode_udp(&frame.data) {
+            apply_packet(&mut objects, &packet, timestamp);
+        }
+    }
+    objects
+}
+
+/// Send a recorded frame with a new frame ID and time
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+async fn play_frame(
+    state: &AppState,
+    frame: &RecordedFrame,
+    objects: &mut HashMap<u32, TuioObject>,
+) {
+    let mut packet = match rosc::decoder::decode_udp(&frame.data) {
+        Ok((_, packet)) => packet,
+        Err(e) => {
+            eprintln!("Skipping invalid recorded frame {}: {}", frame.frame_id, e);
+            return;
+        }
+    };
+
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    let frame_id = state.increment_frame_counter();
+    rewrite_frame(&mut packet, frame_id, timestamp);
+    apply_packet(objects, &packet, timestamp);
+
+    let frame_data = match rosc::encoder::encode(&packet) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Error encoding recorded frame: {}", e);
+            return;
+        }
+    };
+
+    replace_objects(state, objects.clone());
+    *state.last_bundle.lock() = Some(frame_data.clone());
+
+    state.events.publish(SimulatorEvent::FrameSent(FrameInfo {
+        frame_id,
+        timestamp,
+        object_count: objects.len(),
+        message_size: frame_data.len(),
+        connected_clients: state.get_connected_clients(),
+    }));
+
+    for error in state.sinks.send(&frame_data).await {
+        state.events.publish(SimulatorEvent::TransportError(error));
+    }
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+async fn run(state: AppState, frames: Vec<RecordedFrame>) {
+    let player = &state.player;
+    let mut objects = HashMap::new();
+    let mut next = 0;
+
+    loop {
+        // When the next frame is due, or the end of the pass when looping
+        let (due, pass_end) = {
+            let mut active = player.active.lock();
+            let Some(playback) = active.as_mut() else {
+                return;
+            };
+
+            if let Some(position) = playback.seek.take() {
+                next = frames.partition_point(|frame| frame.offset < position);
+                objects = objects_after(&frames[..next]);
+            }
+
+            if next == frames.len() {
+                if !playback.looping {
+                    drop(active);
+                    player.task.lock().take();
+                    finish(&state);
+                    return;
+                }
+
+                // Wait a frame period after the last frame before starting
+                // over, so the passes do not run into each other
+                if playback.clock.position() < playback.loop_length {
+                    (playback.clock.due(playback.loop_length), true)
+                } else {
+                    // Start over, the frame counter keeps counting up
+                    next = 0;
+                    objects.clear();
+                    playback.loop_count += 1;
+                    playback.clock.seek(Duration::ZERO);
+                    let status = playback.status(true);
+                    state
+                        .events
+                        .publish(SimulatorEvent::PlaybackProgress(status));
+                    (playback.clock.due(frames[next].offset), false)
+                }
+            } else {
+                (playback.clock.due(frames[next].offset), false)
+            }
+        };
+
+        let wait = async {
+            match due {
+                Some(due) => tokio::time::sleep_until(due.into()).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            _ = player.changed.notified() => continue,
+            _ = wait => {}
+        }
+        if pass_end {
+            continue;
+        }
+
+        play_frame(&state, &frames[next], &mut objects).await;
+        next += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions;
+    use crate::engine::{start_server, stop_server};
+    use crate::recording::{Recorder, RecordingLimits};
+    use crate::transport::SinkKind;
+    use crate::tuio::encoder::{create_and_encode_tuio_bundle, EncoderOptions, ProtocolVersion};
+    use rosc::{OscPacket, OscType};
+    use tokio::net::UdpSocket;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("tuio-simulator-playback-{}.tuiorec", name))
+    }
+
+    /// Frame with a single token, moved along x by the frame ID
+    fn token_frame(frame_id: u32) -> Vec<u8> {
+        let objects = vec![TuioObject::new(1, 5, 5, frame_id as f32 / 10.0, 0.5, 0)];
+        create_and_encode_tuio_bundle(
+            ProtocolVersion::Tuio2,
+            frame_id,
+            0,
+            1920,
+            1080,
+            "test",
+            &objects,
+            &EncoderOptions::default(),
+        )
+        .unwrap()
+    }
+
+    /// Record frames moving a single token, 10 ms apart
+    async fn record_frames(name: &str, count: u32) -> std::path::PathBuf {
+        let path = temp_path(name);
+        let recorder = Recorder::new();
+        recorder.start(&path, RecordingLimits::default()).unwrap();
+        for frame_id in 1..=count {
+            recorder.record(frame_id, &token_frame(frame_id)).await;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        recorder.stop().await;
+        path
+    }
+
+    async fn udp_state() -> (AppState, UdpSocket) {
+        let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let state = AppState::new();
+        {
+            let mut config = state.config.lock();
+            config.enabled_sinks = vec![SinkKind::Udp];
+            config.udp_targets = vec![receiver.local_addr().unwrap().to_string()];
+        }
+        (state, receiver)
+    }
+
+    async fn next_frame_id(receiver: &UdpSocket) -> i32 {
+        let mut buffer = vec![0; 4096];
+        let len = tokio::time::timeout(Duration::from_secs(2), receiver.recv(&mut buffer))
+            .await
+            .unwrap()
+            .unwrap();
+        let (_, packet) = rosc::decoder::decode_udp(&buffer[..len]).unwrap();
+        let OscPacket::Bundle(bundle) = packet else {
+            panic!("Expected a bundle");
+        };
+        let OscPacket::Message(frm) = &bundle.content[0] else {
+            panic!("Expected a FRM message");
+        };
+        let OscType::Int(frame_id) = frm.args[0] else {
+            panic!("Expected a frame ID");
+        };
+        frame_id
+    }
+
+    #[tokio::test]
+    async fn test_playback_loops_with_monotonic_frame_ids() {
+        let path = record_frames("loop", 3).await;
+        let (state, receiver) = udp_state().await;
+        state
+            .objects
+            .lock()
+            .insert(9, TuioObject::new(9, 1, 1, 0.5, 0.5, 0));
+
+        // The frame loop is held while playing
+        start(&state, &path, 2.0, true).unwrap();
+        start_server(&state, 0, None).await.unwrap();
+        let mut last = 0;
+        for _ in 0..7 {
+            let frame_id = next_frame_id(&receiver).await;
+            assert!(frame_id > last);
+            last = frame_id;
+        }
+        assert!(state.player.status().unwrap().loop_count >= 2);
+        assert!(state.objects.lock().contains_key(&1));
+
+        let status = stop(&state).unwrap();
+        assert!(!status.active);
+        assert!(!state.player.is_playing());
+        assert!(state.objects.lock().contains_key(&9));
+        assert!(!state.objects.lock().contains_key(&1));
+
+        stop_server(&state).await;
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_playback_ends_and_seeks() {
+        let path = record_frames("seek", 5).await;
+        let (state, receiver) = udp_state().await;
+
+        start(&state, &path, 1.0, false).unwrap();
+        state.player.pause().unwrap();
+        start_server(&state, 0, None).await.unwrap();
+        let status = state.player.seek(Duration::from_secs(60)).unwrap();
+        assert_eq!(status.position_ms, status.duration_ms);
+        assert!(state.player.set_speed(20.0).is_err());
+
+        // Seeking to the end plays the last frame only
+        state.player.resume().unwrap();
+        next_frame_id(&receiver).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!state.player.is_playing());
+        assert!(state.objects.lock().is_empty());
+
+        stop_server(&state).await;
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_looping_single_frame_is_paced() {
+        // A single frame, 20 ms into the recording
+        let path = temp_path("single");
+        let recorder = Recorder::new();
+        recorder.start(&path, RecordingLimits::default()).unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        recorder.record(1, &token_frame(1)).await;
+        recorder.stop().await;
+
+        let (state, receiver) = udp_state().await;
+        actions::set_frame_rate(&state, 20).unwrap();
+
+        // Each pass takes the 20 ms up to the frame plus a 50 ms frame period,
+        // so the frame is sent 5 times
+        start(&state, &path, 1.0, true).unwrap();
+        start_server(&state, 0, None).await.unwrap();
+        let mut buffer = vec![0; 4096];
+        let mut frames = 0;
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(350);
+        while let Ok(result) = tokio::time::timeout_at(deadline, receiver.recv(&mut buffer)).await {
+            result.unwrap();
+            frames += 1;
+        }
+        stop(&state);
+        stop_server(&state).await;
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(frames >= 1);
+        assert!(frames <= 6, "{} frames sent", frames);
+    }
+
+    #[tokio::test]
+    async fn test_looping_without_duration_is_refused() {
+        // A single frame right at the start of the recording
+        let data = token_frame(1);
+        let mut contents = b"TUIOREC\x01".to_vec();
+        contents.extend_from_slice(&0u64.to_be_bytes());
+        contents.extend_from_slice(&1u32.to_be_bytes());
+        contents.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        contents.extend_from_slice(&data);
+        let path = temp_path("zero");
+        std::fs::write(&path, contents).unwrap();
+
+        let state = AppState::new();
+        assert!(start(&state, &path, 1.0, true).is_err());
+        start(&state, &path, 1.0, false).unwrap();
+        assert!(state.player.set_looping(true).is_err());
+
+        stop(&state);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
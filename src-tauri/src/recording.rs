@@ -0,0 +1,715 @@
+use crate::bus::SimulatorEvent;
+use crate::state::AppState;
+use anyhow::{anyhow, bail, Context, Result};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+/// Start of a session file, followed by the format version
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+const MAGIC: &[u8; 7] = b"TUIOREC";
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+const FORMAT_VERSION: u8 = 1;
+/// End of the index written when a recording is stopped
+const INDEX_MAGIC: &[u8; 4] = b"TIDX";
+
+/// Size of the header of a frame record: offset, frame ID and length
+const RECORD_HEADER_SIZE: u64 = 16;
+/// Size of an index entry: offset and file position
+const INDEX_ENTRY_SIZE: usize = 16;
+/// Size of the trailer: index position, frame count and magic
+const TRAILER_SIZE: usize = 16;
+
+/// Encoded frames waiting for the writer thread
+///
+/// Frames coming in while the queue is full are dropped and counted, so a
+/// slow disk neither holds up the frame loop nor fills the memory.
+const WRITE_QUEUE_FRAMES: usize = 100;
+
+/// Minimum time between two progress reports
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Limits after which a recording stops by itself
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordingLimits {
+    pub max_bytes: Option<u64>,
+    pub max_duration_ms: Option<u64>,
+}
+
+/// Progress of a recording, reported while recording and when it ends
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecordingStatus {
+    pub path: String,
+    pub active: bool,
+    pub frame_count: u32,
+    /// Size of the session file so far
+    pub bytes: u64,
+    pub duration_ms: u64,
+    /// Frames left out as the writer thread could not keep up
+    pub dropped_frames: u32,
+    /// The recording stopped as it reached one of its limits
+    pub limit_reached: bool,
+    /// The recording stopped as writing failed
+    pub error: Option<String>,
+}
+
+/// Summary of a session file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub struct RecordingInfo {
+    pub path: String,
+    pub frame_count: u32,
+    /// Offset of the last frame
+    pub duration_ms: u64,
+}
+
+/// A frame read from a session file
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub struct RecordedFrame {
+    /// Time since the start of the recording
+    pub offset: Duration,
+    pub frame_id: u32,
+    /// Encoded OSC bundle as it was sent
+    pub data: Vec<u8>,
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+struct ActiveRecording {
+    /// Identifies the recording to its duration timer
+    id: u64,
+    path: String,
+    /// Encoded records, written to the file by the writer thread
+    chunks: mpsc::Sender<Vec<u8>>,
+    /// Result of the writer thread once all chunks are written
+    done: oneshot::Receiver<std::io::Result<()>>,
+    /// Set by the writer thread when a write fails
+    error: Arc<Mutex<Option<String>>>,
+    timer: Option<JoinHandle<()>>,
+    limits: RecordingLimits,
+    started: Instant,
+    last_progress: Instant,
+    bytes: u64,
+    dropped_frames: u32,
+    /// Offset in microseconds and file position of every frame
+    index: Vec<(u64, u64)>,
+}
+
+impl ActiveRecording {
+    fn status(&self, active: bool) -> RecordingStatus {
+        RecordingStatus {
+            path: self.path.clone(),
+            active,
+            frame_count: self.index.len() as u32,
+            bytes: self.bytes,
+            duration_ms: self.started.elapsed().as_millis() as u64,
+            dropped_frames: self.dropped_frames,
+            limit_reached: false,
+            error: None,
+        }
+    }
+
+    fn check_error(&self) -> Result<()> {
+        match self.error.lock().clone() {
+            Some(error) => bail!(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Queue a frame for the writer thread, dropping it if the queue is full
+    fn write_frame(&mut self, offset_us: u64, frame_id: u32, data: &[u8]) -> Result<()> {
+        self.check_error()?;
+
+        let mut chunk = Vec::with_capacity(RECORD_HEADER_SIZE as usize + data.len());
+        chunk.extend_from_slice(&offset_us.to_be_bytes());
+        chunk.extend_from_slice(&frame_id.to_be_bytes());
+        chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        chunk.extend_from_slice(data);
+        match self.chunks.try_send(chunk) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                self.dropped_frames += 1;
+                return Ok(());
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                bail!("The session file writer stopped")
+            }
+        }
+
+        self.index.push((offset_us, self.bytes));
+        self.bytes += RECORD_HEADER_SIZE + data.len() as u64;
+        Ok(())
+    }
+
+    /// Write the index and trailer, then wait for the file to be flushed
+    async fn finish(mut self) -> Result<u64> {
+        if let Some(timer) = self.timer.take() {
+            timer.abort();
+        }
+
+        let mut chunk = Vec::with_capacity(self.index.len() * INDEX_ENTRY_SIZE + TRAILER_SIZE);
+        for (offset_us, position) in &self.index {
+            chunk.extend_from_slice(&offset_us.to_be_bytes());
+            chunk.extend_from_slice(&position.to_be_bytes());
+        }
+        chunk.extend_from_slice(&self.bytes.to_be_bytes());
+        chunk.extend_from_slice(&(self.index.len() as u32).to_be_bytes());
+        chunk.extend_from_slice(INDEX_MAGIC);
+        // The index is never dropped, wait for room in the queue
+        let written = match self.check_error() {
+            Ok(()) => self
+                .chunks
+                .send(chunk)
+                .await
+                .map_err(|_| anyhow!("The session file writer stopped")),
+            Err(e) => Err(e),
+        };
+
+        // Closing the channel lets the writer thread flush and end
+        drop(self.chunks);
+        let flushed = self
+            .done
+            .await
+            .map_err(|_| anyhow!("The session file writer stopped"))?;
+        written?;
+        flushed?;
+
+        Ok(self.bytes + (self.index.len() * INDEX_ENTRY_SIZE + TRAILER_SIZE) as u64)
+    }
+}
+
+/// Write chunks to the session file until the channel is closed
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn write_chunks(
+    mut writer: BufWriter<File>,
+    mut chunks: mpsc::Receiver<Vec<u8>>,
+    error: Arc<Mutex<Option<String>>>,
+) -> std::io::Result<()> {
+    while let Some(chunk) = chunks.blocking_recv() {
+        if let Err(e) = writer.write_all(&chunk) {
+            *error.lock() = Some(e.to_string());
+            return Err(e);
+        }
+    }
+    writer.flush()
+}
+
+/// Writes the generated frames to a session file
+///
+/// A session file starts with `TUIOREC` and a version byte. Every frame
+/// follows as its offset since the start in microseconds (u64), its frame
+/// ID (u32), its length (u32) and the raw OSC bytes. On stop an index of
+/// the offset and file position of every frame is appended, closed by the
+/// position of the index (u64), the frame count (u32) and `TIDX`. All
+/// numbers are big-endian.
+///
+/// The file is written by a separate thread, so recording does not block
+/// the frame loop. Frames are dropped and counted while that thread
+/// falls behind.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub struct Recorder {
+    active: Mutex<Option<ActiveRecording>>,
+    next_id: Mutex<u64>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self {
+            active: Mutex::new(None),
+            next_id: Mutex::new(0),
+        }
+    }
+
+    /// Start recording to a new session file
+    ///
+    /// The maximum duration is only checked when frames are recorded, use
+    /// `recording::start` to also end the recording while no frames come in.
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn start(&self, path: &Path, limits: RecordingLimits) -> Result<()> {
+        self.start_recording(path, limits).map(|_| ())
+    }
+
+    /// Start recording, returning the ID of the new recording
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    fn start_recording(&self, path: &Path, limits: RecordingLimits) -> Result<u64> {
+        let mut active = self.active.lock();
+        if active.is_some() {
+            bail!("A recording is already running");
+        }
+
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create session file {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[FORMAT_VERSION])?;
+
+        let (chunks, receiver) = mpsc::channel(WRITE_QUEUE_FRAMES);
+        let (done_sender, done) = oneshot::channel();
+        let error = Arc::new(Mutex::new(None));
+        let writer_error = error.clone();
+        std::thread::Builder::new()
+            .name("session-writer".to_string())
+            .spawn(move || {
+                let _ = done_sender.send(write_chunks(writer, receiver, writer_error));
+            })?;
+
+        let id = {
+            let mut next_id = self.next_id.lock();
+            *next_id += 1;
+            *next_id
+        };
+        let now = Instant::now();
+        *active = Some(ActiveRecording {
+            id,
+            path: path.display().to_string(),
+            chunks,
+            done,
+            error,
+            timer: None,
+            limits,
+            started: now,
+            last_progress: now,
+            bytes: (MAGIC.len() + 1) as u64,
+            dropped_frames: 0,
+            index: Vec::new(),
+        });
+
+        Ok(id)
+    }
+
+    /// Stop recording, returning the final status if a recording was running
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub async fn stop(&self) -> Option<RecordingStatus> {
+        let recording = self.active.lock().take()?;
+        Some(Self::finish(recording).await)
+    }
+
+    /// Stop the recording with the given ID as it reached its maximum
+    /// duration, called from its timer
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    async fn expire(&self, id: u64) -> Option<RecordingStatus> {
+        let mut recording = {
+            let mut active = self.active.lock();
+            if active.as_ref()?.id != id {
+                return None;
+            }
+            active.take()?
+        };
+        // Not aborted, it is the task running this
+        recording.timer = None;
+
+        let mut status = Self::finish(recording).await;
+        status.limit_reached = true;
+        Some(status)
+    }
+
+    async fn finish(recording: ActiveRecording) -> RecordingStatus {
+        let mut status = recording.status(false);
+        match recording.finish().await {
+            Ok(bytes) => status.bytes = bytes,
+            Err(e) => status.error = Some(e.to_string()),
+        }
+        status
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.active.lock().is_some()
+    }
+
+    /// Status of the running recording
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn status(&self) -> Option<RecordingStatus> {
+        self.active
+            .lock()
+            .as_ref()
+            .map(|recording| recording.status(true))
+    }
+
+    /// Append a frame to the running recording
+    ///
+    /// Returns a status to report, at most every half second while
+    /// recording and always when the recording stopped because of its
+    /// limits or a write error. Limits are checked with every frame.
+    pub async fn record(&self, frame_id: u32, data: &[u8]) -> Option<RecordingStatus> {
+        // The recording to end, and the error that ended it if not a limit
+        let (recording, error) = {
+            let mut active = self.active.lock();
+            let recording = active.as_mut()?;
+
+            let offset = recording.started.elapsed();
+            let limits = recording.limits;
+            let over_duration = limits
+                .max_duration_ms
+                .is_some_and(|max| offset.as_millis() as u64 > max);
+            let over_size = limits
+                .max_bytes
+                .is_some_and(|max| recording.bytes + RECORD_HEADER_SIZE + data.len() as u64 > max);
+
+            let error = if over_duration || over_size {
+                None
+            } else {
+                match recording.write_frame(offset.as_micros() as u64, frame_id, data) {
+                    Ok(()) => {
+                        if recording.last_progress.elapsed() < PROGRESS_INTERVAL {
+                            return None;
+                        }
+                        recording.last_progress = Instant::now();
+                        return Some(recording.status(true));
+                    }
+                    Err(e) => Some(e.to_string()),
+                }
+            };
+            (active.take()?, error)
+        };
+
+        let mut status = Self::finish(recording).await;
+        match error {
+            Some(error) => status.error = Some(error),
+            None => status.limit_reached = true,
+        }
+        Some(status)
+    }
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Start recording to a new session file
+///
+/// With a maximum duration, a timer ends the recording when it is
+/// reached, even if no frames are generated in the meantime.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn start(state: &AppState, path: &Path, limits: RecordingLimits) -> Result<()> {
+    let id = state.recorder.start_recording(path, limits)?;
+
+    if let Some(max_duration_ms) = limits.max_duration_ms {
+        let timer_state = state.clone();
+        let timer = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(max_duration_ms)).await;
+            if let Some(status) = timer_state.recorder.expire(id).await {
+                timer_state
+                    .events
+                    .publish(SimulatorEvent::RecordingProgress(status));
+            }
+        });
+
+        // Aborted when the recording is stopped before
+        let mut active = state.recorder.active.lock();
+        if let Some(recording) = active.as_mut().filter(|recording| recording.id == id) {
+            recording.timer = Some(timer);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn read_u32(data: &[u8], position: usize) -> Option<u32> {
+    let bytes = data.get(position..position.checked_add(4)?)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn read_u64(data: &[u8], position: usize) -> Option<u64> {
+    let bytes = data.get(position..position.checked_add(8)?)?;
+    Some(u64::from_be_bytes(bytes.try_into().ok()?))
+}
+
+/// Read a frame record at the given file position
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn read_record(data: &[u8], position: usize) -> Option<(RecordedFrame, usize)> {
+    let offset_us = read_u64(data, position)?;
+    let frame_id = read_u32(data, position.checked_add(8)?)?;
+    let length = read_u32(data, position.checked_add(12)?)? as usize;
+    let start = position.checked_add(RECORD_HEADER_SIZE as usize)?;
+    let end = start.checked_add(length)?;
+    let bytes = data.get(start..end)?;
+
+    let frame = RecordedFrame {
+        offset: Duration::from_micros(offset_us),
+        frame_id,
+        data: bytes.to_vec(),
+    };
+    Some((frame, end))
+}
+
+/// File positions of the frames listed in the index
+///
+/// Returns `None` for files without an index. The position and count in
+/// the trailer are checked against the file size, so a corrupt trailer is
+/// an error.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn read_index(data: &[u8]) -> Result<Option<Vec<usize>>> {
+    let Some(trailer) = data.len().checked_sub(TRAILER_SIZE) else {
+        return Ok(None);
+    };
+    if &data[trailer + 12..] != INDEX_MAGIC {
+        return Ok(None);
+    }
+
+    let index_position = read_u64(data, trailer)
+        .and_then(|position| usize::try_from(position).ok())
+        .ok_or_else(|| anyhow!("Invalid index position"))?;
+    let frame_count = read_u32(data, trailer + 8).unwrap_or_default() as usize;
+    let index_end = frame_count
+        .checked_mul(INDEX_ENTRY_SIZE)
+        .and_then(|size| index_position.checked_add(size));
+    if index_end != Some(trailer) {
+        bail!(
+            "Index of {} frames at position {} does not fit the file of {} bytes",
+            frame_count,
+            index_position,
+            data.len()
+        );
+    }
+
+    let positions = (0..frame_count)
+        .map(|i| {
+            read_u64(data, index_position + i * INDEX_ENTRY_SIZE + 8)
+                .and_then(|position| usize::try_from(position).ok())
+                .ok_or_else(|| anyhow!("Invalid index entry {}", i))
+        })
+        .collect::<Result<_>>()?;
+    Ok(Some(positions))
+}
+
+/// Parse the contents of a session file
+///
+/// Files without a complete index, e.g. from a crashed recording, are
+/// scanned up to the last complete frame.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn parse(data: &[u8]) -> Result<Vec<RecordedFrame>> {
+    let header_size = MAGIC.len() + 1;
+    if data.len() < header_size || &data[..MAGIC.len()] != MAGIC {
+        bail!("Not a session file");
+    }
+    if data[MAGIC.len()] != FORMAT_VERSION {
+        bail!("Unsupported session file version {}", data[MAGIC.len()]);
+    }
+
+    if let Some(positions) = read_index(data)? {
+        return positions
+            .into_iter()
+            .map(|position| {
+                read_record(data, position)
+                    .map(|(frame, _)| frame)
+                    .ok_or_else(|| anyhow!("Invalid frame at position {}", position))
+            })
+            .collect();
+    }
+
+    let mut frames = Vec::new();
+    let mut position = header_size;
+    while let Some((frame, next)) = read_record(data, position) {
+        frames.push(frame);
+        position = next;
+    }
+    Ok(frames)
+}
+
+/// Read all frames of a session file
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn read(path: &Path) -> Result<Vec<RecordedFrame>> {
+    let data = std::fs::read(path)
+        .with_context(|| format!("Failed to read session file {}", path.display()))?;
+
+    parse(&data).with_context(|| format!("Invalid session file {}", path.display()))
+}
+
+/// Summary of a session file
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn info(path: &Path) -> Result<RecordingInfo> {
+    let frames = read(path)?;
+    Ok(RecordingInfo {
+        path: path.display().to_string(),
+        frame_count: frames.len() as u32,
+        duration_ms: frames
+            .last()
+            .map_or(0, |frame| frame.offset.as_millis() as u64),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("tuio-simulator-{}.tuiorec", name))
+    }
+
+    #[tokio::test]
+    async fn test_record_and_read() {
+        let path = temp_path("record");
+        let recorder = Recorder::new();
+        recorder.start(&path, RecordingLimits::default()).unwrap();
+        assert!(recorder.start(&path, RecordingLimits::default()).is_err());
+
+        recorder.record(1, &[1, 2, 3]).await;
+        recorder.record(2, &[4, 5]).await;
+        let status = recorder.stop().await.unwrap();
+        assert!(!status.active);
+        assert_eq!(status.frame_count, 2);
+        assert_eq!(status.bytes, std::fs::metadata(&path).unwrap().len());
+
+        let frames = read(&path).unwrap();
+        assert_eq!(info(&path).unwrap().frame_count, 2);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].frame_id, 1);
+        assert_eq!(frames[1].data, vec![4, 5]);
+        assert!(frames[0].offset <= frames[1].offset);
+    }
+
+    #[tokio::test]
+    async fn test_size_limit() {
+        let path = temp_path("limit");
+        let recorder = Recorder::new();
+        let limits = RecordingLimits {
+            max_bytes: Some(8 + 2 * (16 + 10)),
+            max_duration_ms: None,
+        };
+        recorder.start(&path, limits).unwrap();
+
+        assert!(recorder.record(1, &[0; 10]).await.is_none());
+        assert!(recorder.record(2, &[0; 10]).await.is_none());
+        let status = recorder.record(3, &[0; 10]).await.unwrap();
+        assert!(status.limit_reached);
+        assert!(!status.active);
+        assert!(recorder.status().is_none());
+
+        let frames = read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(frames.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_duration_limit_without_frames() {
+        let path = temp_path("duration");
+        let state = AppState::new();
+        let mut events = state.events.subscribe();
+        let limits = RecordingLimits {
+            max_bytes: None,
+            max_duration_ms: Some(50),
+        };
+        start(&state, &path, limits).unwrap();
+
+        // No frames are recorded, the timer still ends the recording
+        let event = tokio::time::timeout(Duration::from_secs(10), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!state.recorder.is_recording());
+        match event {
+            SimulatorEvent::RecordingProgress(status) => {
+                assert!(status.limit_reached);
+                assert!(!status.active);
+                assert_eq!(status.bytes, std::fs::metadata(&path).unwrap().len());
+            }
+            _ => panic!("Expected recording progress"),
+        }
+
+        assert!(read(&path).unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_read_without_index() {
+        let mut data = b"TUIOREC\x01".to_vec();
+        data.extend_from_slice(&1000u64.to_be_bytes());
+        data.extend_from_slice(&7u32.to_be_bytes());
+        data.extend_from_slice(&2u32.to_be_bytes());
+        data.extend_from_slice(&[9, 9]);
+        // A frame cut off while writing
+        data.extend_from_slice(&2000u64.to_be_bytes());
+
+        let frames = parse(&data).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].frame_id, 7);
+        assert_eq!(frames[0].offset, Duration::from_millis(1));
+
+        assert!(parse(b"OSC").is_err());
+    }
+
+    /// Session file data with one frame and its index
+    fn indexed_file() -> Vec<u8> {
+        let mut data = b"TUIOREC\x01".to_vec();
+        data.extend_from_slice(&1000u64.to_be_bytes());
+        data.extend_from_slice(&7u32.to_be_bytes());
+        data.extend_from_slice(&2u32.to_be_bytes());
+        data.extend_from_slice(&[9, 9]);
+        let index_position = data.len() as u64;
+        data.extend_from_slice(&1000u64.to_be_bytes());
+        data.extend_from_slice(&8u64.to_be_bytes());
+        data.extend_from_slice(&index_position.to_be_bytes());
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.extend_from_slice(INDEX_MAGIC);
+        data
+    }
+
+    #[test]
+    fn test_read_truncated_trailer() {
+        let data = indexed_file();
+        assert_eq!(parse(&data).unwrap().len(), 1);
+
+        // Without the magic, the frames are scanned instead
+        let frames = parse(&data[..data.len() - 6]).unwrap();
+        assert_eq!(frames[0].frame_id, 7);
+    }
+
+    #[test]
+    fn test_read_corrupt_trailer() {
+        let trailer = indexed_file().len() - TRAILER_SIZE;
+
+        let mut data = indexed_file();
+        data[trailer..trailer + 8].copy_from_slice(&u64::MAX.to_be_bytes());
+        assert!(parse(&data).is_err());
+
+        let mut data = indexed_file();
+        data[trailer + 8..trailer + 12].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(parse(&data).is_err());
+
+        // An index entry pointing past the end of the file
+        let mut data = indexed_file();
+        data[trailer - 8..trailer].copy_from_slice(&u64::MAX.to_be_bytes());
+        assert!(parse(&data).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_drops_frames() {
+        // Nothing drains the queue, so it stays full after one frame
+        let (chunks, _receiver) = mpsc::channel(1);
+        let (_done_sender, done) = oneshot::channel();
+        let now = Instant::now();
+        let mut recording = ActiveRecording {
+            id: 1,
+            path: "queue".to_string(),
+            chunks,
+            done,
+            error: Arc::new(Mutex::new(None)),
+            timer: None,
+            limits: RecordingLimits::default(),
+            started: now,
+            last_progress: now,
+            bytes: 8,
+            dropped_frames: 0,
+            index: Vec::new(),
+        };
+
+        recording.write_frame(0, 1, &[1]).unwrap();
+        recording.write_frame(10, 2, &[2]).unwrap();
+        let status = recording.status(true);
+        assert_eq!(status.frame_count, 1);
+        assert_eq!(status.dropped_frames, 1);
+        assert_eq!(status.bytes, 8 + 16 + 1);
+    }
+}
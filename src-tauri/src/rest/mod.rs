@@ -0,0 +1,505 @@
+use crate::actions;
+use crate::engine;
+use crate::state::{AppState, PointerType, ServerStatus, TuioObject};
+use crate::transport::bind::{bind_listener, BindAddress, StartError};
+use crate::tuio::json::decode_packet;
+use anyhow::{anyhow, Result};
+use axum::extract::{Path, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use tokio::task::JoinHandle;
+
+/// Embedded HTTP server exposing the simulator as a REST API
+pub struct ApiServer {
+    local_addr: Mutex<Option<SocketAddr>>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl ApiServer {
+    pub fn new() -> Self {
+        Self {
+            local_addr: Mutex::new(None),
+            task: Mutex::new(None),
+        }
+    }
+
+    /// Start serving on the given address, stopping a running server first
+    pub async fn start(&self, state: AppState, addr: SocketAddr) -> Result<()> {
+        self.stop();
+
+        let listener = bind_listener(addr).await?;
+        let local_addr = listener.local_addr()?;
+        let app = router(state);
+
+        let task = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                eprintln!("HTTP API error: {}", e);
+            }
+        });
+
+        *self.task.lock() = Some(task);
+        *self.local_addr.lock() = Some(local_addr);
+
+        Ok(())
+    }
+
+    pub fn stop(&self) {
+        if let Some(task) = self.task.lock().take() {
+            task.abort();
+        }
+        *self.local_addr.lock() = None;
+    }
+
+    /// Address the server is listening on, if running
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        *self.local_addr.lock()
+    }
+}
+
+impl Default for ApiServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Address the HTTP API binds to, only reachable from this machine
+pub fn api_address(port: u16) -> SocketAddr {
+    SocketAddr::new(BindAddress::Localhost.ip(), port)
+}
+
+/// Error response with a `{ message }` body
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn not_found(session_id: u32) -> Self {
+        Self::new(
+            StatusCode::NOT_FOUND,
+            format!("Object with session_id {} not found", session_id),
+        )
+    }
+}
+
+/// Errors of the shared actions are reported as bad requests
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(json!({ "message": self.message }))).into_response()
+    }
+}
+
+/// Start errors keep their `{ kind, sink, address, message }` body
+struct StartFailed(StartError);
+
+impl IntoResponse for StartFailed {
+    fn into_response(self) -> Response {
+        let status = match self.0.kind() {
+            "already_running" | "port_in_use" => StatusCode::CONFLICT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self.0)).into_response()
+    }
+}
+
+#[derive(Deserialize)]
+struct AddObjectBody {
+    component_id: u16,
+    x: f32,
+    y: f32,
+}
+
+#[derive(Deserialize)]
+struct AddPointerBody {
+    pointer_type: PointerType,
+    x: f32,
+    y: f32,
+}
+
+#[derive(Deserialize)]
+struct AddBlobBody {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+#[derive(Deserialize)]
+struct UpdateObjectBody {
+    x: f32,
+    y: f32,
+    angle: f32,
+}
+
+#[derive(Deserialize)]
+struct UpdatePointerBody {
+    x: f32,
+    y: f32,
+    angle: f32,
+    shear: f32,
+    radius: f32,
+    pressure: f32,
+}
+
+#[derive(Deserialize)]
+struct StartServerBody {
+    port: u16,
+    #[serde(default)]
+    bind_address: Option<BindAddress>,
+}
+
+#[derive(Deserialize)]
+struct FrameRateBody {
+    fps: u32,
+}
+
+#[derive(Deserialize)]
+struct DimensionsBody {
+    width: u16,
+    height: u16,
+}
+
+fn router(state: AppState) -> Router {
+    let write = Router::new()
+        .route("/objects", post(add_object))
+        .route("/objects/:id", put(update_object).delete(remove_object))
+        .route("/pointers", post(add_pointer))
+        .route("/pointers/:id", put(update_pointer))
+        .route("/blobs", post(add_blob))
+        .route("/server/start", post(start_server))
+        .route("/server/stop", post(stop_server))
+        .route("/fps", put(set_frame_rate))
+        .route("/dimensions", put(set_canvas_dimensions))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_token));
+
+    Router::new()
+        .route("/objects", get(get_objects))
+        .route("/objects/:id", get(get_object))
+        .route("/server/status", get(server_status))
+        .route("/bundle/last", get(last_bundle))
+        .merge(write)
+        .with_state(state)
+}
+
+/// Require `Authorization: Bearer <token>` if a control token is set
+async fn require_token(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let expected = state.config.lock().control_token.clone();
+    if let Some(token) = expected {
+        let provided = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if provided != Some(token.as_str()) {
+            return Err(ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "A valid control token is required",
+            ));
+        }
+    }
+
+    Ok(next.run(request).await)
+}
+
+fn require_object(state: &AppState, session_id: u32) -> Result<(), ApiError> {
+    if state.objects.lock().contains_key(&session_id) {
+        Ok(())
+    } else {
+        Err(ApiError::not_found(session_id))
+    }
+}
+
+async fn get_objects(State(state): State<AppState>) -> Json<Vec<TuioObject>> {
+    Json(state.objects.lock().values().cloned().collect())
+}
+
+async fn get_object(
+    State(state): State<AppState>,
+    Path(session_id): Path<u32>,
+) -> Result<Json<TuioObject>, ApiError> {
+    state
+        .objects
+        .lock()
+        .get(&session_id)
+        .cloned()
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(session_id))
+}
+
+fn created(session_id: u32) -> Response {
+    (
+        StatusCode::CREATED,
+        Json(json!({ "session_id": session_id })),
+    )
+        .into_response()
+}
+
+async fn add_object(
+    State(state): State<AppState>,
+    Json(body): Json<AddObjectBody>,
+) -> Result<Response, ApiError> {
+    let session_id = actions::add_object(&state, body.component_id, body.x, body.y)?;
+    Ok(created(session_id))
+}
+
+async fn add_pointer(
+    State(state): State<AppState>,
+    Json(body): Json<AddPointerBody>,
+) -> Result<Response, ApiError> {
+    let session_id = actions::add_pointer(&state, body.pointer_type, body.x, body.y)?;
+    Ok(created(session_id))
+}
+
+async fn add_blob(
+    State(state): State<AppState>,
+    Json(body): Json<AddBlobBody>,
+) -> Result<Response, ApiError> {
+    let session_id = actions::add_blob(&state, body.x, body.y, body.width, body.height)?;
+    Ok(created(session_id))
+}
+
+async fn update_object(
+    State(state): State<AppState>,
+    Path(session_id): Path<u32>,
+    Json(body): Json<UpdateObjectBody>,
+) -> Result<StatusCode, ApiError> {
+    require_object(&state, session_id)?;
+    actions::update_object(&state, session_id, body.x, body.y, body.angle)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn update_pointer(
+    State(state): State<AppState>,
+    Path(session_id): Path<u32>,
+    Json(body): Json<UpdatePointerBody>,
+) -> Result<StatusCode, ApiError> {
+    require_object(&state, session_id)?;
+    actions::update_pointer(
+        &state,
+        session_id,
+        body.x,
+        body.y,
+        body.angle,
+        body.shear,
+        body.radius,
+        body.pressure,
+    )?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn remove_object(
+    State(state): State<AppState>,
+    Path(session_id): Path<u32>,
+) -> Result<StatusCode, ApiError> {
+    require_object(&state, session_id)?;
+    actions::remove_object(&state, session_id)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn start_server(
+    State(state): State<AppState>,
+    Json(body): Json<StartServerBody>,
+) -> Result<StatusCode, StartFailed> {
+    engine::start_server(&state, body.port, body.bind_address)
+        .await
+        .map_err(StartFailed)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn stop_server(State(state): State<AppState>) -> StatusCode {
+    engine::stop_server(&state).await;
+    StatusCode::NO_CONTENT
+}
+
+async fn server_status(State(state): State<AppState>) -> Json<ServerStatus> {
+    Json(actions::server_status(&state))
+}
+
+async fn set_frame_rate(
+    State(state): State<AppState>,
+    Json(body): Json<FrameRateBody>,
+) -> Result<StatusCode, ApiError> {
+    actions::set_frame_rate(&state, body.fps)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn set_canvas_dimensions(
+    State(state): State<AppState>,
+    Json(body): Json<DimensionsBody>,
+) -> Result<StatusCode, ApiError> {
+    actions::set_canvas_dimensions(&state, body.width, body.height)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The most recently generated bundle, decoded to JSON
+async fn last_bundle(State(state): State<AppState>) -> Result<Json<Value>, ApiError> {
+    let data = state
+        .last_bundle
+        .lock()
+        .clone()
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No bundle has been sent yet"))?;
+
+    decode_packet(&data)
+        .map(Json)
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Start or stop the HTTP API according to the config
+pub async fn apply_config(state: &AppState) -> Result<()> {
+    let (enabled, port) = {
+        let config = state.config.lock();
+        (config.http_api_enabled, config.http_api_port)
+    };
+
+    if !enabled {
+        state.api_server.stop();
+        return Ok(());
+    }
+
+    state
+        .api_server
+        .start(state.clone(), api_address(port))
+        .await
+        .map_err(|e| anyhow!("Failed to start HTTP API: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    /// Send a request and return the status code and JSON body
+    async fn request(
+        addr: SocketAddr,
+        method: &str,
+        path: &str,
+        body: Option<Value>,
+        token: Option<&str>,
+    ) -> (u16, Value) {
+        let body = body.map(|body| body.to_string()).unwrap_or_default();
+        let auth = token
+            .map(|token| format!("Authorization: Bearer {}\r\n", token))
+            .unwrap_or_default();
+        let request = format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n{}\
+             Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            auth,
+            body.len(),
+            body
+        );
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        let status = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, serde_json::from_str(body).unwrap_or(Value::Null))
+    }
+
+    async fn start_api(state: &AppState) -> SocketAddr {
+        state
+            .api_server
+            .start(state.clone(), api_address(0))
+            .await
+            .unwrap();
+        state.api_server.local_addr().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_object_crud() {
+        let state = AppState::new();
+        let addr = start_api(&state).await;
+
+        let body = json!({ "component_id": 3, "x": 0.5, "y": 0.5 });
+        let (status, created) = request(addr, "POST", "/objects", Some(body), None).await;
+        assert_eq!(status, 201);
+        let id = created["session_id"].as_u64().unwrap();
+
+        let body = json!({ "x": 0.25, "y": 0.75, "angle": 1.0 });
+        let path = format!("/objects/{}", id);
+        let (status, _) = request(addr, "PUT", &path, Some(body), None).await;
+        assert_eq!(status, 204);
+
+        let (status, object) = request(addr, "GET", &path, None, None).await;
+        assert_eq!(status, 200);
+        assert_eq!(object["y"], 0.75);
+
+        let (status, _) = request(addr, "DELETE", &path, None, None).await;
+        assert_eq!(status, 204);
+        let (status, error) = request(addr, "GET", &path, None, None).await;
+        assert_eq!(status, 404);
+        assert!(error["message"].as_str().unwrap().contains("not found"));
+
+        let body = json!({ "component_id": 30, "x": 0.5, "y": 0.5 });
+        let (status, _) = request(addr, "POST", "/objects", Some(body), None).await;
+        assert_eq!(status, 400);
+
+        state.api_server.stop();
+        assert!(state.api_server.local_addr().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_control_token() {
+        let state = AppState::new();
+        state.config.lock().control_token = Some("secret".to_string());
+        let addr = start_api(&state).await;
+
+        let body = json!({ "fps": 30 });
+        let (status, _) = request(addr, "PUT", "/fps", Some(body.clone()), None).await;
+        assert_eq!(status, 401);
+        let (status, _) = request(addr, "PUT", "/fps", Some(body), Some("secret")).await;
+        assert_eq!(status, 204);
+        assert_eq!(state.config.lock().fps, 30);
+
+        // Reading does not require the token
+        let (status, status_body) = request(addr, "GET", "/server/status", None, None).await;
+        assert_eq!(status, 200);
+        assert_eq!(status_body["http_api_address"], addr.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_last_bundle() {
+        let state = AppState::new();
+        let addr = start_api(&state).await;
+
+        let (status, _) = request(addr, "GET", "/bundle/last", None, None).await;
+        assert_eq!(status, 404);
+
+        actions::add_object(&state, 1, 0.5, 0.5).unwrap();
+        let data = crate::tuio::frame::generate_frame(&state).unwrap();
+        *state.last_bundle.lock() = Some(data);
+
+        let (status, bundle) = request(addr, "GET", "/bundle/last", None, None).await;
+        assert_eq!(status, 200);
+        assert!(bundle["elements"].as_array().unwrap().len() >= 3);
+    }
+}
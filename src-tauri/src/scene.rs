@@ -0,0 +1,557 @@
+use crate::actions;
+use crate::bus::ObjectChange;
+use crate::state::{
+    AppState, ContainerSlot, ControlValue, ObjectBounds, ObjectData, ObjectGeometry, ObjectKind,
+    ObjectLink, ObjectSymbol, Pose3D, TuioObject,
+};
+use crate::tuio::encoder::ProtocolVersion;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Version written to new scene files
+///
+/// Version 0 is the bare JSON array of objects returned by the
+/// `get_objects` control method.
+pub const SCENE_VERSION: u32 = 1;
+
+/// How a loaded scene is combined with the current one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadMode {
+    /// Remove all current objects and apply the scene settings
+    #[default]
+    Replace,
+    /// Add the objects to the current ones, keeping the current settings
+    Merge,
+}
+
+/// Scene file contents
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    pub version: u32,
+    /// Missing in scenes migrated from version 0
+    #[serde(default)]
+    pub settings: Option<SceneSettings>,
+    pub objects: Vec<SceneObject>,
+}
+
+/// Settings saved with a scene, transports are left out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneSettings {
+    pub fps: u32,
+    pub width: u16,
+    pub height: u16,
+    pub source: String,
+    pub protocol_version: ProtocolVersion,
+    pub enable_3d: bool,
+    pub depth: u16,
+}
+
+/// 3D pose of a scene object, without the derived motion
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScenePose {
+    pub z: f32,
+    pub roll: f32,
+    pub pitch: f32,
+    pub depth: f32,
+}
+
+/// Object of a scene, without velocities and the last_* fields
+///
+/// Session IDs are only used to resolve associations within the file,
+/// loaded objects get fresh ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneObject {
+    pub session_id: u32,
+    pub kind: ObjectKind,
+    pub type_id: u16,
+    pub user_id: u16,
+    pub component_id: u16,
+    pub x: f32,
+    pub y: f32,
+    pub angle: f32,
+    pub shear: f32,
+    pub radius: f32,
+    pub pressure: f32,
+    #[serde(default)]
+    pub bounds: Option<ObjectBounds>,
+    #[serde(default)]
+    pub symbol: Option<ObjectSymbol>,
+    #[serde(default)]
+    pub geometry: Option<ObjectGeometry>,
+    #[serde(default)]
+    pub controls: Vec<ControlValue>,
+    #[serde(default)]
+    pub data: Vec<ObjectData>,
+    #[serde(default)]
+    pub container: Option<ContainerSlot>,
+    #[serde(default)]
+    pub links: Vec<ObjectLink>,
+    #[serde(default)]
+    pub pose_3d: Option<ScenePose>,
+}
+
+impl From<&TuioObject> for SceneObject {
+    fn from(object: &TuioObject) -> Self {
+        Self {
+            session_id: object.session_id,
+            kind: object.kind,
+            type_id: object.type_id,
+            user_id: object.user_id,
+            component_id: object.component_id,
+            x: object.x,
+            y: object.y,
+            angle: object.angle,
+            shear: object.shear,
+            radius: object.radius,
+            pressure: object.pressure,
+            bounds: object.bounds,
+            symbol: object.symbol.clone(),
+            geometry: object.geometry.clone(),
+            controls: object.controls.clone(),
+            data: object.data.clone(),
+            container: object.container,
+            links: object.links.clone(),
+            pose_3d: object.pose_3d.map(|pose| ScenePose {
+                z: pose.z,
+                roll: pose.roll,
+                pitch: pose.pitch,
+                depth: pose.depth,
+            }),
+        }
+    }
+}
+
+impl SceneObject {
+    /// Create a resting object with the given session ID
+    ///
+    /// Associations still refer to the session IDs of the file.
+    fn to_object(&self, session_id: u32, timestamp: i64) -> TuioObject {
+        let mut object = TuioObject::new(
+            session_id,
+            self.type_id,
+            self.component_id,
+            self.x,
+            self.y,
+            timestamp,
+        );
+        object.kind = self.kind;
+        object.user_id = self.user_id;
+        object.angle = self.angle;
+        object.last_angle = self.angle;
+        object.shear = self.shear;
+        object.radius = self.radius;
+        object.pressure = self.pressure;
+        object.last_pressure = self.pressure;
+        object.bounds = self.bounds;
+        object.symbol = self.symbol.clone();
+        object.geometry = self.geometry.clone();
+        object.controls = self.controls.clone();
+        object.data = self.data.clone();
+        object.container = self.container;
+        object.links = self.links.clone();
+        object.pose_3d = self
+            .pose_3d
+            .map(|pose| Pose3D::new(pose.z, pose.roll, pose.pitch, pose.depth));
+        object
+    }
+}
+
+/// Capture the objects and settings of the current scene
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn capture(state: &AppState) -> Scene {
+    let config = state.config.lock().clone();
+    let mut objects: Vec<SceneObject> = state
+        .objects
+        .lock()
+        .values()
+        .map(SceneObject::from)
+        .collect();
+    objects.sort_by_key(|object| object.session_id);
+
+    Scene {
+        version: SCENE_VERSION,
+        settings: Some(SceneSettings {
+            fps: config.fps,
+            width: config.width,
+            height: config.height,
+            source: config.source,
+            protocol_version: config.protocol_version,
+            enable_3d: config.enable_3d,
+            depth: config.depth,
+        }),
+        objects,
+    }
+}
+
+/// Parse a scene file of any known version
+pub fn parse(contents: &str) -> Result<Scene> {
+    let value: Value = serde_json::from_str(contents)?;
+    migrate(value)
+}
+
+/// Bring the JSON of an older scene version to the current one
+fn migrate(value: Value) -> Result<Scene> {
+    // Version 0: a bare array of objects including derived fields
+    if value.is_array() {
+        let objects: Vec<TuioObject> = serde_json::from_value(value)?;
+        return Ok(Scene {
+            version: SCENE_VERSION,
+            settings: None,
+            objects: objects.iter().map(SceneObject::from).collect(),
+        });
+    }
+
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| anyhow!("Scene file has no version"))?;
+    if version > SCENE_VERSION as u64 {
+        bail!(
+            "Scene version {} is newer than the supported version {}",
+            version,
+            SCENE_VERSION
+        );
+    }
+
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Write the current scene to a file
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn save(state: &AppState, path: &Path) -> Result<()> {
+    let contents = serde_json::to_string_pretty(&capture(state))?;
+    std::fs::write(path, contents)
+        .with_context(|| format!("Failed to write scene file {}", path.display()))
+}
+
+/// Read a scene file, migrating older versions
+pub fn read(path: &Path) -> Result<Scene> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read scene file {}", path.display()))?;
+
+    parse(&contents).with_context(|| format!("Invalid scene file {}", path.display()))
+}
+
+/// Check a single scene object with the rules used when editing objects
+fn validate_object(object: &SceneObject) -> Result<(), String> {
+    actions::validate_position(object.x, object.y)?;
+
+    if object.kind == ObjectKind::Token {
+        actions::validate_component_id(object.component_id)?;
+    }
+    match &object.bounds {
+        Some(bounds) => actions::validate_bounds(bounds)?,
+        None if object.kind == ObjectKind::Blob => return Err("A blob needs bounds".to_string()),
+        None => {}
+    }
+
+    if let Some(symbol) = &object.symbol {
+        actions::validate_symbol(symbol)?;
+    }
+    if let Some(geometry) = &object.geometry {
+        actions::validate_geometry(geometry)?;
+    }
+    if let Some(pose) = &object.pose_3d {
+        actions::validate_pose_3d(pose.z, pose.depth)?;
+    }
+    actions::validate_controls(&object.controls)?;
+    actions::validate_data(&object.data)?;
+
+    for link in &object.links {
+        actions::validate_link(object.session_id, link.target_id)?;
+    }
+
+    Ok(())
+}
+
+/// Check a scene before any of it is applied
+fn validate(scene: &Scene, mode: LoadMode) -> Result<()> {
+    let mut objects = HashMap::new();
+    let mut component_ids = HashSet::new();
+    for object in &scene.objects {
+        if objects.insert(object.session_id, object).is_some() {
+            bail!(
+                "Session ID {} is used by more than one object in the scene",
+                object.session_id
+            );
+        }
+
+        if object.kind == ObjectKind::Token && !component_ids.insert(object.component_id) {
+            bail!(
+                "Component ID {} is used by more than one token in the scene",
+                object.component_id
+            );
+        }
+
+        validate_object(object).map_err(|e| anyhow!("Object {}: {}", object.session_id, e))?;
+    }
+
+    for object in &scene.objects {
+        if let Some(slot) = object.container {
+            actions::validate_container(object.session_id, slot.container_id, |id| {
+                objects
+                    .get(&id)
+                    .and_then(|object| object.container)
+                    .map(|c| c.container_id)
+            })
+            .map_err(|e| anyhow!("Object {}: {}", object.session_id, e))?;
+        }
+    }
+
+    if let (LoadMode::Replace, Some(settings)) = (mode, &scene.settings) {
+        actions::validate_frame_rate(settings.fps).map_err(|e| anyhow!(e))?;
+        actions::validate_canvas_dimensions(settings.width, settings.height)
+            .map_err(|e| anyhow!(e))?;
+    }
+
+    Ok(())
+}
+
+/// Add the objects of a scene with fresh session IDs
+///
+/// Returns the new session IDs in the order of the scene objects.
+/// Associations to objects missing from the scene are dropped. The
+/// scene is validated first, so a failing load changes nothing.
+pub fn apply(state: &AppState, scene: Scene, mode: LoadMode) -> Result<Vec<u32>> {
+    validate(&scene, mode)?;
+
+    if mode == LoadMode::Replace {
+        if let Some(settings) = &scene.settings {
+            actions::set_frame_rate(state, settings.fps).map_err(|e| anyhow!(e))?;
+            actions::set_canvas_dimensions(state, settings.width, settings.height)
+                .map_err(|e| anyhow!(e))?;
+
+            let mut config = state.config.lock();
+            config.source = settings.source.clone();
+            config.protocol_version = settings.protocol_version;
+            config.enable_3d = settings.enable_3d;
+            config.depth = settings.depth;
+        }
+
+        let removed: Vec<u32> = state.objects.lock().keys().copied().collect();
+        for session_id in removed {
+            state.remove_object(session_id);
+            state.object_changed(session_id, ObjectChange::Removed);
+        }
+    } else {
+        // Token component IDs stay unique, as when adding objects
+        let objects = state.objects.lock();
+        for object in &scene.objects {
+            let in_use = object.kind == ObjectKind::Token
+                && objects.values().any(|current| {
+                    current.kind == ObjectKind::Token && current.component_id == object.component_id
+                });
+            if in_use {
+                bail!("Component ID {} is already in use", object.component_id);
+            }
+        }
+    }
+
+    let ids: HashMap<u32, u32> = scene
+        .objects
+        .iter()
+        .map(|object| (object.session_id, state.allocate_session_id()))
+        .collect();
+    let timestamp = chrono::Utc::now().timestamp_millis();
+
+    let mut session_ids = Vec::with_capacity(scene.objects.len());
+    let mut objects = state.objects.lock();
+    for scene_object in &scene.objects {
+        let session_id = ids[&scene_object.session_id];
+        let mut object = scene_object.to_object(session_id, timestamp);
+
+        object.container = object.container.and_then(|slot| {
+            ids.get(&slot.container_id)
+                .map(|&container_id| ContainerSlot {
+                    container_id,
+                    slot: slot.slot,
+                })
+        });
+        object.links = object
+            .links
+            .iter()
+            .filter_map(|link| {
+                ids.get(&link.target_id)
+                    .map(|&target_id| ObjectLink { target_id, ..*link })
+            })
+            .collect();
+
+        objects.insert(session_id, object);
+        session_ids.push(session_id);
+    }
+    drop(objects);
+
+    for &session_id in &session_ids {
+        state.object_changed(session_id, ObjectChange::Added);
+    }
+
+    Ok(session_ids)
+}
+
+/// Read a scene file and add its objects to the state
+pub fn load(state: &AppState, path: &Path, mode: LoadMode) -> Result<Vec<u32>> {
+    apply(state, read(path)?, mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load_scene() {
+        let state = AppState::new();
+        let container = actions::add_object(&state, 1, 0.5, 0.5).unwrap();
+        let token = actions::add_object(&state, 2, 0.25, 0.75).unwrap();
+        {
+            let mut objects = state.objects.lock();
+            let object = objects.get_mut(&token).unwrap();
+            object.container = Some(ContainerSlot {
+                container_id: container,
+                slot: 3,
+            });
+            object.x_vel = 2.0;
+        }
+        state.config.lock().width = 800;
+
+        let path = std::env::temp_dir().join(format!(
+            "tuio-simulator-scene-{}.json",
+            uuid::Uuid::new_v4()
+        ));
+        save(&state, &path).unwrap();
+
+        let loaded = AppState::new();
+        actions::add_object(&loaded, 5, 0.1, 0.1).unwrap();
+        let ids = load(&loaded, &path, LoadMode::Replace).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(loaded.config.lock().width, 800);
+        let objects = loaded.objects.lock();
+        assert_eq!(objects.len(), 2);
+        let object = &objects[&2];
+        assert_eq!(object.y, 0.75);
+        assert_eq!(object.x_vel, 0.0);
+        assert_eq!(object.container.unwrap().container_id, 1);
+    }
+
+    #[test]
+    fn test_merge_scene() {
+        let state = AppState::new();
+        actions::add_object(&state, 1, 0.5, 0.5).unwrap();
+        let scene = capture(&state);
+
+        // The same tokens cannot be added twice
+        assert!(apply(&state, scene.clone(), LoadMode::Merge).is_err());
+
+        state.objects.lock().clear();
+        actions::add_pointer(&state, crate::state::PointerType::Finger, 0.2, 0.2).unwrap();
+        let ids = apply(&state, scene, LoadMode::Merge).unwrap();
+        assert_eq!(ids, vec![2]);
+        assert_eq!(state.objects.lock().len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_scene_changes_nothing() {
+        let state = AppState::new();
+        actions::add_object(&state, 1, 0.5, 0.5).unwrap();
+
+        let mut scene = capture(&state);
+        let settings = scene.settings.as_mut().unwrap();
+        settings.fps = 30;
+        settings.width = 0;
+        assert!(apply(&state, scene, LoadMode::Replace).is_err());
+        assert_eq!(state.config.lock().fps, 60);
+        assert_eq!(state.objects.lock().len(), 1);
+
+        // Tokens sharing a component ID within the file
+        let mut scene = capture(&state);
+        scene.objects.push(scene.objects[0].clone());
+        scene.objects[1].session_id = 2;
+        assert!(apply(&state, scene.clone(), LoadMode::Replace).is_err());
+        state.objects.lock().clear();
+        assert!(apply(&state, scene, LoadMode::Merge).is_err());
+        assert!(state.objects.lock().is_empty());
+    }
+
+    #[test]
+    fn test_migrate_version_0() {
+        let objects = vec![
+            TuioObject::new(4, 1, 1, 0.5, 0.5, 0),
+            TuioObject::new(7, 2, 2, 0.25, 0.75, 0),
+        ];
+        let scene = parse(&serde_json::to_string(&objects).unwrap()).unwrap();
+
+        assert_eq!(scene.version, SCENE_VERSION);
+        assert!(scene.settings.is_none());
+        assert_eq!(scene.objects[1].session_id, 7);
+        assert_eq!(scene.objects[1].y, 0.75);
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        assert!(parse(r#"{"version":99,"objects":[]}"#).is_err());
+        assert!(parse(r#"{"objects":[]}"#).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_session_ids() {
+        let state = AppState::new();
+        actions::add_object(&state, 1, 0.5, 0.5).unwrap();
+        actions::add_pointer(&state, crate::state::PointerType::Finger, 0.2, 0.2).unwrap();
+
+        let mut scene = capture(&state);
+        scene.objects[1].session_id = scene.objects[0].session_id;
+        let error = apply(&state, scene, LoadMode::Replace).unwrap_err();
+        assert!(error.to_string().contains("Session ID"));
+        assert_eq!(state.objects.lock().len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_objects() {
+        let state = AppState::new();
+        actions::add_object(&state, 1, 0.5, 0.5).unwrap();
+        actions::add_blob(&state, 0.5, 0.5, 0.2, 0.2).unwrap();
+        let scene = capture(&state);
+        assert!(validate(&scene, LoadMode::Replace).is_ok());
+
+        let invalid = |change: fn(&mut Vec<SceneObject>)| {
+            let mut scene = scene.clone();
+            change(&mut scene.objects);
+            validate(&scene, LoadMode::Replace).is_err()
+        };
+        assert!(invalid(|objects| objects[0].component_id = 25));
+        assert!(invalid(|objects| objects[1].bounds = None));
+        assert!(invalid(|objects| {
+            objects[1].bounds = Some(ObjectBounds::new(2.0, 0.1));
+        }));
+        assert!(invalid(|objects| {
+            objects[0].links = vec![ObjectLink {
+                target_id: objects[0].session_id,
+                physical: true,
+                out_port: 0,
+                in_port: 0,
+            }];
+        }));
+        assert!(invalid(|objects| {
+            objects[0].container = Some(ContainerSlot {
+                container_id: objects[0].session_id,
+                slot: 0,
+            });
+        }));
+        assert!(invalid(|objects| {
+            let (first, second) = (objects[0].session_id, objects[1].session_id);
+            objects[0].container = Some(ContainerSlot {
+                container_id: second,
+                slot: 0,
+            });
+            objects[1].container = Some(ContainerSlot {
+                container_id: first,
+                slot: 0,
+            });
+        }));
+    }
+}
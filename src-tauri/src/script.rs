@@ -0,0 +1,448 @@
+use crate::actions;
+use crate::bus::SimulatorEvent;
+use crate::engine::frame_period;
+use crate::motion::Motion;
+use crate::state::{AppState, PointerType};
+use anyhow::{anyhow, Context, Result};
+use parking_lot::Mutex;
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Position};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tokio::sync::broadcast::{self, error::RecvError, error::TryRecvError};
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Stops running scripts on request
+pub struct ScriptControl {
+    generation: watch::Sender<u64>,
+}
+
+impl ScriptControl {
+    pub fn new() -> Self {
+        Self {
+            generation: watch::channel(0).0,
+        }
+    }
+
+    /// Stop all scripts running right now
+    pub fn stop_all(&self) {
+        self.generation.send_modify(|generation| *generation += 1);
+    }
+
+    fn subscribe(&self) -> watch::Receiver<u64> {
+        self.generation.subscribe()
+    }
+}
+
+impl Default for ScriptControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn stopped() -> Box<EvalAltResult> {
+    "The script was stopped".into()
+}
+
+/// Clock of a script, advanced by waiting
+///
+/// Script time only moves forward by the waited time, so a script does
+/// not drift however long its own statements take. After waiting, the
+/// script continues right after the next frame was sent.
+struct ScriptClock {
+    state: AppState,
+    runtime: Handle,
+    started: Instant,
+    elapsed: Duration,
+    frames: broadcast::Receiver<SimulatorEvent>,
+    stop: watch::Receiver<u64>,
+}
+
+impl ScriptClock {
+    /// Run a future, unless the script is stopped first
+    fn block_on<T>(&mut self, future: impl std::future::Future<Output = T>) -> ScriptResult<T> {
+        let stop = &mut self.stop;
+        self.runtime.block_on(async {
+            tokio::select! {
+                _ = stop.changed() => Err(stopped()),
+                value = future => Ok(value),
+            }
+        })
+    }
+
+    /// Forget frames sent before now
+    fn skip_sent_frames(&mut self) {
+        while !matches!(
+            self.frames.try_recv(),
+            Err(TryRecvError::Empty | TryRecvError::Closed)
+        ) {}
+    }
+
+    fn wait(&mut self, ms: i64) -> ScriptResult<()> {
+        let ms = u64::try_from(ms).map_err(|_| "Wait time must not be negative")?;
+        self.elapsed += Duration::from_millis(ms);
+        let deadline = self.started + self.elapsed;
+        self.block_on(tokio::time::sleep_until(deadline))?;
+
+        if !*self.state.server_running.lock() {
+            return Ok(());
+        }
+
+        // Frames are skipped without clients, so do not wait for long
+        let timeout = frame_period(self.state.config.lock().fps) * 2;
+        self.skip_sent_frames();
+        let frames = &mut self.frames;
+        let next_frame = tokio::time::timeout(timeout, next_frame(frames));
+        self.runtime.block_on(next_frame).ok();
+        Ok(())
+    }
+
+    fn wait_frames(&mut self, count: i64) -> ScriptResult<()> {
+        if !*self.state.server_running.lock() {
+            return Err("The server is not running".into());
+        }
+
+        self.skip_sent_frames();
+        for _ in 0..count {
+            let frames = &mut self.frames;
+            let stop = &mut self.stop;
+            self.runtime.block_on(async {
+                tokio::select! {
+                    _ = stop.changed() => Err(stopped()),
+                    _ = next_frame(frames) => Ok(()),
+                }
+            })?;
+        }
+
+        // Keep the script clock in step with the frames waited for
+        self.elapsed = self.started.elapsed();
+        Ok(())
+    }
+}
+
+async fn next_frame(frames: &mut broadcast::Receiver<SimulatorEvent>) {
+    loop {
+        match frames.recv().await {
+            Ok(SimulatorEvent::FrameSent(_)) | Err(RecvError::Closed) => return,
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+        }
+    }
+}
+
+fn session_id(id: i64) -> ScriptResult<u32> {
+    u32::try_from(id).map_err(|_| format!("Invalid session ID {}", id).into())
+}
+
+fn create_engine(
+    state: &AppState,
+    runtime: Handle,
+    stop: watch::Receiver<u64>,
+    on_print: impl Fn(&str) + Send + Sync + 'static,
+) -> Engine {
+    let mut engine = Engine::new();
+
+    // Scripts only get the functions below, no modules or files
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine.set_max_string_size(1 << 20);
+    engine.set_max_array_size(100_000);
+    engine.set_max_map_size(100_000);
+    engine.on_print(on_print);
+
+    let progress_stop = stop.clone();
+    engine.on_progress(move |_| match progress_stop.has_changed() {
+        Ok(false) => None,
+        _ => Some(Dynamic::from("The script was stopped")),
+    });
+
+    let clock = Arc::new(Mutex::new(ScriptClock {
+        state: state.clone(),
+        runtime,
+        started: Instant::now(),
+        elapsed: Duration::ZERO,
+        frames: state.events.subscribe(),
+        stop,
+    }));
+
+    let c = clock.clone();
+    engine.register_fn("wait", move |ms: i64| c.lock().wait(ms));
+    let c = clock.clone();
+    engine.register_fn("wait_frames", move |count: i64| c.lock().wait_frames(count));
+    let c = clock.clone();
+    engine.register_fn("now", move || c.lock().elapsed.as_millis() as i64);
+
+    let s = state.clone();
+    engine.register_fn("frame", move || *s.frame_counter.lock() as i64);
+
+    let s = state.clone();
+    engine.register_fn(
+        "add_token",
+        move |component_id: i64, x: f64, y: f64| -> ScriptResult<i64> {
+            let component_id =
+                u16::try_from(component_id).map_err(|_| "Component ID must be in range [1, 24]")?;
+            let session_id = actions::add_object(&s, component_id, x as f32, y as f32)?;
+            Ok(session_id as i64)
+        },
+    );
+    let s = state.clone();
+    engine.register_fn("add_pointer", move |x: f64, y: f64| -> ScriptResult<i64> {
+        let session_id = actions::add_pointer(&s, PointerType::Finger, x as f32, y as f32)?;
+        Ok(session_id as i64)
+    });
+    let s = state.clone();
+    engine.register_fn(
+        "add_pointer",
+        move |pointer_type: &str, x: f64, y: f64| -> ScriptResult<i64> {
+            let pointer_type: PointerType =
+                serde_json::from_value(serde_json::Value::from(pointer_type))
+                    .map_err(|_| format!("Unknown pointer type {}", pointer_type))?;
+            let session_id = actions::add_pointer(&s, pointer_type, x as f32, y as f32)?;
+            Ok(session_id as i64)
+        },
+    );
+    let s = state.clone();
+    engine.register_fn(
+        "add_blob",
+        move |x: f64, y: f64, width: f64, height: f64| -> ScriptResult<i64> {
+            let session_id =
+                actions::add_blob(&s, x as f32, y as f32, width as f32, height as f32)?;
+            Ok(session_id as i64)
+        },
+    );
+    let s = state.clone();
+    engine.register_fn(
+        "update_object",
+        move |id: i64, x: f64, y: f64, angle: f64| -> ScriptResult<()> {
+            Ok(actions::update_object(
+                &s,
+                session_id(id)?,
+                x as f32,
+                y as f32,
+                angle as f32,
+            )?)
+        },
+    );
+    let s = state.clone();
+    engine.register_fn(
+        "update_pointer",
+        move |id: i64,
+              x: f64,
+              y: f64,
+              angle: f64,
+              shear: f64,
+              radius: f64,
+              pressure: f64|
+              -> ScriptResult<()> {
+            Ok(actions::update_pointer(
+                &s,
+                session_id(id)?,
+                x as f32,
+                y as f32,
+                angle as f32,
+                shear as f32,
+                radius as f32,
+                pressure as f32,
+            )?)
+        },
+    );
+    let s = state.clone();
+    engine.register_fn("remove_object", move |id: i64| -> ScriptResult<()> {
+        Ok(actions::remove_object(&s, session_id(id)?)?)
+    });
+    let s = state.clone();
+    engine.register_fn("get_object", move |id: i64| -> ScriptResult<Dynamic> {
+        let id = session_id(id)?;
+        let object = s
+            .objects
+            .lock()
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| format!("Object with session_id {} not found", id))?;
+        rhai::serde::to_dynamic(object)
+    });
+    let s = state.clone();
+    engine.register_fn("objects", move || -> Array {
+        let mut ids: Vec<u32> = s.objects.lock().keys().copied().collect();
+        ids.sort_unstable();
+        ids.into_iter().map(|id| Dynamic::from(id as i64)).collect()
+    });
+    let s = state.clone();
+    engine.register_fn(
+        "attach_motion",
+        move |id: i64, motion: Dynamic| -> ScriptResult<()> {
+            let motion: Motion = rhai::serde::from_dynamic(&motion)?;
+            Ok(actions::attach_motion(&s, session_id(id)?, motion)?)
+        },
+    );
+    let s = state.clone();
+    engine.register_fn("detach_motion", move |id: i64| -> ScriptResult<()> {
+        Ok(actions::detach_motion(&s, session_id(id)?)?)
+    });
+    let s = state.clone();
+    engine.register_fn("set_fps", move |fps: i64| -> ScriptResult<()> {
+        let fps = u32::try_from(fps).map_err(|_| "FPS must be in range [1, 120]")?;
+        Ok(actions::set_frame_rate(&s, fps)?)
+    });
+    let s = state.clone();
+    engine.register_fn(
+        "set_dimensions",
+        move |width: i64, height: i64| -> ScriptResult<()> {
+            let width = u16::try_from(width).map_err(|_| "Invalid canvas width")?;
+            let height = u16::try_from(height).map_err(|_| "Invalid canvas height")?;
+            Ok(actions::set_canvas_dimensions(&s, width, height)?)
+        },
+    );
+
+    engine
+}
+
+/// Prefix an error message with the script name and position
+fn located(name: &str, position: Position, message: impl std::fmt::Display) -> anyhow::Error {
+    match (position.line(), position.position()) {
+        (Some(line), Some(column)) => anyhow!("{}:{}:{}: {}", name, line, column, message),
+        (Some(line), None) => anyhow!("{}:{}: {}", name, line, message),
+        _ => anyhow!("{}: {}", name, message),
+    }
+}
+
+fn execute(
+    state: &AppState,
+    runtime: Handle,
+    stop: watch::Receiver<u64>,
+    name: &str,
+    source: &str,
+    on_print: impl Fn(&str) + Send + Sync + 'static,
+) -> Result<()> {
+    let engine = create_engine(state, runtime, stop, on_print);
+
+    let ast = engine
+        .compile(source)
+        .map_err(|e| located(name, e.1, &e.0))?;
+
+    engine.run_ast(&ast).map_err(|mut e| {
+        let position = e.take_position();
+        located(name, position, e)
+    })
+}
+
+/// Run a Rhai script until it ends, fails or is stopped
+///
+/// Errors are prefixed with the name and the line and column they
+/// occurred at. Output of `print` is passed to `on_print`.
+pub async fn run(
+    state: &AppState,
+    name: &str,
+    source: String,
+    on_print: impl Fn(&str) + Send + Sync + 'static,
+) -> Result<()> {
+    let state = state.clone();
+    let name = name.to_string();
+    let runtime = Handle::current();
+    // Subscribe before the blocking thread starts, so stopping right
+    // after starting is not missed
+    let stop = state.scripts.subscribe();
+
+    tokio::task::spawn_blocking(move || execute(&state, runtime, stop, &name, &source, on_print))
+        .await
+        .context("The script panicked")?
+}
+
+/// Run a Rhai script file
+pub async fn run_file(
+    state: &AppState,
+    path: &Path,
+    on_print: impl Fn(&str) + Send + Sync + 'static,
+) -> Result<()> {
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read script {}", path.display()))?;
+
+    run(state, &path.display().to_string(), source, on_print).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn run_source(state: &AppState, source: &str) -> Result<Vec<String>> {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let lines = output.clone();
+        run(state, "test.rhai", source.to_string(), move |line| {
+            lines.lock().push(line.to_string())
+        })
+        .await?;
+        let output = output.lock().clone();
+        Ok(output)
+    }
+
+    #[tokio::test]
+    async fn test_scenario() {
+        let state = AppState::new();
+        let source = r#"
+            let id = add_token(3, 0.5, 0.5);
+            wait(20);
+            for step in 1..=4 {
+                update_object(id, 0.5, 0.5, to_radians(90.0) * step / 4.0);
+                wait(5);
+            }
+            print(`${objects().len()} at ${now()}`);
+            print(get_object(id).angle > 1.5);
+            remove_object(id);
+        "#;
+
+        let output = run_source(&state, source).await.unwrap();
+        assert_eq!(output, vec!["1 at 40", "true"]);
+        assert!(state.objects.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_attach_motion() {
+        let state = AppState::new();
+        let source = r#"
+            let id = add_pointer("stylus", 0.5, 0.5);
+            attach_motion(id, #{
+                steps: [#{ pattern: #{ type: "linear", to: [1.0, 0.5] }, duration_ms: 1000 }],
+                looping: true,
+            });
+        "#;
+
+        run_source(&state, source).await.unwrap();
+        let motions = actions::list_motions(&state);
+        assert_eq!(motions.len(), 1);
+        assert!(motions[0].motion.looping);
+    }
+
+    #[tokio::test]
+    async fn test_errors_have_line_numbers() {
+        let state = AppState::new();
+
+        let error = run_source(&state, "let id = 1;\nremove_object(id);")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "test.rhai:2:1: Runtime error: Object with session_id 1 not found"
+        );
+
+        let error = run_source(&state, "let x = ;").await.unwrap_err();
+        assert!(error.to_string().starts_with("test.rhai:1:9: "));
+    }
+
+    #[tokio::test]
+    async fn test_stop() {
+        let state = AppState::new();
+        let script = tokio::spawn({
+            let state = state.clone();
+            async move { run_source(&state, "loop { wait(10); }").await }
+        });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        state.scripts.stop_all();
+        let error = script.await.unwrap().unwrap_err();
+        assert!(error.to_string().contains("stopped"));
+    }
+}
@@ -1,49 +1,465 @@
-use crate::websocket::WebSocketServer;
+use crate::bus::{EventBus, ObjectChange, SimulatorEvent};
+use crate::motion::ActiveMotion;
+use crate::playback::Player;
+use crate::recording::Recorder;
+use crate::rest::ApiServer;
+use crate::script::ScriptControl;
+use crate::tcp::TcpFraming;
+use crate::transport::bind::BindAddress;
+use crate::transport::{SinkKind, SinkRegistry, SinkStatus};
+use crate::tuio::encoder::ProtocolVersion;
+use crate::tuio::tuio11::Profile;
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::{watch, Notify};
 use tokio::task::JoinHandle;
 
+/// Kind of TUIO object, selecting the component message it is sent as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ObjectKind {
+    #[default]
+    Token, // TOK - Tagged tangible objects (fiducials)
+    Pointer, // PTR - Pointing gestures (touch, stylus, mouse)
+    Blob,    // BND - Untagged objects
+}
+
+/// Oriented bounding box of an object, sized in normalized units
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ObjectBounds {
+    pub width: f32,
+    pub height: f32,
+    /// Covered area, at most width * height
+    pub area: f32,
+}
+
+impl ObjectBounds {
+    /// Create bounds for a fully covered rectangle
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            width,
+            height,
+            area: width * height,
+        }
+    }
+}
+
+/// Pointer type, mapped to the type ID of the TUIO 2.0 pointer type table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PointerType {
+    Finger,
+    Stylus,
+    Mouse,
+}
+
+impl PointerType {
+    /// TUIO 2.0 type ID of this pointer type
+    ///
+    /// 1-5 are right hand fingers (1 = index finger), 6-10 left hand
+    /// fingers, 21 is a stylus and 23 a mouse.
+    pub fn type_id(&self) -> u16 {
+        match self {
+            PointerType::Finger => 1,
+            PointerType::Stylus => 21,
+            PointerType::Mouse => 23,
+        }
+    }
+}
+
+/// Symbol data identifying a tagged object, sent as SYM
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObjectSymbol {
+    pub type_id: u16,
+    /// Symbol group, e.g. "fidtrk/18", "qr" or "rfid"
+    pub group: String,
+    /// Symbol payload, e.g. a fiducial ID, QR content or NFC tag UID
+    pub data: String,
+}
+
+/// Value of a control on a smart tangible, sent in CTL
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ControlValue {
+    /// Button or switch state
+    Bool(bool),
+    /// Slider or sensor value between -1.0 and 1.0
+    Float(f32),
+}
+
+/// MIME-typed data attached to an object, sent as DAT
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObjectData {
+    /// MIME type, e.g. "text/plain" or "application/octet-stream"
+    pub mime: String,
+    pub data: Vec<u8>,
+}
+
+/// When CTL and DAT messages are sent for an object
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlSendMode {
+    /// In every frame, like all other component messages
+    #[default]
+    EveryFrame,
+    /// Only in the frame after the controls or data changed
+    OnChange,
+}
+
+/// When the frame loop generates frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameMode {
+    /// At the configured frame rate
+    #[default]
+    Continuous,
+    /// Only after objects changed, plus periodic keep-alive frames
+    OnChange,
+}
+
+/// Slot of a container object that an object is placed in, sent as COA
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContainerSlot {
+    pub container_id: u32,
+    pub slot: u32,
+}
+
+/// Link from an object to another one, sent as LIA
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectLink {
+    pub target_id: u32,
+    /// Physical connection (e.g. stacked) or logical one
+    pub physical: bool,
+    /// Output port on this object
+    pub out_port: u16,
+    /// Input port on the target
+    pub in_port: u16,
+}
+
+/// Outline geometry of an object, sent as CHG/OCG/ICG/SKG
+///
+/// Points are relative to the object center in normalized units and
+/// are moved and rotated with the object. The convex hull is derived
+/// from the outline.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ObjectGeometry {
+    /// Outer contour polygon
+    pub outline: Vec<[f32; 2]>,
+    /// Inner contour polygons (holes)
+    #[serde(default)]
+    pub inner_contours: Vec<Vec<[f32; 2]>>,
+    /// Skeleton branches, each a polyline
+    #[serde(default)]
+    pub skeleton: Vec<Vec<[f32; 2]>>,
+}
+
+/// Elevation and tilt of an object, sent as T3D/P3D/R3D/B3D
+///
+/// The rotation about the surface normal (yaw) is the object's 2D
+/// angle, so moving an object on the canvas keeps both in sync.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Pose3D {
+    /// Height above the surface, normalized to the sensor depth
+    pub z: f32,
+    /// Rotation about the x axis in radians
+    pub roll: f32,
+    /// Rotation about the y axis in radians
+    pub pitch: f32,
+    /// Depth of the bounding box, sent in B3D
+    pub depth: f32,
+    // Derived motion, calculated per frame
+    #[serde(default)]
+    pub z_vel: f32,
+    #[serde(default)]
+    pub roll_vel: f32,
+    #[serde(default)]
+    pub pitch_vel: f32,
+    #[serde(default)]
+    pub accel: f32,
+    #[serde(default)]
+    pub rotation_accel: f32,
+    #[serde(default)]
+    pub last_z: f32,
+    #[serde(default)]
+    pub last_roll: f32,
+    #[serde(default)]
+    pub last_pitch: f32,
+}
+
+impl Pose3D {
+    /// Create a resting pose
+    pub fn new(z: f32, roll: f32, pitch: f32, depth: f32) -> Self {
+        Self {
+            z,
+            roll,
+            pitch,
+            depth,
+            z_vel: 0.0,
+            roll_vel: 0.0,
+            pitch_vel: 0.0,
+            accel: 0.0,
+            rotation_accel: 0.0,
+            last_z: z,
+            last_roll: roll,
+            last_pitch: pitch,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TuioObject {
     pub session_id: u32,
+    pub kind: ObjectKind,
     pub type_id: u16,
     pub user_id: u16,
     pub component_id: u16,
     pub x: f32,
     pub y: f32,
     pub angle: f32,
+    // Pointer properties, only sent for pointers
+    pub shear: f32,
+    pub radius: f32,
+    pub pressure: f32,
+    // Bounding box, always set for blobs and optional for other kinds
+    pub bounds: Option<ObjectBounds>,
+    pub symbol: Option<ObjectSymbol>,
+    pub geometry: Option<ObjectGeometry>,
+    // Smart tangible controls and data, sent as CTL and DAT
+    pub controls: Vec<ControlValue>,
+    pub data: Vec<ObjectData>,
+    // Associations to other objects, sent as COA and LIA
+    pub container: Option<ContainerSlot>,
+    pub links: Vec<ObjectLink>,
+    // Elevation and tilt, only sent when 3D output is enabled
+    pub pose_3d: Option<Pose3D>,
     pub x_vel: f32,
     pub y_vel: f32,
     pub angle_vel: f32,
+    pub pressure_vel: f32,
+    pub accel: f32,
+    pub angle_accel: f32,
     pub last_x: f32,
     pub last_y: f32,
     pub last_angle: f32,
+    pub last_pressure: f32,
     pub last_update: i64,
 }
 
+impl TuioObject {
+    /// Create a resting token at the given position
+    pub fn new(
+        session_id: u32,
+        type_id: u16,
+        component_id: u16,
+        x: f32,
+        y: f32,
+        timestamp: i64,
+    ) -> Self {
+        Self {
+            session_id,
+            kind: ObjectKind::Token,
+            type_id,
+            user_id: 0,
+            component_id,
+            x,
+            y,
+            angle: 0.0,
+            shear: 0.0,
+            radius: 0.0,
+            pressure: 0.0,
+            bounds: None,
+            symbol: None,
+            geometry: None,
+            controls: Vec::new(),
+            data: Vec::new(),
+            container: None,
+            links: Vec::new(),
+            pose_3d: None,
+            x_vel: 0.0,
+            y_vel: 0.0,
+            angle_vel: 0.0,
+            pressure_vel: 0.0,
+            accel: 0.0,
+            angle_accel: 0.0,
+            last_x: x,
+            last_y: y,
+            last_angle: 0.0,
+            last_pressure: 0.0,
+            last_update: timestamp,
+        }
+    }
+
+    /// Create a resting pointer touching the surface at the given position
+    pub fn new_pointer(
+        session_id: u32,
+        pointer_type: PointerType,
+        x: f32,
+        y: f32,
+        timestamp: i64,
+    ) -> Self {
+        let mut object = Self::new(session_id, pointer_type.type_id(), 0, x, y, timestamp);
+        object.kind = ObjectKind::Pointer;
+        object.pressure = 1.0; // Positive pressure = touching
+        object.last_pressure = object.pressure;
+        object
+    }
+
+    /// Create a resting untagged blob with the given bounding box
+    pub fn new_blob(session_id: u32, x: f32, y: f32, bounds: ObjectBounds, timestamp: i64) -> Self {
+        let mut object = Self::new(session_id, 0, 0, x, y, timestamp);
+        object.kind = ObjectKind::Blob;
+        object.bounds = Some(bounds);
+        object
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub port: u16,
+    /// Address the WebSocket and TCP servers listen on
+    pub bind_address: BindAddress,
     pub fps: u32,
     pub width: u16,
     pub height: u16,
     pub source: String,
+    pub protocol_version: ProtocolVersion,
+    pub enabled_sinks: Vec<SinkKind>,
+    pub udp_targets: Vec<String>,
+    pub tcp_port: u16,
+    pub tcp_framing: TcpFraming,
+    pub file_sink_path: Option<String>,
+    /// Send the 3D component messages for objects with a 3D pose
+    pub enable_3d: bool,
+    /// Sensor depth sent in FRM while 3D output is enabled
+    pub depth: u16,
+    pub control_send_mode: ControlSendMode,
+    /// Token WebSocket clients must present before changing the scene
+    pub control_token: Option<String>,
+    /// Serve the HTTP API on localhost
+    pub http_api_enabled: bool,
+    pub http_api_port: u16,
+    pub frame_mode: FrameMode,
+    /// Minimum time between two frames in on-change mode
+    pub min_frame_interval_ms: u64,
+    /// Time without changes after which a full frame is sent in on-change mode
+    pub keep_alive_ms: u64,
+    /// Leave out TOK for tokens that did not move in on-change mode
+    pub skip_unmoved_tokens: bool,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             port: 3343,
+            bind_address: BindAddress::default(),
             fps: 60,
             width: 1920,
             height: 1080,
             source: "tuio-simulator".to_string(),
+            protocol_version: ProtocolVersion::default(),
+            enabled_sinks: vec![SinkKind::WebSocket, SinkKind::Udp],
+            udp_targets: Vec::new(),
+            tcp_port: 3333,
+            tcp_framing: TcpFraming::default(),
+            file_sink_path: None,
+            enable_3d: false,
+            depth: 1080,
+            control_send_mode: ControlSendMode::default(),
+            control_token: None,
+            http_api_enabled: false,
+            http_api_port: 3380,
+            frame_mode: FrameMode::default(),
+            min_frame_interval_ms: 10,
+            keep_alive_ms: 1000,
+            skip_unmoved_tokens: false,
         }
     }
 }
 
+/// Hold on the frame loop
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct FrameControl {
+    pub paused: bool,
+    /// Frames still to be generated while paused
+    pub pending_steps: u32,
+}
+
+impl FrameControl {
+    /// Whether the next tick generates a frame, using up a pending step
+    pub fn take_frame(&mut self) -> bool {
+        if !self.paused {
+            return true;
+        }
+
+        if self.pending_steps > 0 {
+            self.pending_steps -= 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Number of frames the timing statistics are computed over
+const FRAME_STATS_WINDOW: usize = 120;
+
+/// Timing of the most recently generated frames
+#[derive(Debug, Default)]
+pub struct FrameStats {
+    frames: VecDeque<Instant>,
+}
+
+impl FrameStats {
+    pub fn record(&mut self, at: Instant) {
+        if self.frames.len() == FRAME_STATS_WINDOW {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(at);
+    }
+
+    /// Forget all frames, e.g. after a gap in frame generation
+    pub fn reset(&mut self) {
+        self.frames.clear();
+    }
+
+    fn intervals(&self) -> impl Iterator<Item = f64> + '_ {
+        self.frames
+            .iter()
+            .zip(self.frames.iter().skip(1))
+            .map(|(previous, next)| (*next - *previous).as_secs_f64())
+    }
+
+    /// Frames per second over the window, 0 with fewer than two frames
+    pub fn achieved_fps(&self) -> f64 {
+        match (self.frames.front(), self.frames.back()) {
+            (Some(first), Some(last)) if self.frames.len() > 1 => {
+                (self.frames.len() - 1) as f64 / (*last - *first).as_secs_f64()
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Standard deviation of the frame intervals in milliseconds
+    pub fn jitter_ms(&self) -> f64 {
+        let count = self.frames.len().saturating_sub(1);
+        if count == 0 {
+            return 0.0;
+        }
+
+        let mean = self.intervals().sum::<f64>() / count as f64;
+        let variance = self
+            .intervals()
+            .map(|interval| (interval - mean).powi(2))
+            .sum::<f64>()
+            / count as f64;
+        variance.sqrt() * 1000.0
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerStatus {
     pub running: bool,
@@ -52,8 +468,22 @@ pub struct ServerStatus {
     pub connected_clients: usize,
     pub frame_count: u32,
     pub object_count: usize,
+    pub sinks: Vec<SinkStatus>,
+    /// Frame generation is held, connections stay open
+    pub paused: bool,
+    /// Frames still to be generated while paused
+    pub pending_steps: u32,
+    /// Frame rate measured over the last frames
+    pub achieved_fps: f64,
+    /// Standard deviation of the frame intervals in milliseconds
+    pub frame_jitter_ms: f64,
+    /// Address of the HTTP API while it is running
+    pub http_api_address: Option<SocketAddr>,
 }
 
+/// Position and angle of a token
+pub type TokenPosition = (f32, f32, f32);
+
 #[derive(Clone)]
 pub struct AppState {
     pub objects: Arc<Mutex<HashMap<u32, TuioObject>>>,
@@ -61,25 +491,62 @@ pub struct AppState {
     pub frame_counter: Arc<Mutex<u32>>,
     pub config: Arc<Mutex<Config>>,
     pub server_running: Arc<Mutex<bool>>,
-    pub websocket_server: Arc<WebSocketServer>,
+    pub sinks: Arc<SinkRegistry>,
     pub frame_task: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// Objects whose controls or data changed since the last frame
+    pub changed_controls: Arc<Mutex<HashSet<u32>>>,
+    pub frame_control: Arc<Mutex<FrameControl>>,
+    /// Frame rate the frame loop runs at, kept in sync with `Config::fps`
+    pub frame_rate: Arc<watch::Sender<u32>>,
+    pub frame_stats: Arc<Mutex<FrameStats>>,
+    /// Wakes the frame loop in on-change mode
+    pub frame_trigger: Arc<Notify>,
+    /// Motion patterns attached to objects, by session ID
+    pub motions: Arc<Mutex<HashMap<u32, ActiveMotion>>>,
+    /// Position and angle of each token in the last frame
+    pub sent_tokens: Arc<Mutex<HashMap<u32, TokenPosition>>>,
+    /// TUIO 1.1 profiles with objects in the last frame
+    pub sent_profiles: Arc<Mutex<HashSet<Profile>>>,
+    /// Most recently generated bundle
+    pub last_bundle: Arc<Mutex<Option<Vec<u8>>>>,
+    pub api_server: Arc<ApiServer>,
+    pub recorder: Arc<Recorder>,
+    pub player: Arc<Player>,
+    pub scripts: Arc<ScriptControl>,
+    pub events: EventBus,
 }
 
 impl AppState {
     pub fn new() -> Self {
+        let events = EventBus::new();
+
         Self {
             objects: Arc::new(Mutex::new(HashMap::new())),
             next_session_id: Arc::new(Mutex::new(0)),
             frame_counter: Arc::new(Mutex::new(0)),
             config: Arc::new(Mutex::new(Config::default())),
             server_running: Arc::new(Mutex::new(false)),
-            websocket_server: Arc::new(WebSocketServer::new()),
+            sinks: Arc::new(SinkRegistry::new(events.clone())),
             frame_task: Arc::new(Mutex::new(None)),
+            changed_controls: Arc::new(Mutex::new(HashSet::new())),
+            frame_control: Arc::new(Mutex::new(FrameControl::default())),
+            frame_rate: Arc::new(watch::channel(Config::default().fps).0),
+            frame_stats: Arc::new(Mutex::new(FrameStats::default())),
+            frame_trigger: Arc::new(Notify::new()),
+            motions: Arc::new(Mutex::new(HashMap::new())),
+            sent_tokens: Arc::new(Mutex::new(HashMap::new())),
+            sent_profiles: Arc::new(Mutex::new(HashSet::new())),
+            last_bundle: Arc::new(Mutex::new(None)),
+            api_server: Arc::new(ApiServer::new()),
+            recorder: Arc::new(Recorder::new()),
+            player: Arc::new(Player::new()),
+            scripts: Arc::new(ScriptControl::new()),
+            events,
         }
     }
 
     pub fn get_connected_clients(&self) -> usize {
-        self.websocket_server.get_connected_clients()
+        self.sinks.get_connections()
     }
 
     pub fn allocate_session_id(&self) -> u32 {
@@ -89,6 +556,37 @@ impl AppState {
         id
     }
 
+    /// Remove an object together with all associations to it
+    ///
+    /// Objects inside a removed container are taken out of it, links to
+    /// the removed object are dropped and its motion is detached, so no
+    /// association refers to a session ID that is no longer alive.
+    pub fn remove_object(&self, session_id: u32) -> Option<TuioObject> {
+        let mut objects = self.objects.lock();
+        let removed = objects.remove(&session_id)?;
+
+        for object in objects.values_mut() {
+            if object.container.map(|c| c.container_id) == Some(session_id) {
+                object.container = None;
+            }
+            object.links.retain(|link| link.target_id != session_id);
+        }
+        drop(objects);
+
+        self.motions.lock().remove(&session_id);
+        self.changed_controls.lock().remove(&session_id);
+        Some(removed)
+    }
+
+    /// Publish that an object was added, changed or removed
+    ///
+    /// Also triggers a frame in on-change mode.
+    pub fn object_changed(&self, session_id: u32, change: ObjectChange) {
+        self.events
+            .publish(SimulatorEvent::ObjectChanged { session_id, change });
+        self.frame_trigger.notify_one();
+    }
+
     pub fn increment_frame_counter(&self) -> u32 {
         let mut counter = self.frame_counter.lock();
         *counter = counter.wrapping_add(1);
@@ -101,3 +599,105 @@ impl Default for AppState {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::motion::{Motion, MotionPattern, MotionStep};
+    use std::time::Duration;
+
+    #[test]
+    fn test_frame_stats() {
+        let mut stats = FrameStats::default();
+        assert_eq!(stats.achieved_fps(), 0.0);
+
+        let start = Instant::now();
+        for i in 0..5 {
+            stats.record(start + Duration::from_millis(i * 20));
+        }
+        assert!((stats.achieved_fps() - 50.0).abs() < 1e-6);
+        assert!(stats.jitter_ms() < 1e-6);
+
+        // Alternating 10 ms and 30 ms intervals deviate by 10 ms
+        stats.reset();
+        for at in [0, 10, 40, 50, 80] {
+            stats.record(start + Duration::from_millis(at));
+        }
+        assert!((stats.achieved_fps() - 50.0).abs() < 1e-6);
+        assert!((stats.jitter_ms() - 10.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_frame_control_steps() {
+        let mut control = FrameControl::default();
+        assert!(control.take_frame());
+
+        control.paused = true;
+        control.pending_steps = 2;
+        assert!(control.take_frame());
+        assert!(control.take_frame());
+        assert!(!control.take_frame());
+        assert_eq!(control.pending_steps, 0);
+    }
+
+    #[test]
+    fn test_remove_object_drops_associations() {
+        let state = AppState::new();
+        {
+            let mut objects = state.objects.lock();
+            objects.insert(1, TuioObject::new(1, 1, 1, 0.5, 0.5, 0));
+
+            let mut contained = TuioObject::new(2, 1, 2, 0.5, 0.5, 0);
+            contained.container = Some(ContainerSlot {
+                container_id: 1,
+                slot: 0,
+            });
+            contained.links = vec![
+                ObjectLink {
+                    target_id: 1,
+                    physical: true,
+                    out_port: 0,
+                    in_port: 0,
+                },
+                ObjectLink {
+                    target_id: 3,
+                    physical: false,
+                    out_port: 0,
+                    in_port: 0,
+                },
+            ];
+            objects.insert(2, contained);
+            objects.insert(3, TuioObject::new(3, 1, 3, 0.5, 0.5, 0));
+        }
+
+        assert!(state.remove_object(1).is_some());
+        assert!(state.remove_object(1).is_none());
+
+        let objects = state.objects.lock();
+        let contained = objects.get(&2).unwrap();
+        assert_eq!(contained.container, None);
+        assert_eq!(contained.links.len(), 1);
+        assert_eq!(contained.links[0].target_id, 3);
+    }
+
+    #[test]
+    fn test_remove_object_detaches_motion() {
+        let state = AppState::new();
+        let object = TuioObject::new(1, 1, 1, 0.5, 0.5, 0);
+        let motion = Motion {
+            steps: vec![MotionStep {
+                pattern: MotionPattern::Linear { to: [1.0, 1.0] },
+                duration_ms: 1000,
+            }],
+            looping: true,
+        };
+        state
+            .motions
+            .lock()
+            .insert(1, ActiveMotion::new(&object, motion, 0));
+        state.objects.lock().insert(1, object);
+
+        assert!(state.remove_object(1).is_some());
+        assert!(state.motions.lock().is_empty());
+    }
+}
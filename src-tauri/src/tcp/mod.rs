@@ -0,0 +1,337 @@
+use crate::state::Config;
+use crate::transport::bind::bind_listener;
+use crate::transport::clients::{ClientHandle, ClientRegistry};
+use crate::transport::{SinkError, SinkKind, TransportSink};
+use anyhow::Result;
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, oneshot};
+use tokio::task::JoinHandle;
+
+/// SLIP frame delimiter
+const SLIP_END: u8 = 0xC0;
+/// SLIP escape byte
+const SLIP_ESC: u8 = 0xDB;
+/// Escaped SLIP_END
+const SLIP_ESC_END: u8 = 0xDC;
+/// Escaped SLIP_ESC
+const SLIP_ESC_ESC: u8 = 0xDD;
+
+/// Framing of OSC packets on a TCP stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TcpFraming {
+    /// OSC 1.1: double-ended SLIP encoding
+    #[default]
+    Slip,
+    /// OSC 1.0: big-endian int32 size followed by the packet
+    LengthPrefix,
+}
+
+/// Frame an encoded OSC packet for a TCP stream
+pub fn frame_packet(framing: TcpFraming, data: &[u8]) -> Vec<u8> {
+    match framing {
+        TcpFraming::Slip => {
+            let mut framed = Vec::with_capacity(data.len() + 2);
+            framed.push(SLIP_END);
+            for &byte in data {
+                match byte {
+                    SLIP_END => framed.extend([SLIP_ESC, SLIP_ESC_END]),
+                    SLIP_ESC => framed.extend([SLIP_ESC, SLIP_ESC_ESC]),
+                    _ => framed.push(byte),
+                }
+            }
+            framed.push(SLIP_END);
+            framed
+        }
+        TcpFraming::LengthPrefix => {
+            let mut framed = Vec::with_capacity(data.len() + 4);
+            framed.extend((data.len() as u32).to_be_bytes());
+            framed.extend_from_slice(data);
+            framed
+        }
+    }
+}
+
+/// TCP server state
+///
+/// Sends every bundle to all connected clients, framed as configured
+/// in `Config::tcp_framing`.
+pub struct TcpServer {
+    broadcast_tx: broadcast::Sender<Arc<Vec<u8>>>,
+    framing: Mutex<TcpFraming>,
+    local_addr: Mutex<Option<SocketAddr>>,
+    clients: Arc<ClientRegistry>,
+    server_task: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl TcpServer {
+    /// Create a new TCP server registering its clients in the registry
+    pub fn new(clients: Arc<ClientRegistry>) -> Self {
+        let (broadcast_tx, _) = broadcast::channel(100);
+
+        Self {
+            broadcast_tx,
+            framing: Mutex::new(TcpFraming::default()),
+            local_addr: Mutex::new(None),
+            clients,
+            server_task: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Get the current number of connected clients
+    pub fn get_connected_clients(&self) -> usize {
+        self.clients.count(SinkKind::Tcp)
+    }
+
+    /// Get the address the server is listening on
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        *self.local_addr.lock()
+    }
+
+    /// Start the TCP server on the specified address
+    pub async fn start(&self, addr: SocketAddr, framing: TcpFraming) -> Result<()> {
+        // Stop any existing server first
+        self.stop().await;
+
+        let listener = bind_listener(addr).await?;
+        let local_addr = listener.local_addr()?;
+
+        println!("TCP server listening on: {} ({:?})", local_addr, framing);
+
+        *self.framing.lock() = framing;
+        *self.local_addr.lock() = Some(local_addr);
+
+        let broadcast_tx = self.broadcast_tx.clone();
+        let clients = self.clients.clone();
+
+        // Spawn task to accept connections
+        let task = tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, addr)) => {
+                        println!("New TCP connection from: {}", addr);
+
+                        let broadcast_rx = broadcast_tx.subscribe();
+                        let (client, disconnect_rx) =
+                            clients.register(SinkKind::Tcp, addr, None, None);
+
+                        // Spawn task to handle this client
+                        tokio::spawn(async move {
+                            if let Err(e) =
+                                handle_connection(stream, broadcast_rx, &client, disconnect_rx)
+                                    .await
+                            {
+                                eprintln!("Error handling TCP connection from {}: {}", addr, e);
+                            }
+
+                            drop(client);
+                            println!("TCP client disconnected: {}", addr);
+                        });
+                    }
+                    Err(e) => {
+                        eprintln!("Error accepting TCP connection: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+
+        {
+            let mut server_task = self.server_task.lock();
+            *server_task = Some(task);
+        }
+
+        Ok(())
+    }
+
+    /// Stop the TCP server
+    pub async fn stop(&self) {
+        let mut server_task = self.server_task.lock();
+        if let Some(task) = server_task.take() {
+            task.abort();
+            println!("TCP server stopped");
+        }
+
+        *self.local_addr.lock() = None;
+
+        // Close the connections of all clients
+        self.clients.disconnect_sink(SinkKind::Tcp);
+    }
+}
+
+impl Default for TcpServer {
+    fn default() -> Self {
+        Self::new(Arc::default())
+    }
+}
+
+#[async_trait]
+impl TransportSink for TcpServer {
+    fn kind(&self) -> SinkKind {
+        SinkKind::Tcp
+    }
+
+    async fn start(&self, config: &Config) -> Result<()> {
+        let addr = SocketAddr::new(config.bind_address.ip(), config.tcp_port);
+        TcpServer::start(self, addr, config.tcp_framing).await
+    }
+
+    async fn stop(&self) {
+        TcpServer::stop(self).await
+    }
+
+    async fn send(&self, data: &[u8]) -> Vec<SinkError> {
+        // Frame once for all clients, lagging clients are handled per connection
+        let framed = frame_packet(*self.framing.lock(), data);
+        let _ = self.broadcast_tx.send(Arc::new(framed));
+        Vec::new()
+    }
+
+    fn is_running(&self) -> bool {
+        self.server_task.lock().is_some()
+    }
+
+    fn get_connections(&self) -> usize {
+        self.get_connected_clients()
+    }
+
+    fn local_addrs(&self) -> Vec<SocketAddr> {
+        self.local_addr().into_iter().collect()
+    }
+}
+
+/// Handle a single TCP connection
+///
+/// Sends framed packets until the client closes the connection, a write
+/// fails or a disconnect is requested. Data sent by the client is ignored.
+async fn handle_connection(
+    stream: TcpStream,
+    mut broadcast_rx: broadcast::Receiver<Arc<Vec<u8>>>,
+    client: &ClientHandle,
+    mut disconnect_rx: oneshot::Receiver<()>,
+) -> Result<()> {
+    let (mut reader, mut writer) = stream.into_split();
+    let mut buf = [0u8; 1024];
+
+    loop {
+        tokio::select! {
+            msg = broadcast_rx.recv() => match msg {
+                Ok(data) => {
+                    writer.write_all(&data).await?;
+                    client.record_sent(data.len());
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    eprintln!("TCP client lagged, skipped {} messages", skipped);
+                    client.record_lag(skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            read = reader.read(&mut buf) => {
+                // A read of 0 bytes means the client closed the connection
+                if read? == 0 {
+                    break;
+                }
+            }
+            _ = &mut disconnect_rx => break,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::{EventBus, SimulatorEvent};
+    use std::time::Duration;
+
+    #[test]
+    fn test_slip_framing() {
+        let framed = frame_packet(TcpFraming::Slip, &[1, SLIP_END, 2, SLIP_ESC]);
+        assert_eq!(
+            framed,
+            vec![
+                SLIP_END,
+                1,
+                SLIP_ESC,
+                SLIP_ESC_END,
+                2,
+                SLIP_ESC,
+                SLIP_ESC_ESC,
+                SLIP_END
+            ]
+        );
+    }
+
+    #[test]
+    fn test_length_prefix_framing() {
+        let framed = frame_packet(TcpFraming::LengthPrefix, &[1, 2, 3]);
+        assert_eq!(framed, vec![0, 0, 0, 3, 1, 2, 3]);
+    }
+
+    async fn wait_for_clients(server: &TcpServer, count: usize) {
+        for _ in 0..100 {
+            if server.get_connected_clients() == count {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("Expected {} connected clients", count);
+    }
+
+    #[tokio::test]
+    async fn test_tcp_server_sends_framed_packets() {
+        let bus = EventBus::new();
+        let server = TcpServer::new(Arc::new(ClientRegistry::new(bus.clone())));
+        let config = Config {
+            tcp_port: 0,
+            tcp_framing: TcpFraming::LengthPrefix,
+            ..Config::default()
+        };
+        TransportSink::start(&server, &config).await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let mut events = bus.subscribe();
+
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        wait_for_clients(&server, 1).await;
+
+        assert!(server.send(&[1, 2]).await.is_empty());
+        let mut received = [0u8; 6];
+        client.read_exact(&mut received).await.unwrap();
+        assert_eq!(received, [0, 0, 0, 2, 1, 2]);
+
+        // A disconnect request closes the connection
+        let client_id = server.clients.list()[0].client_id.clone();
+        assert!(server.clients.disconnect(&client_id));
+        assert_eq!(client.read(&mut received).await.unwrap(), 0);
+        wait_for_clients(&server, 0).await;
+
+        assert!(matches!(
+            events.recv().await,
+            Ok(SimulatorEvent::ClientConnected(_))
+        ));
+        match events.recv().await {
+            Ok(SimulatorEvent::ClientDisconnected(info)) => {
+                assert_eq!(info.client_id, client_id);
+                assert_eq!(info.frames_sent, 1);
+                assert_eq!(info.bytes_sent, 6);
+            }
+            other => panic!("Expected disconnect event, got {:?}", other),
+        }
+
+        // Closing the connection is noticed without sending
+        let client = TcpStream::connect(addr).await.unwrap();
+        wait_for_clients(&server, 1).await;
+        drop(client);
+        wait_for_clients(&server, 0).await;
+
+        TransportSink::stop(&server).await;
+        assert!(!server.is_running());
+    }
+}
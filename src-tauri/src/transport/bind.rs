@@ -0,0 +1,187 @@
+use super::SinkKind;
+use anyhow::Result;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use tokio::net::TcpListener;
+
+/// Address the servers listen on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", content = "address", rename_all = "snake_case")]
+pub enum BindAddress {
+    /// Only reachable from this machine (127.0.0.1)
+    #[default]
+    Localhost,
+    /// All IPv4 interfaces (0.0.0.0)
+    AllInterfaces,
+    /// A specific interface address, IPv4 or IPv6 (:: for all IPv6 interfaces)
+    Address(IpAddr),
+}
+
+impl BindAddress {
+    /// IP address to bind to
+    pub fn ip(&self) -> IpAddr {
+        match self {
+            BindAddress::Localhost => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            BindAddress::AllInterfaces => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            BindAddress::Address(ip) => *ip,
+        }
+    }
+}
+
+/// Reason a listening socket could not be bound
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum BindError {
+    #[error("port {} is already in use on {}", .0.port(), .0.ip())]
+    PortInUse(SocketAddr),
+    #[error("address {} is not available on this machine", .0.ip())]
+    AddressUnavailable(SocketAddr),
+    #[error("failed to bind {0}: {1}")]
+    Other(SocketAddr, String),
+}
+
+/// Bind a TCP listener, classifying the common failures
+pub async fn bind_listener(address: SocketAddr) -> Result<TcpListener, BindError> {
+    TcpListener::bind(address)
+        .await
+        .map_err(|e| match e.kind() {
+            ErrorKind::AddrInUse => BindError::PortInUse(address),
+            ErrorKind::AddrNotAvailable => BindError::AddressUnavailable(address),
+            _ => BindError::Other(address, e.to_string()),
+        })
+}
+
+/// Addresses clients can use to reach a bound socket
+///
+/// Unspecified addresses (0.0.0.0, ::) are expanded to the addresses of
+/// the local interfaces of the same family, IPv6 also covering IPv4.
+pub fn reachable_addresses(bound: SocketAddr) -> Vec<SocketAddr> {
+    if !bound.ip().is_unspecified() {
+        return vec![bound];
+    }
+
+    let interfaces = if_addrs::get_if_addrs().unwrap_or_default();
+    let addresses: Vec<SocketAddr> = interfaces
+        .iter()
+        .map(|interface| interface.ip())
+        .filter(|ip| bound.is_ipv6() || ip.is_ipv4())
+        .map(|ip| SocketAddr::new(ip, bound.port()))
+        .collect();
+
+    if addresses.is_empty() {
+        vec![bound]
+    } else {
+        addresses
+    }
+}
+
+/// Error returned when the servers could not be started
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum StartError {
+    #[error("Server is already running")]
+    AlreadyRunning,
+    #[error("Failed to start {0} sink: {1}")]
+    Bind(SinkKind, BindError),
+    #[error("Failed to start {0} sink: {1}")]
+    Failed(SinkKind, String),
+}
+
+impl StartError {
+    /// Create an error for a failed sink, keeping bind failures typed
+    pub fn from_sink(kind: SinkKind, error: anyhow::Error) -> Self {
+        match error.downcast::<BindError>() {
+            Ok(bind_error) => StartError::Bind(kind, bind_error),
+            Err(error) => StartError::Failed(kind, format!("{:#}", error)),
+        }
+    }
+
+    /// Identifier of the error, used by the frontend
+    pub fn kind(&self) -> &'static str {
+        match self {
+            StartError::AlreadyRunning => "already_running",
+            StartError::Bind(_, BindError::PortInUse(_)) => "port_in_use",
+            StartError::Bind(_, BindError::AddressUnavailable(_)) => "address_unavailable",
+            StartError::Bind(_, BindError::Other(..)) | StartError::Failed(..) => "failed",
+        }
+    }
+}
+
+/// Serialized as `{ kind, sink, address, message }` for the frontend
+impl Serialize for StartError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (sink, address) = match self {
+            StartError::AlreadyRunning => (None, None),
+            StartError::Bind(sink, error) => {
+                let address = match error {
+                    BindError::PortInUse(address)
+                    | BindError::AddressUnavailable(address)
+                    | BindError::Other(address, _) => address,
+                };
+                (Some(*sink), Some(address.to_string()))
+            }
+            StartError::Failed(sink, _) => (Some(*sink), None),
+        };
+
+        let mut state = serializer.serialize_struct("StartError", 4)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("sink", &sink)?;
+        state.serialize_field("address", &address)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bind_port_in_use() {
+        let first = bind_listener("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let address = first.local_addr().unwrap();
+
+        let error = bind_listener(address).await.unwrap_err();
+        assert!(matches!(error, BindError::PortInUse(a) if a == address));
+    }
+
+    #[test]
+    fn test_start_error_serialization() {
+        let address: SocketAddr = "127.0.0.1:3343".parse().unwrap();
+        let error = StartError::from_sink(
+            SinkKind::WebSocket,
+            anyhow::Error::new(BindError::PortInUse(address)),
+        );
+
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["kind"], "port_in_use");
+        assert_eq!(json["sink"], "websocket");
+        assert_eq!(json["address"], "127.0.0.1:3343");
+        assert_eq!(
+            json["message"],
+            "Failed to start WebSocket sink: port 3343 is already in use on 127.0.0.1"
+        );
+    }
+
+    #[test]
+    fn test_reachable_addresses() {
+        let bound: SocketAddr = "127.0.0.1:3343".parse().unwrap();
+        assert_eq!(reachable_addresses(bound), vec![bound]);
+
+        // All interfaces are expanded to the IPv4 interface addresses
+        let any: SocketAddr = "0.0.0.0:3343".parse().unwrap();
+        assert!(reachable_addresses(any)
+            .iter()
+            .all(|a| a.port() == 3343 && a.is_ipv4()));
+    }
+
+    #[test]
+    fn test_bind_address_serialization() {
+        let json = serde_json::to_string(&BindAddress::AllInterfaces).unwrap();
+        assert_eq!(json, r#"{"type":"all_interfaces"}"#);
+
+        let address: BindAddress =
+            serde_json::from_str(r#"{"type":"address","address":"::1"}"#).unwrap();
+        assert_eq!(address.ip(), "::1".parse::<IpAddr>().unwrap());
+    }
+}
@@ -0,0 +1,336 @@
+use super::SinkKind;
+use crate::bus::{EventBus, SimulatorEvent};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+
+/// A client connected to one of the servers
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientInfo {
+    pub client_id: String,
+    pub sink: SinkKind,
+    pub peer_addr: SocketAddr,
+    /// Connect time in milliseconds since the Unix epoch
+    pub connected_at: i64,
+    pub frames_sent: u64,
+    pub bytes_sent: u64,
+    /// Number of times the client fell behind the broadcast
+    pub lag_events: u64,
+    /// Frames dropped for the client because it fell behind
+    pub skipped_frames: u64,
+    /// User agent sent with the WebSocket handshake
+    pub user_agent: Option<String>,
+    /// Subprotocol accepted in the WebSocket handshake
+    pub subprotocol: Option<String>,
+}
+
+/// Text message from a client, answered through `reply_tx`
+pub struct ClientMessage {
+    pub client_id: String,
+    pub text: String,
+    /// Reply to send back to the client, if any
+    pub reply_tx: oneshot::Sender<Option<String>>,
+}
+
+struct ClientEntry {
+    info: ClientInfo,
+    disconnect_tx: Option<oneshot::Sender<()>>,
+}
+
+/// Registry of the clients connected to all servers
+///
+/// Connections register themselves when they are accepted and are
+/// removed again when their `ClientHandle` is dropped. Connects and
+/// disconnects are published on the event bus.
+pub struct ClientRegistry {
+    clients: Mutex<HashMap<String, ClientEntry>>,
+    events: EventBus,
+    message_tx: Mutex<Option<mpsc::Sender<ClientMessage>>>,
+}
+
+impl ClientRegistry {
+    /// Create an empty registry publishing on the given bus
+    pub fn new(events: EventBus) -> Self {
+        Self {
+            clients: Mutex::new(HashMap::new()),
+            events,
+            message_tx: Mutex::new(None),
+        }
+    }
+
+    /// Route text messages from clients to the returned receiver
+    ///
+    /// Replaces any previous receiver. Messages are ignored while nobody
+    /// receives them.
+    pub fn receive_messages(&self) -> mpsc::Receiver<ClientMessage> {
+        let (message_tx, message_rx) = mpsc::channel(100);
+        *self.message_tx.lock() = Some(message_tx);
+        message_rx
+    }
+
+    /// Forward a text message from a client, returning the reply to send back
+    pub async fn forward_message(&self, client_id: &str, text: String) -> Option<String> {
+        let message_tx = self.message_tx.lock().clone()?;
+        let (reply_tx, reply_rx) = oneshot::channel();
+
+        let message = ClientMessage {
+            client_id: client_id.to_string(),
+            text,
+            reply_tx,
+        };
+        message_tx.send(message).await.ok()?;
+        reply_rx.await.ok().flatten()
+    }
+
+    /// Register a new connection
+    ///
+    /// Returns the handle that keeps the client registered and a receiver
+    /// that resolves when the client should be disconnected.
+    pub fn register(
+        self: &Arc<Self>,
+        sink: SinkKind,
+        peer_addr: SocketAddr,
+        user_agent: Option<String>,
+        subprotocol: Option<String>,
+    ) -> (ClientHandle, oneshot::Receiver<()>) {
+        let client_id = uuid::Uuid::new_v4().to_string();
+        let (disconnect_tx, disconnect_rx) = oneshot::channel();
+
+        let info = ClientInfo {
+            client_id: client_id.clone(),
+            sink,
+            peer_addr,
+            connected_at: chrono::Utc::now().timestamp_millis(),
+            frames_sent: 0,
+            bytes_sent: 0,
+            lag_events: 0,
+            skipped_frames: 0,
+            user_agent,
+            subprotocol,
+        };
+
+        self.clients.lock().insert(
+            client_id.clone(),
+            ClientEntry {
+                info: info.clone(),
+                disconnect_tx: Some(disconnect_tx),
+            },
+        );
+        self.events.publish(SimulatorEvent::ClientConnected(info));
+
+        let handle = ClientHandle {
+            client_id,
+            registry: self.clone(),
+        };
+        (handle, disconnect_rx)
+    }
+
+    /// All connected clients, oldest first
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn list(&self) -> Vec<ClientInfo> {
+        let mut clients: Vec<ClientInfo> = self
+            .clients
+            .lock()
+            .values()
+            .map(|entry| entry.info.clone())
+            .collect();
+        clients.sort_by(|a, b| {
+            a.connected_at
+                .cmp(&b.connected_at)
+                .then_with(|| a.client_id.cmp(&b.client_id))
+        });
+        clients
+    }
+
+    /// Number of clients connected to the given sink
+    pub fn count(&self, sink: SinkKind) -> usize {
+        self.clients
+            .lock()
+            .values()
+            .filter(|entry| entry.info.sink == sink)
+            .count()
+    }
+
+    /// Ask a client to disconnect, returning false for unknown clients
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn disconnect(&self, client_id: &str) -> bool {
+        let mut clients = self.clients.lock();
+        match clients.get_mut(client_id) {
+            Some(entry) => {
+                if let Some(tx) = entry.disconnect_tx.take() {
+                    let _ = tx.send(());
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Ask all clients of a sink to disconnect
+    pub fn disconnect_sink(&self, sink: SinkKind) {
+        let mut clients = self.clients.lock();
+        for entry in clients.values_mut() {
+            if entry.info.sink == sink {
+                if let Some(tx) = entry.disconnect_tx.take() {
+                    let _ = tx.send(());
+                }
+            }
+        }
+    }
+
+    fn update(&self, client_id: &str, f: impl FnOnce(&mut ClientInfo)) {
+        if let Some(entry) = self.clients.lock().get_mut(client_id) {
+            f(&mut entry.info);
+        }
+    }
+
+    fn unregister(&self, client_id: &str) {
+        let entry = self.clients.lock().remove(client_id);
+        if let Some(entry) = entry {
+            self.events
+                .publish(SimulatorEvent::ClientDisconnected(entry.info));
+        }
+    }
+}
+
+impl Default for ClientRegistry {
+    fn default() -> Self {
+        Self::new(EventBus::default())
+    }
+}
+
+/// Registration of a single connection, removed from the registry on drop
+pub struct ClientHandle {
+    client_id: String,
+    registry: Arc<ClientRegistry>,
+}
+
+impl ClientHandle {
+    /// ID of the client in the registry
+    pub fn id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// Record a frame sent to the client
+    pub fn record_sent(&self, bytes: usize) {
+        self.registry.update(&self.client_id, |info| {
+            info.frames_sent += 1;
+            info.bytes_sent += bytes as u64;
+        });
+    }
+
+    /// Record that the client fell behind and missed frames
+    pub fn record_lag(&self, skipped: u64) {
+        self.registry.update(&self.client_id, |info| {
+            info.lag_events += 1;
+            info.skipped_frames += skipped;
+        });
+    }
+}
+
+impl Drop for ClientHandle {
+    fn drop(&mut self) {
+        self.registry.unregister(&self.client_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer() -> SocketAddr {
+        "127.0.0.1:50000".parse().unwrap()
+    }
+
+    #[test]
+    fn test_register_and_drop() {
+        let bus = EventBus::new();
+        let registry = Arc::new(ClientRegistry::new(bus.clone()));
+        let mut events = bus.subscribe();
+
+        let (handle, _disconnect_rx) = registry.register(
+            SinkKind::WebSocket,
+            peer(),
+            Some("test-agent".to_string()),
+            None,
+        );
+        handle.record_sent(10);
+        handle.record_sent(5);
+        handle.record_lag(3);
+
+        let clients = registry.list();
+        assert_eq!(clients.len(), 1);
+        assert_eq!(clients[0].frames_sent, 2);
+        assert_eq!(clients[0].bytes_sent, 15);
+        assert_eq!(clients[0].lag_events, 1);
+        assert_eq!(clients[0].skipped_frames, 3);
+        assert_eq!(clients[0].user_agent.as_deref(), Some("test-agent"));
+        assert_eq!(registry.count(SinkKind::WebSocket), 1);
+        assert_eq!(registry.count(SinkKind::Tcp), 0);
+
+        drop(handle);
+        assert!(registry.list().is_empty());
+
+        assert!(matches!(
+            events.try_recv(),
+            Ok(SimulatorEvent::ClientConnected(_))
+        ));
+        match events.try_recv() {
+            Ok(SimulatorEvent::ClientDisconnected(info)) => assert_eq!(info.frames_sent, 2),
+            other => panic!("Expected disconnect event, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forward_message() {
+        let registry = Arc::new(ClientRegistry::default());
+        let (handle, _disconnect_rx) = registry.register(SinkKind::WebSocket, peer(), None, None);
+
+        // Without a receiver messages are ignored
+        assert_eq!(
+            registry.forward_message(handle.id(), "ping".into()).await,
+            None
+        );
+
+        let mut messages = registry.receive_messages();
+        let responder = tokio::spawn(async move {
+            let message = messages.recv().await.unwrap();
+            let reply = format!("{} from {}", message.text, message.client_id);
+            message.reply_tx.send(Some(reply)).unwrap();
+        });
+
+        let reply = registry.forward_message(handle.id(), "ping".into()).await;
+        assert_eq!(reply, Some(format!("ping from {}", handle.id())));
+        responder.await.unwrap();
+    }
+
+    #[test]
+    fn test_disconnect() {
+        let registry = Arc::new(ClientRegistry::default());
+        let (handle, mut disconnect_rx) = registry.register(SinkKind::Tcp, peer(), None, None);
+        let (_other, mut other_rx) = registry.register(SinkKind::WebSocket, peer(), None, None);
+
+        assert!(!registry.disconnect("unknown"));
+        assert!(disconnect_rx.try_recv().is_err());
+
+        let tcp_client = registry
+            .list()
+            .into_iter()
+            .find(|client| client.sink == SinkKind::Tcp)
+            .unwrap();
+        assert!(registry.disconnect(&tcp_client.client_id));
+        assert!(disconnect_rx.try_recv().is_ok());
+        assert!(other_rx.try_recv().is_err());
+
+        registry.disconnect_sink(SinkKind::WebSocket);
+        assert!(other_rx.try_recv().is_ok());
+
+        // The client stays registered until the connection is closed
+        assert_eq!(registry.count(SinkKind::Tcp), 1);
+        drop(handle);
+        assert_eq!(registry.count(SinkKind::Tcp), 0);
+    }
+}
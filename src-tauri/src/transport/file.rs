@@ -0,0 +1,211 @@
+use super::{SinkError, SinkKind, TransportSink};
+use crate::state::Config;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Bundles waiting for the writer task, further bundles are dropped
+const QUEUE_FRAMES: usize = 100;
+
+/// An open file and the task writing to it
+struct FileWriter {
+    path: String,
+    frames: mpsc::Sender<Vec<u8>>,
+    task: JoinHandle<()>,
+    /// Write errors not reported yet
+    errors: Arc<Mutex<Vec<String>>>,
+}
+
+/// File sink state
+///
+/// Appends every bundle to a file using OSC 1.0 stream framing: a
+/// big-endian int32 size followed by the bundle bytes. Bundles are
+/// written by a separate task, so a slow disk does not hold up frames.
+/// Bundles coming in while the task is behind are dropped and reported
+/// as errors, like a slow network client missing frames.
+pub struct FileSink {
+    writer: Mutex<Option<FileWriter>>,
+}
+
+impl FileSink {
+    /// Create a new, closed file sink
+    pub fn new() -> Self {
+        Self {
+            writer: Mutex::new(None),
+        }
+    }
+}
+
+impl Default for FileSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Write framed bundles until the sink is stopped, then flush the file
+async fn write_frames(
+    file: File,
+    mut frames: mpsc::Receiver<Vec<u8>>,
+    errors: Arc<Mutex<Vec<String>>>,
+) {
+    let mut file = BufWriter::new(file);
+
+    while let Some(data) = frames.recv().await {
+        let result = match file.write_all(&(data.len() as u32).to_be_bytes()).await {
+            Ok(()) => file.write_all(&data).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            errors.lock().push(e.to_string());
+        }
+    }
+
+    if let Err(e) = file.flush().await {
+        errors.lock().push(e.to_string());
+    }
+}
+
+#[async_trait]
+impl TransportSink for FileSink {
+    fn kind(&self) -> SinkKind {
+        SinkKind::File
+    }
+
+    async fn start(&self, config: &Config) -> Result<()> {
+        self.stop().await;
+
+        let path = config
+            .file_sink_path
+            .clone()
+            .ok_or_else(|| anyhow!("No file path configured"))?;
+        let file = File::create(&path).await?;
+
+        println!("File sink writing to: {}", path);
+
+        let (frames, receiver) = mpsc::channel(QUEUE_FRAMES);
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let task = tokio::spawn(write_frames(file, receiver, errors.clone()));
+
+        let mut writer = self.writer.lock();
+        *writer = Some(FileWriter {
+            path,
+            frames,
+            task,
+            errors,
+        });
+
+        Ok(())
+    }
+
+    async fn stop(&self) {
+        let writer = self.writer.lock().take();
+        if let Some(FileWriter {
+            path, frames, task, ..
+        }) = writer
+        {
+            // Closing the channel lets the task write what is left and flush
+            drop(frames);
+            let _ = task.await;
+            println!("File sink closed: {}", path);
+        }
+    }
+
+    async fn send(&self, data: &[u8]) -> Vec<SinkError> {
+        let writer = self.writer.lock();
+        let Some(writer) = writer.as_ref() else {
+            return Vec::new();
+        };
+
+        let mut messages: Vec<String> = writer.errors.lock().drain(..).collect();
+        match writer.frames.try_send(data.to_vec()) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                messages.push("Dropped a frame, the file writer is behind".to_string());
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                messages.push("File writer stopped".to_string());
+            }
+        }
+
+        messages
+            .into_iter()
+            .map(|message| SinkError {
+                kind: SinkKind::File,
+                target: writer.path.clone(),
+                message,
+            })
+            .collect()
+    }
+
+    fn is_running(&self) -> bool {
+        self.writer.lock().is_some()
+    }
+
+    fn get_connections(&self) -> usize {
+        usize::from(self.is_running())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_sink_writes_framed_bundles() {
+        let path = std::env::temp_dir().join(format!(
+            "tuio-simulator-file-sink-{}.osc",
+            uuid::Uuid::new_v4()
+        ));
+        let config = Config {
+            file_sink_path: Some(path.to_string_lossy().into_owned()),
+            ..Config::default()
+        };
+
+        let sink = FileSink::new();
+        sink.start(&config).await.unwrap();
+        assert_eq!(sink.get_connections(), 1);
+
+        assert!(sink.send(&[1, 2, 3, 4]).await.is_empty());
+        sink.stop().await;
+        assert!(!sink.is_running());
+
+        let data = std::fs::read(&path).unwrap();
+        assert_eq!(data, vec![0, 0, 0, 4, 1, 2, 3, 4]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_file_sink_drops_frames_when_behind() {
+        let path = std::env::temp_dir().join(format!(
+            "tuio-simulator-file-sink-{}.osc",
+            uuid::Uuid::new_v4()
+        ));
+        let config = Config {
+            file_sink_path: Some(path.to_string_lossy().into_owned()),
+            ..Config::default()
+        };
+
+        let sink = FileSink::new();
+        sink.start(&config).await.unwrap();
+
+        // The writer task cannot run before this test yields
+        for _ in 0..QUEUE_FRAMES {
+            assert!(sink.send(&[1]).await.is_empty());
+        }
+        let errors = sink.send(&[1]).await;
+        sink.stop().await;
+
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("Dropped"));
+        let data = std::fs::read(&path).unwrap();
+        assert_eq!(data.len(), QUEUE_FRAMES * 5);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
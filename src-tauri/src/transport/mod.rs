@@ -0,0 +1,265 @@
+pub mod bind;
+pub mod clients;
+pub mod file;
+
+use crate::bus::EventBus;
+use crate::state::Config;
+use crate::tcp::TcpServer;
+use crate::udp::UdpSender;
+use crate::websocket::WebSocketServer;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use bind::StartError;
+use clients::ClientRegistry;
+use file::FileSink;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// Kind of transport sink a frame can be sent to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SinkKind {
+    WebSocket,
+    Udp,
+    Tcp,
+    File,
+}
+
+impl SinkKind {
+    /// Identifier used in events and on the frontend
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SinkKind::WebSocket => "websocket",
+            SinkKind::Udp => "udp",
+            SinkKind::Tcp => "tcp",
+            SinkKind::File => "file",
+        }
+    }
+}
+
+impl fmt::Display for SinkKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SinkKind::WebSocket => write!(f, "WebSocket"),
+            SinkKind::Udp => write!(f, "UDP"),
+            SinkKind::Tcp => write!(f, "TCP"),
+            SinkKind::File => write!(f, "file"),
+        }
+    }
+}
+
+/// Connectivity of a single sink, reported through `ServerStatus`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SinkStatus {
+    pub kind: SinkKind,
+    pub enabled: bool,
+    pub running: bool,
+    /// Connected clients for servers, configured targets for senders
+    pub connections: usize,
+    /// Addresses clients can connect to, empty for senders
+    pub addresses: Vec<SocketAddr>,
+}
+
+/// Send failure for a single sink target
+#[derive(Debug, Clone)]
+pub struct SinkError {
+    pub kind: SinkKind,
+    pub target: String,
+    pub message: String,
+}
+
+/// A destination for encoded TUIO bundles
+///
+/// Every generated frame is handed to all running sinks. Sinks are
+/// configured from `Config` when they are started.
+#[async_trait]
+pub trait TransportSink: Send + Sync {
+    /// The kind of this sink
+    fn kind(&self) -> SinkKind;
+
+    /// Start the sink using its settings from the config
+    async fn start(&self, config: &Config) -> Result<()>;
+
+    /// Stop the sink and drop all of its connections
+    async fn stop(&self);
+
+    /// Send an encoded bundle, returning per-target errors
+    async fn send(&self, data: &[u8]) -> Vec<SinkError>;
+
+    /// Whether the sink is running
+    fn is_running(&self) -> bool;
+
+    /// Number of connected clients or configured targets while running
+    fn get_connections(&self) -> usize;
+
+    /// Addresses the sink is listening on while running
+    fn local_addrs(&self) -> Vec<SocketAddr> {
+        Vec::new()
+    }
+
+    /// Current status of the sink
+    fn status(&self, enabled: bool) -> SinkStatus {
+        let running = self.is_running();
+        SinkStatus {
+            kind: self.kind(),
+            enabled,
+            running,
+            connections: if running { self.get_connections() } else { 0 },
+            addresses: self
+                .local_addrs()
+                .into_iter()
+                .flat_map(bind::reachable_addresses)
+                .collect(),
+        }
+    }
+}
+
+/// Registry of all transport sinks
+///
+/// Which sinks take part in a session is controlled by
+/// `Config::enabled_sinks`.
+pub struct SinkRegistry {
+    sinks: Vec<Arc<dyn TransportSink>>,
+    clients: Arc<ClientRegistry>,
+}
+
+impl SinkRegistry {
+    /// Create a registry with all available sinks
+    pub fn new(events: EventBus) -> Self {
+        let clients = Arc::new(ClientRegistry::new(events));
+
+        Self {
+            sinks: vec![
+                Arc::new(WebSocketServer::new(clients.clone())),
+                Arc::new(UdpSender::new()),
+                Arc::new(TcpServer::new(clients.clone())),
+                Arc::new(FileSink::new()),
+            ],
+            clients,
+        }
+    }
+
+    /// Clients connected to the servers of all sinks
+    pub fn clients(&self) -> &Arc<ClientRegistry> {
+        &self.clients
+    }
+
+    /// Get the sink of the given kind
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn get(&self, kind: SinkKind) -> Result<Arc<dyn TransportSink>> {
+        self.sinks
+            .iter()
+            .find(|sink| sink.kind() == kind)
+            .cloned()
+            .ok_or_else(|| anyhow!("No {} sink registered", kind))
+    }
+
+    /// Start all sinks enabled in the config
+    ///
+    /// If one sink fails to start, all sinks are stopped again.
+    pub async fn start(&self, config: &Config) -> Result<(), StartError> {
+        for sink in &self.sinks {
+            if !config.enabled_sinks.contains(&sink.kind()) {
+                continue;
+            }
+
+            if let Err(e) = sink.start(config).await {
+                self.stop().await;
+                return Err(StartError::from_sink(sink.kind(), e));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stop all sinks
+    pub async fn stop(&self) {
+        for sink in &self.sinks {
+            sink.stop().await;
+        }
+    }
+
+    /// Send an encoded bundle to all running sinks
+    pub async fn send(&self, data: &[u8]) -> Vec<SinkError> {
+        let mut errors = Vec::new();
+
+        for sink in &self.sinks {
+            if sink.is_running() {
+                errors.extend(sink.send(data).await);
+            }
+        }
+
+        errors
+    }
+
+    /// Get the total number of connections across all running sinks
+    pub fn get_connections(&self) -> usize {
+        self.sinks
+            .iter()
+            .filter(|sink| sink.is_running())
+            .map(|sink| sink.get_connections())
+            .sum()
+    }
+
+    /// Get the status of every sink
+    pub fn statuses(&self, enabled_sinks: &[SinkKind]) -> Vec<SinkStatus> {
+        self.sinks
+            .iter()
+            .map(|sink| sink.status(enabled_sinks.contains(&sink.kind())))
+            .collect()
+    }
+}
+
+impl Default for SinkRegistry {
+    fn default() -> Self {
+        Self::new(EventBus::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_statuses() {
+        let registry = SinkRegistry::default();
+        let statuses = registry.statuses(&[SinkKind::WebSocket]);
+
+        assert_eq!(statuses.len(), 4);
+        for status in &statuses {
+            assert_eq!(status.enabled, status.kind == SinkKind::WebSocket);
+            assert!(!status.running);
+            assert_eq!(status.connections, 0);
+        }
+    }
+
+    #[test]
+    fn test_registry_get() {
+        let registry = SinkRegistry::default();
+        let sink = registry.get(SinkKind::Udp).unwrap();
+        assert_eq!(sink.kind(), SinkKind::Udp);
+    }
+
+    #[tokio::test]
+    async fn test_registry_start_failure_stops_all() {
+        let registry = SinkRegistry::default();
+        let config = Config {
+            enabled_sinks: vec![SinkKind::Udp, SinkKind::File],
+            file_sink_path: None,
+            ..Config::default()
+        };
+
+        // The file sink has no path, so starting fails
+        assert!(registry.start(&config).await.is_err());
+        assert!(!registry.get(SinkKind::Udp).unwrap().is_running());
+    }
+
+    #[test]
+    fn test_sink_kind_serialization() {
+        let json = serde_json::to_string(&SinkKind::WebSocket).unwrap();
+        assert_eq!(json, "\"websocket\"");
+    }
+}
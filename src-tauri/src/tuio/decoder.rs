@@ -0,0 +1,435 @@
+use crate::state::{ObjectBounds, ObjectKind, PointerType, TuioObject};
+use crate::tuio::messages::FrameMessage;
+use crate::tuio::tuio11::Profile;
+use rosc::{OscMessage, OscPacket, OscType};
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn for_each_message(packet: &OscPacket, f: &mut impl FnMut(&OscMessage)) {
+    match packet {
+        OscPacket::Message(message) => f(message),
+        OscPacket::Bundle(bundle) => {
+            for packet in &bundle.content {
+                for_each_message(packet, f);
+            }
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn for_each_message_mut(packet: &mut OscPacket, f: &mut impl FnMut(&mut OscMessage)) {
+    match packet {
+        OscPacket::Message(message) => f(message),
+        OscPacket::Bundle(bundle) => {
+            for packet in &mut bundle.content {
+                for_each_message_mut(packet, f);
+            }
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn int(args: &[OscType], index: usize) -> Option<i32> {
+    match args.get(index)? {
+        OscType::Int(value) => Some(*value),
+        _ => None,
+    }
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn float(args: &[OscType], index: usize) -> Option<f32> {
+    match args.get(index)? {
+        OscType::Float(value) => Some(*value),
+        _ => None,
+    }
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn string(args: &[OscType], index: usize) -> Option<&str> {
+    match args.get(index)? {
+        OscType::String(value) => Some(value),
+        _ => None,
+    }
+}
+
+/// Rewrite the frame ID and time of a TUIO bundle
+///
+/// TUIO 2.0 bundles get a new FRM message, TUIO 1.1 bundles new frame
+/// IDs in their fseq messages.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn rewrite_frame(packet: &mut OscPacket, frame_id: u32, timestamp: i64) {
+    for_each_message_mut(packet, &mut |message| {
+        if message.addr == "/tuio2/frm" {
+            let dimension = int(&message.args, 2).unwrap_or(0);
+            let source = string(&message.args, 3).unwrap_or_default().to_string();
+            let mut frm = FrameMessage::new(
+                frame_id,
+                timestamp,
+                (dimension >> 16) as u16,
+                dimension as u16,
+                source,
+            );
+            if let Some(depth) = int(&message.args, 4) {
+                frm = frm.with_depth(depth as u16);
+            }
+            *message = frm.to_osc();
+        } else if message.addr.starts_with("/tuio/") && string(&message.args, 0) == Some("fseq") {
+            message.args.truncate(1);
+            message.args.push(OscType::Int(frame_id as i32));
+        }
+    });
+}
+
+/// Object with the given session ID, created if it is new
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn object_at(
+    objects: &mut HashMap<u32, TuioObject>,
+    session_id: i32,
+    kind: ObjectKind,
+    x: f32,
+    y: f32,
+    timestamp: i64,
+) -> &mut TuioObject {
+    let session_id = session_id as u32;
+    let object = objects
+        .entry(session_id)
+        .or_insert_with(|| TuioObject::new(session_id, 0, 0, x, y, timestamp));
+    object.kind = kind;
+    object.x = x;
+    object.y = y;
+    object.last_x = x;
+    object.last_y = y;
+    object.last_update = timestamp;
+    object
+}
+
+/// Update a blob, or the bounds of the token or pointer with the same
+/// session ID, returning the updated object
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn apply_bounds(
+    objects: &mut HashMap<u32, TuioObject>,
+    session_id: i32,
+    x: f32,
+    y: f32,
+    angle: f32,
+    bounds: ObjectBounds,
+    timestamp: i64,
+) -> &mut TuioObject {
+    let id = session_id as u32;
+    if objects
+        .get(&id)
+        .is_some_and(|object| object.kind != ObjectKind::Blob)
+    {
+        let object = objects.get_mut(&id).expect("object exists");
+        object.bounds = Some(bounds);
+        return object;
+    }
+
+    let blob = object_at(objects, session_id, ObjectKind::Blob, x, y, timestamp);
+    blob.angle = angle;
+    blob.last_angle = angle;
+    blob.bounds = Some(bounds);
+    blob
+}
+
+/// Session IDs listed in an alive message, starting at the given argument
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn alive_ids(args: &[OscType], start: usize) -> Vec<u32> {
+    (start..args.len())
+        .filter_map(|i| int(args, i))
+        .map(|id| id as u32)
+        .collect()
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn apply_message(
+    objects: &mut HashMap<u32, TuioObject>,
+    message: &OscMessage,
+    timestamp: i64,
+) -> Option<()> {
+    let args = &message.args;
+    match message.addr.as_str() {
+        "/tuio2/tok" => {
+            let type_user_id = int(args, 1)?;
+            let token = object_at(
+                objects,
+                int(args, 0)?,
+                ObjectKind::Token,
+                float(args, 3)?,
+                float(args, 4)?,
+                timestamp,
+            );
+            token.type_id = (type_user_id >> 16) as u16;
+            token.user_id = type_user_id as u16;
+            token.component_id = int(args, 2)? as u16;
+            token.angle = float(args, 5)?;
+            token.last_angle = token.angle;
+            token.x_vel = float(args, 6)?;
+            token.y_vel = float(args, 7)?;
+            token.angle_vel = float(args, 8)?;
+        }
+        "/tuio2/ptr" => {
+            let type_user_id = int(args, 1)?;
+            let pointer = object_at(
+                objects,
+                int(args, 0)?,
+                ObjectKind::Pointer,
+                float(args, 3)?,
+                float(args, 4)?,
+                timestamp,
+            );
+            pointer.type_id = (type_user_id >> 16) as u16;
+            pointer.user_id = type_user_id as u16;
+            pointer.component_id = int(args, 2)? as u16;
+            pointer.angle = float(args, 5)?;
+            pointer.last_angle = pointer.angle;
+            pointer.shear = float(args, 6)?;
+            pointer.radius = float(args, 7)?;
+            pointer.pressure = float(args, 8)?;
+            pointer.last_pressure = pointer.pressure;
+            pointer.x_vel = float(args, 9)?;
+            pointer.y_vel = float(args, 10)?;
+            pointer.pressure_vel = float(args, 11)?;
+            pointer.accel = float(args, 12)?;
+        }
+        "/tuio2/bnd" => {
+            let bounds = ObjectBounds {
+                width: float(args, 4)?,
+                height: float(args, 5)?,
+                area: float(args, 6)?,
+            };
+            let object = apply_bounds(
+                objects,
+                int(args, 0)?,
+                float(args, 1)?,
+                float(args, 2)?,
+                float(args, 3)?,
+                bounds,
+                timestamp,
+            );
+
+            // Optional motion block
+            if args.len() > 7 {
+                object.x_vel = float(args, 7)?;
+                object.y_vel = float(args, 8)?;
+                object.angle_vel = float(args, 9)?;
+                object.accel = float(args, 10)?;
+                object.angle_accel = float(args, 11)?;
+            }
+        }
+        "/tuio2/alv" => {
+            let alive = alive_ids(args, 0);
+            objects.retain(|id, _| alive.contains(id));
+        }
+        address => {
+            let profile = Profile::ALL
+                .into_iter()
+                .find(|profile| profile.address() == address)?;
+            return apply_tuio11_message(objects, profile, args, timestamp);
+        }
+    }
+    Some(())
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn apply_tuio11_message(
+    objects: &mut HashMap<u32, TuioObject>,
+    profile: Profile,
+    args: &[OscType],
+    timestamp: i64,
+) -> Option<()> {
+    match (string(args, 0)?, profile) {
+        ("set", Profile::Object) => {
+            let token = object_at(
+                objects,
+                int(args, 1)?,
+                ObjectKind::Token,
+                float(args, 3)?,
+                float(args, 4)?,
+                timestamp,
+            );
+            token.component_id = int(args, 2)? as u16;
+            token.angle = float(args, 5)?;
+            token.last_angle = token.angle;
+            token.x_vel = float(args, 6)?;
+            token.y_vel = float(args, 7)?;
+            token.angle_vel = float(args, 8)? * TAU;
+            token.accel = float(args, 9)?;
+            token.angle_accel = float(args, 10)? * TAU;
+        }
+        ("set", Profile::Cursor) => {
+            let pointer = object_at(
+                objects,
+                int(args, 1)?,
+                ObjectKind::Pointer,
+                float(args, 2)?,
+                float(args, 3)?,
+                timestamp,
+            );
+            pointer.type_id = PointerType::Finger.type_id();
+            pointer.pressure = 1.0;
+            pointer.last_pressure = pointer.pressure;
+            pointer.x_vel = float(args, 4)?;
+            pointer.y_vel = float(args, 5)?;
+            pointer.accel = float(args, 6)?;
+        }
+        ("set", Profile::Blob) => {
+            let bounds = ObjectBounds {
+                width: float(args, 5)?,
+                height: float(args, 6)?,
+                area: float(args, 7)?,
+            };
+            apply_bounds(
+                objects,
+                int(args, 1)?,
+                float(args, 2)?,
+                float(args, 3)?,
+                float(args, 4)?,
+                bounds,
+                timestamp,
+            );
+        }
+        ("alive", _) => {
+            let kind = match profile {
+                Profile::Object => ObjectKind::Token,
+                Profile::Cursor => ObjectKind::Pointer,
+                Profile::Blob => ObjectKind::Blob,
+            };
+            let alive = alive_ids(args, 1);
+            objects.retain(|id, object| object.kind != kind || alive.contains(id));
+        }
+        _ => {}
+    }
+    Some(())
+}
+
+/// Apply the objects of a TUIO 2.0 or 1.1 bundle to a set of objects
+///
+/// Objects missing from the alive messages are removed. Only positions,
+/// motion and bounds are restored, other components are ignored.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn apply_packet(objects: &mut HashMap<u32, TuioObject>, packet: &OscPacket, timestamp: i64) {
+    for_each_message(packet, &mut |message| {
+        apply_message(objects, message, timestamp);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuio::encoder::{create_tuio11_bundle, create_tuio_bundle, EncoderOptions};
+    use rosc::OscBundle;
+
+    fn test_objects() -> Vec<TuioObject> {
+        let mut token = TuioObject::new(1, 5, 7, 0.25, 0.5, 0);
+        token.angle = 1.0;
+        let pointer = TuioObject::new_pointer(2, PointerType::Finger, 0.75, 0.5, 0);
+        let blob = TuioObject::new_blob(3, 0.5, 0.5, ObjectBounds::new(0.1, 0.2), 0);
+        vec![token, pointer, blob]
+    }
+
+    fn frm_args(packet: &OscPacket) -> Vec<OscType> {
+        let mut args = Vec::new();
+        for_each_message(packet, &mut |message| {
+            if message.addr == "/tuio2/frm" {
+                args = message.args.clone();
+            }
+        });
+        args
+    }
+
+    #[test]
+    fn test_rewrite_frame() {
+        let bundle = create_tuio_bundle(
+            1,
+            0,
+            1920,
+            1080,
+            "test",
+            &test_objects(),
+            &EncoderOptions::default(),
+        );
+        let mut packet = OscPacket::Bundle(bundle);
+        rewrite_frame(&mut packet, 42, 1500);
+
+        let expected = FrameMessage::new(42, 1500, 1920, 1080, "test".to_string()).to_osc();
+        assert_eq!(frm_args(&packet), expected.args);
+
+        let mut packet = OscPacket::Bundle(create_tuio11_bundle(
+            1,
+            "test",
+            &test_objects(),
+            &EncoderOptions::default(),
+        ));
+        rewrite_frame(&mut packet, 42, 1500);
+        let mut fseqs = Vec::new();
+        for_each_message(&packet, &mut |message| {
+            if string(&message.args, 0) == Some("fseq") {
+                fseqs.push(int(&message.args, 1));
+            }
+        });
+        assert_eq!(fseqs, vec![Some(42); 3]);
+    }
+
+    fn assert_objects(objects: &HashMap<u32, TuioObject>) {
+        assert_eq!(objects.len(), 3);
+        assert_eq!(objects[&1].kind, ObjectKind::Token);
+        assert_eq!(objects[&1].component_id, 7);
+        assert_eq!(objects[&1].x, 0.25);
+        assert!((objects[&1].angle - 1.0).abs() < 1e-6);
+        assert_eq!(objects[&2].kind, ObjectKind::Pointer);
+        assert_eq!(objects[&2].x, 0.75);
+        assert_eq!(objects[&3].kind, ObjectKind::Blob);
+        assert_eq!(objects[&3].bounds.as_ref().unwrap().height, 0.2);
+    }
+
+    #[test]
+    fn test_apply_tuio2_packet() {
+        let mut sent = test_objects();
+        sent[2].x_vel = 0.1;
+        sent[2].angle_accel = 0.2;
+        let bundle =
+            create_tuio_bundle(1, 0, 1920, 1080, "test", &sent, &EncoderOptions::default());
+        let mut objects = HashMap::new();
+        apply_packet(&mut objects, &OscPacket::Bundle(bundle), 10);
+        assert_objects(&objects);
+        assert_eq!(objects[&1].type_id, 5);
+        assert_eq!(objects[&3].x_vel, 0.1);
+        assert_eq!(objects[&3].angle_accel, 0.2);
+
+        let bundle = create_tuio_bundle(
+            2,
+            0,
+            1920,
+            1080,
+            "test",
+            &test_objects()[..1],
+            &EncoderOptions::default(),
+        );
+        apply_packet(&mut objects, &OscPacket::Bundle(bundle), 20);
+        assert_eq!(objects.len(), 1);
+    }
+
+    #[test]
+    fn test_apply_tuio11_packet() {
+        let bundle = create_tuio11_bundle(1, "test", &test_objects(), &EncoderOptions::default());
+        let mut objects = HashMap::new();
+        apply_packet(&mut objects, &OscPacket::Bundle(bundle), 10);
+        assert_objects(&objects);
+
+        let empty = OscPacket::Bundle(OscBundle {
+            timetag: rosc::OscTime {
+                seconds: 0,
+                fractional: 1,
+            },
+            content: vec![OscPacket::Message(
+                crate::tuio::tuio11::AliveMessage::new(Profile::Cursor, vec![]).to_osc(),
+            )],
+        });
+        apply_packet(&mut objects, &empty, 20);
+        assert_eq!(objects.len(), 2);
+        assert!(!objects.contains_key(&2));
+    }
+}
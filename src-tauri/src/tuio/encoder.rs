@@ -1,22 +1,73 @@
-use super::messages::{AliveMessage, FrameMessage, PointerMessage, TokenMessage};
-use crate::state::TuioObject;
+use super::geometry::{
+    convex_hull, euler_to_quaternion, quaternion_to_axis_angle, rotate_vector, transform_points,
+};
+use super::messages::{
+    AliveAssociationsMessage, AliveMessage, Bounds3DMessage, BoundsMessage,
+    ContainerAssociationMessage, ControlMessage, ConvexHullMessage, DataMessage, FrameMessage,
+    InnerContourMessage, LinkAssociationMessage, OuterContourMessage, Pointer3DMessage,
+    PointerMessage, Rotation3DMessage, SkeletonMessage, SymbolMessage, Token3DMessage,
+    TokenMessage,
+};
+use super::tuio11::{
+    self, BlobSetMessage, CursorSetMessage, FseqMessage, ObjectSetMessage, Profile, SourceMessage,
+};
+use crate::state::{Config, ObjectGeometry, ObjectKind, ObjectLink, Pose3D, TuioObject};
 use anyhow::Result;
-use rosc::{encoder, OscBundle, OscPacket, OscTime};
-
-/// Message type for TUIO objects
-#[derive(Debug, Clone, Copy)]
-#[allow(dead_code)]
-pub enum MessageType {
-    Token,  // TOK - Tagged tangible objects (fiducials)
-    Pointer, // PTR - Pointing gestures (touch, stylus)
+use rosc::{encoder, OscBundle, OscMessage, OscPacket, OscTime};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// TUIO protocol version used to encode bundles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ProtocolVersion {
+    /// TUIO 1.1 profiles (/tuio/2Dobj, /tuio/2Dcur, /tuio/2Dblb)
+    #[serde(rename = "1.1")]
+    Tuio11,
+    /// TUIO 2.0 messages (/tuio2/frm, /tuio2/tok, /tuio2/alv, ...)
+    #[default]
+    #[serde(rename = "2.0")]
+    Tuio2,
+}
+
+/// Optional TUIO 2.0 features of an encoded bundle
+#[derive(Debug, Clone, Default)]
+pub struct EncoderOptions {
+    /// Sensor depth, enables the 3D component messages when set
+    pub depth: Option<u16>,
+    /// Session IDs of tokens whose TOK or 2Dobj set is left out, e.g. as
+    /// they did not move
+    pub skip_tokens: HashSet<u32>,
+    /// TUIO 1.1 profiles that had objects in the previous frame, used to
+    /// send optional profiles once more after their last object
+    pub previous_profiles: HashSet<Profile>,
+}
+
+impl EncoderOptions {
+    /// Options selected in the config
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            depth: config.enable_3d.then_some(config.depth),
+            skip_tokens: HashSet::new(),
+            previous_profiles: HashSet::new(),
+        }
+    }
 }
 
 /// Creates a complete TUIO 2.0 OSC bundle
 ///
 /// A TUIO 2.0 bundle contains:
 /// 1. FRM (Frame) message - opens the bundle
-/// 2. Object messages (TOK or PTR) - one per object (0 or more)
-/// 3. ALV (Alive) message - closes the bundle
+/// 2. Object messages - per object (0 or more), TOK for tokens and PTR
+///    for pointers, followed by BND and SYM if the object has bounds or
+///    symbol data, by CHG, OCG, ICG and SKG if it has geometry and by
+///    CTL and DAT if it has controls or data
+/// 3. Association messages - ALA, COA and LIA if any object is
+///    associated with another one
+/// 4. ALV (Alive) message - closes the bundle
+///
+/// With a sensor depth, FRM carries the depth and objects with a 3D
+/// pose are followed by T3D or P3D, R3D and B3D (if they have bounds).
+/// The 2D messages are still sent for clients without 3D support.
 pub fn create_tuio_bundle(
     frame_id: u32,
     timestamp: i64,
@@ -24,30 +75,22 @@ pub fn create_tuio_bundle(
     height: u16,
     source: &str,
     objects: &[TuioObject],
-) -> OscBundle {
-    create_tuio_bundle_with_type(frame_id, timestamp, width, height, source, objects, MessageType::Token)
-}
-
-/// Creates a TUIO bundle with specified message type
-pub fn create_tuio_bundle_with_type(
-    frame_id: u32,
-    timestamp: i64,
-    width: u16,
-    height: u16,
-    source: &str,
-    objects: &[TuioObject],
-    message_type: MessageType,
+    options: &EncoderOptions,
 ) -> OscBundle {
     let mut content = Vec::new();
 
     // 1. Add FRM message
-    let frm = FrameMessage::new(frame_id, timestamp, width, height, source.to_string());
+    let mut frm = FrameMessage::new(frame_id, timestamp, width, height, source.to_string());
+    if let Some(depth) = options.depth {
+        frm = frm.with_depth(depth);
+    }
     content.push(OscPacket::Message(frm.to_osc()));
 
-    // 2. Add object messages (TOK or PTR) for each object
+    // 2. Add the component messages for each object
     for obj in objects {
-        let msg = match message_type {
-            MessageType::Token => {
+        let msg = match obj.kind {
+            ObjectKind::Token if options.skip_tokens.contains(&obj.session_id) => None,
+            ObjectKind::Token => {
                 let tok = TokenMessage::new(
                     obj.session_id,
                     obj.type_id,
@@ -60,9 +103,9 @@ pub fn create_tuio_bundle_with_type(
                     obj.y_vel,
                     obj.angle_vel,
                 );
-                tok.to_osc()
+                Some(tok.to_osc())
             }
-            MessageType::Pointer => {
+            ObjectKind::Pointer => {
                 let ptr = PointerMessage::new(
                     obj.session_id,
                     obj.type_id,
@@ -71,16 +114,90 @@ pub fn create_tuio_bundle_with_type(
                     obj.x,
                     obj.y,
                     obj.angle,
+                    obj.shear,
+                    obj.radius,
+                    obj.pressure,
                     obj.x_vel,
                     obj.y_vel,
+                    obj.pressure_vel,
+                    obj.accel,
                 );
-                ptr.to_osc()
+                Some(ptr.to_osc())
             }
+            // Blobs are described by their BND message only
+            ObjectKind::Blob => None,
         };
-        content.push(OscPacket::Message(msg));
+        if let Some(msg) = msg {
+            content.push(OscPacket::Message(msg));
+        }
+
+        // BND shares the session ID of the TOK/PTR it is attached to
+        if let Some(bounds) = &obj.bounds {
+            let bnd = BoundsMessage::new(
+                obj.session_id,
+                obj.x,
+                obj.y,
+                obj.angle,
+                bounds.width,
+                bounds.height,
+                bounds.area,
+                obj.x_vel,
+                obj.y_vel,
+                obj.angle_vel,
+                obj.accel,
+                obj.angle_accel,
+            );
+            content.push(OscPacket::Message(bnd.to_osc()));
+        }
+
+        // SYM follows the component messages of the same session ID
+        if let Some(symbol) = &obj.symbol {
+            let sym = SymbolMessage::new(
+                obj.session_id,
+                symbol.type_id,
+                obj.user_id,
+                obj.component_id,
+                symbol.group.clone(),
+                symbol.data.clone(),
+            );
+            content.push(OscPacket::Message(sym.to_osc()));
+        }
+
+        if let Some(geometry) = &obj.geometry {
+            content.extend(
+                create_geometry_messages(obj, geometry)
+                    .into_iter()
+                    .map(OscPacket::Message),
+            );
+        }
+
+        if !obj.controls.is_empty() {
+            let ctl = ControlMessage::new(obj.session_id, obj.controls.clone());
+            content.push(OscPacket::Message(ctl.to_osc()));
+        }
+
+        for data in &obj.data {
+            let dat = DataMessage::new(obj.session_id, data.mime.clone(), data.data.clone());
+            content.push(OscPacket::Message(dat.to_osc()));
+        }
+
+        if let (Some(_), Some(pose)) = (options.depth, &obj.pose_3d) {
+            content.extend(
+                create_3d_messages(obj, pose)
+                    .into_iter()
+                    .map(OscPacket::Message),
+            );
+        }
     }
 
-    // 3. Add ALV message with all active session IDs
+    // 3. Add the association messages
+    content.extend(
+        create_association_messages(objects)
+            .into_iter()
+            .map(OscPacket::Message),
+    );
+
+    // 4. Add ALV message with all active session IDs
     let session_ids: Vec<u32> = objects.iter().map(|obj| obj.session_id).collect();
     let alv = AliveMessage::new(session_ids);
     content.push(OscPacket::Message(alv.to_osc()));
@@ -95,6 +212,271 @@ pub fn create_tuio_bundle_with_type(
     OscBundle { timetag, content }
 }
 
+/// Creates ALA and the COA and LIA messages of all associated objects
+///
+/// Messages are ordered by session ID so that bundles stay stable
+/// across frames.
+fn create_association_messages(objects: &[TuioObject]) -> Vec<OscMessage> {
+    let mut associated = BTreeSet::new();
+    let mut containers: BTreeMap<(u32, u32), Vec<u32>> = BTreeMap::new();
+    let mut links: BTreeMap<(u32, bool), Vec<&ObjectLink>> = BTreeMap::new();
+
+    for obj in objects {
+        if let Some(container) = obj.container {
+            let key = (container.container_id, container.slot);
+            containers.entry(key).or_default().push(obj.session_id);
+            associated.insert(container.container_id);
+            associated.insert(obj.session_id);
+        }
+
+        for link in &obj.links {
+            let key = (obj.session_id, link.physical);
+            links.entry(key).or_default().push(link);
+            associated.insert(obj.session_id);
+            associated.insert(link.target_id);
+        }
+    }
+
+    if associated.is_empty() {
+        return Vec::new();
+    }
+
+    let ala = AliveAssociationsMessage::new(associated.into_iter().collect());
+    let mut messages = vec![ala.to_osc()];
+
+    for ((container_id, slot), mut contained_ids) in containers {
+        contained_ids.sort_unstable();
+        let coa = ContainerAssociationMessage::new(container_id, slot, contained_ids);
+        messages.push(coa.to_osc());
+    }
+
+    for ((session_id, physical), links) in links {
+        let targets = links
+            .iter()
+            .map(|link| (link.target_id, link.out_port, link.in_port))
+            .collect();
+        let lia = LinkAssociationMessage::new(session_id, physical, targets);
+        messages.push(lia.to_osc());
+    }
+
+    messages
+}
+
+/// Creates the geometry messages of an object, placed at its position
+fn create_geometry_messages(obj: &TuioObject, geometry: &ObjectGeometry) -> Vec<OscMessage> {
+    let mut messages = Vec::new();
+    let transform = |points: &[[f32; 2]]| transform_points(points, obj.x, obj.y, obj.angle);
+
+    if !geometry.outline.is_empty() {
+        let outline = transform(&geometry.outline);
+        let chg = ConvexHullMessage::new(obj.session_id, convex_hull(&outline));
+        messages.push(chg.to_osc());
+        let ocg = OuterContourMessage::new(obj.session_id, outline);
+        messages.push(ocg.to_osc());
+    }
+
+    if !geometry.inner_contours.is_empty() {
+        let contours = geometry
+            .inner_contours
+            .iter()
+            .map(|c| transform(c))
+            .collect();
+        let icg = InnerContourMessage::new(obj.session_id, contours);
+        messages.push(icg.to_osc());
+    }
+
+    if !geometry.skeleton.is_empty() {
+        let branches = geometry.skeleton.iter().map(|b| transform(b)).collect();
+        let skg = SkeletonMessage::new(obj.session_id, branches);
+        messages.push(skg.to_osc());
+    }
+
+    messages
+}
+
+/// Creates the 3D component messages of an object with a 3D pose
+fn create_3d_messages(obj: &TuioObject, pose: &Pose3D) -> Vec<OscMessage> {
+    let mut messages = Vec::new();
+
+    let rotation = euler_to_quaternion(pose.roll, pose.pitch, obj.angle);
+    let (angle, axis) = quaternion_to_axis_angle(rotation);
+    let position = [obj.x, obj.y, pose.z];
+    let velocity = [obj.x_vel, obj.y_vel, pose.z_vel];
+    let rotation_vel = [pose.roll_vel, pose.pitch_vel, obj.angle_vel];
+    let rotation_speed = rotation_vel.iter().map(|v| v * v).sum::<f32>().sqrt();
+
+    match obj.kind {
+        ObjectKind::Token => {
+            let t3d = Token3DMessage::new(
+                obj.session_id,
+                obj.type_id,
+                obj.user_id,
+                obj.component_id,
+                position,
+                angle,
+                axis,
+                velocity,
+                rotation_speed,
+                pose.accel,
+                pose.rotation_accel,
+            );
+            messages.push(t3d.to_osc());
+        }
+        ObjectKind::Pointer => {
+            // An untilted pointer points down onto the surface
+            let direction = rotate_vector(rotation, [0.0, 0.0, -1.0]);
+            let p3d = Pointer3DMessage::new(
+                obj.session_id,
+                obj.type_id,
+                obj.user_id,
+                obj.component_id,
+                position,
+                direction,
+                obj.radius,
+                obj.pressure,
+                velocity,
+                obj.pressure_vel,
+                pose.accel,
+            );
+            messages.push(p3d.to_osc());
+        }
+        ObjectKind::Blob => {}
+    }
+
+    let r3d = Rotation3DMessage::new(
+        obj.session_id,
+        [pose.roll, pose.pitch, obj.angle],
+        rotation_vel,
+        pose.rotation_accel,
+    );
+    messages.push(r3d.to_osc());
+
+    if let Some(bounds) = &obj.bounds {
+        let b3d = Bounds3DMessage::new(
+            obj.session_id,
+            position,
+            angle,
+            axis,
+            [bounds.width, bounds.height, pose.depth],
+            bounds.area * pose.depth,
+            velocity,
+            rotation_speed,
+            pose.accel,
+            pose.rotation_accel,
+        );
+        messages.push(b3d.to_osc());
+    }
+
+    messages
+}
+
+/// Creates a TUIO 1.1 OSC bundle
+///
+/// The bundle contains one block of messages per profile, each made of:
+/// 1. source message
+/// 2. alive message with the session IDs of the profile
+/// 3. set messages - one per object of the profile (0 or more)
+/// 4. fseq message with the frame ID
+///
+/// Tokens are sent on 2Dobj, pointers on 2Dcur and blobs on 2Dblb.
+/// Tokens in `skip_tokens` are listed as alive without a set message.
+/// 2Dobj and 2Dcur are sent in every frame. 2Dblb is left out without
+/// blobs, unless it had blobs in the previous frame, so clients notice
+/// the removals.
+pub fn create_tuio11_bundle(
+    frame_id: u32,
+    source: &str,
+    objects: &[TuioObject],
+    options: &EncoderOptions,
+) -> OscBundle {
+    let mut content = Vec::new();
+
+    for profile in Profile::ALL {
+        let mut session_ids = Vec::new();
+        let mut sets: Vec<OscMessage> = Vec::new();
+
+        for obj in objects {
+            let set = match (profile, obj.kind) {
+                // Skipped tokens stay alive without a set message
+                (Profile::Object, ObjectKind::Token)
+                    if options.skip_tokens.contains(&obj.session_id) =>
+                {
+                    session_ids.push(obj.session_id);
+                    continue;
+                }
+                (Profile::Object, ObjectKind::Token) => ObjectSetMessage::new(
+                    obj.session_id,
+                    obj.component_id,
+                    obj.x,
+                    obj.y,
+                    obj.angle,
+                    obj.x_vel,
+                    obj.y_vel,
+                    obj.angle_vel,
+                    obj.accel,
+                    obj.angle_accel,
+                )
+                .to_osc(),
+                (Profile::Cursor, ObjectKind::Pointer) => CursorSetMessage::new(
+                    obj.session_id,
+                    obj.x,
+                    obj.y,
+                    obj.x_vel,
+                    obj.y_vel,
+                    obj.accel,
+                )
+                .to_osc(),
+                (Profile::Blob, ObjectKind::Blob) => match &obj.bounds {
+                    Some(bounds) => BlobSetMessage::new(
+                        obj.session_id,
+                        obj.x,
+                        obj.y,
+                        obj.angle,
+                        bounds.width,
+                        bounds.height,
+                        bounds.area,
+                        obj.x_vel,
+                        obj.y_vel,
+                        obj.angle_vel,
+                        obj.accel,
+                        obj.angle_accel,
+                    )
+                    .to_osc(),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            session_ids.push(obj.session_id);
+            sets.push(set);
+        }
+
+        if profile.is_optional()
+            && session_ids.is_empty()
+            && !options.previous_profiles.contains(&profile)
+        {
+            continue;
+        }
+
+        let src = SourceMessage::new(profile, source.to_string());
+        content.push(OscPacket::Message(src.to_osc()));
+
+        let alv = tuio11::AliveMessage::new(profile, session_ids);
+        content.push(OscPacket::Message(alv.to_osc()));
+
+        content.extend(sets.into_iter().map(OscPacket::Message));
+
+        let fseq = FseqMessage::new(profile, frame_id);
+        content.push(OscPacket::Message(fseq.to_osc()));
+    }
+
+    let timetag = OscTime {
+        seconds: 0,
+        fractional: 1,
+    };
+
+    OscBundle { timetag, content }
+}
+
 /// Encodes an OSC bundle to binary format
 ///
 /// Returns the encoded binary data ready for transmission over WebSocket
@@ -104,44 +486,47 @@ pub fn encode_bundle(bundle: &OscBundle) -> Result<Vec<u8>> {
 }
 
 /// Convenience function to create and encode a TUIO bundle in one step
+///
+/// The sensor depth only applies to TUIO 2.0 bundles.
+#[allow(clippy::too_many_arguments)]
 pub fn create_and_encode_tuio_bundle(
+    version: ProtocolVersion,
     frame_id: u32,
     timestamp: i64,
     width: u16,
     height: u16,
     source: &str,
     objects: &[TuioObject],
+    options: &EncoderOptions,
 ) -> Result<Vec<u8>> {
-    let bundle = create_tuio_bundle(frame_id, timestamp, width, height, source, objects);
+    let bundle = match version {
+        ProtocolVersion::Tuio2 => {
+            create_tuio_bundle(frame_id, timestamp, width, height, source, objects, options)
+        }
+        ProtocolVersion::Tuio11 => create_tuio11_bundle(frame_id, source, objects, options),
+    };
     encode_bundle(&bundle)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::state::{
+        ContainerSlot, ControlValue, ObjectBounds, ObjectData, ObjectGeometry, ObjectLink,
+        ObjectSymbol, PointerType, Pose3D,
+    };
 
     fn create_test_object() -> TuioObject {
-        TuioObject {
-            session_id: 42,
-            type_id: 1,
-            user_id: 0,
-            component_id: 0,
-            x: 0.5,
-            y: 0.5,
-            angle: 1.57,
-            x_vel: 0.0,
-            y_vel: 0.0,
-            angle_vel: 0.0,
-            last_x: 0.5,
-            last_y: 0.5,
-            last_angle: 1.57,
-            last_update: 0,
-        }
+        let mut object = TuioObject::new(42, 1, 0, 0.5, 0.5, 0);
+        object.angle = 1.57;
+        object.last_angle = 1.57;
+        object
     }
 
     #[test]
     fn test_create_tuio_bundle_empty() {
-        let bundle = create_tuio_bundle(1, 1000, 1920, 1080, "test", &[]);
+        let bundle =
+            create_tuio_bundle(1, 1000, 1920, 1080, "test", &[], &EncoderOptions::default());
 
         assert_eq!(bundle.content.len(), 2); // FRM + ALV only
 
@@ -168,7 +553,15 @@ mod tests {
         obj2.session_id = 43;
 
         let objects = vec![obj1, obj2];
-        let bundle = create_tuio_bundle(1, 1000, 1920, 1080, "test", &objects);
+        let bundle = create_tuio_bundle(
+            1,
+            1000,
+            1920,
+            1080,
+            "test",
+            &objects,
+            &EncoderOptions::default(),
+        );
 
         assert_eq!(bundle.content.len(), 4); // FRM + 2*TOK + ALV
 
@@ -199,9 +592,406 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_create_tuio_bundle_mixed_kinds() {
+        let tok = create_test_object();
+        let ptr = TuioObject::new_pointer(43, PointerType::Stylus, 0.2, 0.8, 0);
+
+        let bundle = create_tuio_bundle(
+            1,
+            1000,
+            1920,
+            1080,
+            "test",
+            &[tok, ptr],
+            &EncoderOptions::default(),
+        );
+
+        assert_eq!(bundle.content.len(), 4); // FRM + TOK + PTR + ALV
+
+        if let OscPacket::Message(msg) = &bundle.content[1] {
+            assert_eq!(msg.addr, "/tuio2/tok");
+        } else {
+            panic!("Expected TOK message");
+        }
+
+        if let OscPacket::Message(msg) = &bundle.content[2] {
+            assert_eq!(msg.addr, "/tuio2/ptr");
+            // Stylus type ID 21 in the upper 16 bits
+            assert_eq!(msg.args[1], rosc::OscType::Int(21 << 16));
+        } else {
+            panic!("Expected PTR message");
+        }
+
+        if let OscPacket::Message(msg) = &bundle.content[3] {
+            assert_eq!(msg.args.len(), 2); // Both kinds are alive
+        } else {
+            panic!("Expected ALV message");
+        }
+    }
+
+    #[test]
+    fn test_create_tuio_bundle_with_bounds() {
+        let mut tok = create_test_object();
+        tok.bounds = Some(ObjectBounds::new(0.1, 0.2));
+        let blob = TuioObject::new_blob(43, 0.3, 0.3, ObjectBounds::new(0.2, 0.2), 0);
+
+        let bundle = create_tuio_bundle(
+            1,
+            1000,
+            1920,
+            1080,
+            "test",
+            &[tok, blob],
+            &EncoderOptions::default(),
+        );
+
+        // FRM + TOK + BND (token) + BND (blob) + ALV
+        let addrs: Vec<(&str, i32)> = bundle
+            .content
+            .iter()
+            .map(|packet| match packet {
+                OscPacket::Message(msg) => match msg.args.first() {
+                    Some(rosc::OscType::Int(id)) => (msg.addr.as_str(), *id),
+                    _ => (msg.addr.as_str(), -1),
+                },
+                _ => panic!("Expected message"),
+            })
+            .collect();
+        assert_eq!(
+            addrs,
+            vec![
+                ("/tuio2/frm", 1),
+                ("/tuio2/tok", 42),
+                ("/tuio2/bnd", 42),
+                ("/tuio2/bnd", 43),
+                ("/tuio2/alv", 42),
+            ]
+        );
+
+        // BND carries the full motion block including rotation acceleration
+        for packet in &bundle.content {
+            if let OscPacket::Message(msg) = packet {
+                if msg.addr == "/tuio2/bnd" {
+                    assert_eq!(msg.args.len(), 12);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_create_tuio_bundle_with_symbol() {
+        let mut tok = create_test_object();
+        tok.symbol = Some(ObjectSymbol {
+            type_id: 0,
+            group: "qr".to_string(),
+            data: "https://example.com".to_string(),
+        });
+        let plain = TuioObject::new(43, 1, 1, 0.2, 0.2, 0);
+
+        let bundle = create_tuio_bundle(
+            1,
+            1000,
+            1920,
+            1080,
+            "test",
+            &[tok, plain],
+            &EncoderOptions::default(),
+        );
+
+        assert_eq!(bundle.content.len(), 5); // FRM + TOK + SYM + TOK + ALV
+
+        if let OscPacket::Message(msg) = &bundle.content[2] {
+            assert_eq!(msg.addr, "/tuio2/sym");
+            assert_eq!(msg.args[0], rosc::OscType::Int(42));
+            assert_eq!(msg.args[3], rosc::OscType::String("qr".to_string()));
+        } else {
+            panic!("Expected SYM message after TOK");
+        }
+    }
+
+    #[test]
+    fn test_create_tuio_bundle_with_geometry() {
+        let mut blob = TuioObject::new_blob(42, 0.5, 0.5, ObjectBounds::new(0.2, 0.2), 0);
+        blob.angle = std::f32::consts::FRAC_PI_2;
+        blob.geometry = Some(ObjectGeometry {
+            // Concave outline, the notch at (0, 0.05) is not on the hull
+            outline: vec![
+                [-0.1, 0.1],
+                [0.0, 0.05],
+                [0.1, 0.1],
+                [0.1, -0.1],
+                [-0.1, -0.1],
+            ],
+            inner_contours: Vec::new(),
+            skeleton: vec![vec![[0.0, 0.0], [0.1, 0.0]]],
+        });
+
+        let bundle = create_tuio_bundle(
+            1,
+            1000,
+            1920,
+            1080,
+            "test",
+            &[blob],
+            &EncoderOptions::default(),
+        );
+
+        let msgs: Vec<&OscMessage> = bundle
+            .content
+            .iter()
+            .map(|packet| match packet {
+                OscPacket::Message(msg) => msg,
+                _ => panic!("Expected message"),
+            })
+            .collect();
+        let addrs: Vec<&str> = msgs.iter().map(|msg| msg.addr.as_str()).collect();
+        assert_eq!(
+            addrs,
+            vec![
+                "/tuio2/frm",
+                "/tuio2/bnd",
+                "/tuio2/chg",
+                "/tuio2/ocg",
+                "/tuio2/skg",
+                "/tuio2/alv"
+            ]
+        );
+
+        // Hull drops the notch, the contour keeps all points
+        assert_eq!(msgs[2].args.len(), 1 + 4 * 2);
+        assert_eq!(msgs[3].args.len(), 1 + 5 * 2);
+
+        // Skeleton end (0.1, 0) is rotated by 90 degrees around the center
+        match (&msgs[4].args[3], &msgs[4].args[4]) {
+            (rosc::OscType::Float(x), rosc::OscType::Float(y)) => {
+                assert!((x - 0.5).abs() < 1e-6);
+                assert!((y - 0.6).abs() < 1e-6);
+            }
+            _ => panic!("Expected skeleton point"),
+        }
+    }
+
+    #[test]
+    fn test_create_tuio_bundle_with_controls() {
+        let mut tok = create_test_object();
+        tok.controls = vec![ControlValue::Bool(true), ControlValue::Float(0.5)];
+        tok.data = vec![
+            ObjectData {
+                mime: "text/plain".to_string(),
+                data: b"hi".to_vec(),
+            },
+            ObjectData {
+                mime: "image/png".to_string(),
+                data: vec![0x89],
+            },
+        ];
+
+        let bundle = create_tuio_bundle(
+            1,
+            1000,
+            1920,
+            1080,
+            "test",
+            &[tok],
+            &EncoderOptions::default(),
+        );
+
+        let addrs: Vec<&str> = bundle
+            .content
+            .iter()
+            .map(|packet| match packet {
+                OscPacket::Message(msg) => msg.addr.as_str(),
+                _ => panic!("Expected message"),
+            })
+            .collect();
+        assert_eq!(
+            addrs,
+            vec![
+                "/tuio2/frm",
+                "/tuio2/tok",
+                "/tuio2/ctl",
+                "/tuio2/dat",
+                "/tuio2/dat",
+                "/tuio2/alv"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_create_tuio_bundle_with_associations() {
+        let container = TuioObject::new(1, 1, 1, 0.5, 0.5, 0);
+        let mut inner_b = TuioObject::new(3, 1, 3, 0.5, 0.5, 0);
+        inner_b.container = Some(ContainerSlot {
+            container_id: 1,
+            slot: 0,
+        });
+        let mut inner_a = TuioObject::new(2, 1, 2, 0.5, 0.5, 0);
+        inner_a.container = inner_b.container;
+        inner_a.links = vec![ObjectLink {
+            target_id: 4,
+            physical: true,
+            out_port: 0,
+            in_port: 1,
+        }];
+        let stacked = TuioObject::new(4, 1, 4, 0.5, 0.5, 0);
+
+        let objects = [inner_b, container, stacked, inner_a];
+        let bundle = create_tuio_bundle(
+            1,
+            1000,
+            1920,
+            1080,
+            "test",
+            &objects,
+            &EncoderOptions::default(),
+        );
+
+        let msgs: Vec<&OscMessage> = bundle
+            .content
+            .iter()
+            .map(|packet| match packet {
+                OscPacket::Message(msg) => msg,
+                _ => panic!("Expected message"),
+            })
+            .collect();
+
+        // FRM + 4*TOK + ALA + COA + LIA + ALV
+        assert_eq!(msgs.len(), 9);
+        assert_eq!(msgs[5].addr, "/tuio2/ala");
+        assert_eq!(msgs[5].args.len(), 4);
+        assert_eq!(msgs[6].addr, "/tuio2/coa");
+        assert_eq!(
+            msgs[6].args,
+            vec![
+                rosc::OscType::Int(1),
+                rosc::OscType::Int(0),
+                rosc::OscType::Int(2),
+                rosc::OscType::Int(3)
+            ]
+        );
+        assert_eq!(msgs[7].addr, "/tuio2/lia");
+        assert_eq!(msgs[7].args[0], rosc::OscType::Int(2));
+        assert_eq!(msgs[8].addr, "/tuio2/alv");
+    }
+
+    #[test]
+    fn test_create_tuio_bundle_3d() {
+        let mut tok = create_test_object();
+        tok.pose_3d = Some(Pose3D::new(0.1, 0.0, 0.0, 0.05));
+        tok.bounds = Some(ObjectBounds::new(0.1, 0.2));
+        let mut ptr = TuioObject::new_pointer(43, PointerType::Stylus, 0.2, 0.8, 0);
+        ptr.pose_3d = Some(Pose3D::new(0.2, 0.0, 0.0, 0.0));
+        let flat = TuioObject::new(44, 1, 1, 0.2, 0.2, 0);
+
+        let options = EncoderOptions {
+            depth: Some(500),
+            ..EncoderOptions::default()
+        };
+        let objects = [tok, ptr, flat];
+        let bundle = create_tuio_bundle(1, 1000, 1920, 1080, "test", &objects, &options);
+
+        let addrs: Vec<&str> = bundle
+            .content
+            .iter()
+            .map(|packet| match packet {
+                OscPacket::Message(msg) => msg.addr.as_str(),
+                _ => panic!("Expected message"),
+            })
+            .collect();
+        assert_eq!(
+            addrs,
+            vec![
+                "/tuio2/frm",
+                "/tuio2/tok",
+                "/tuio2/bnd",
+                "/tuio2/t3d",
+                "/tuio2/r3d",
+                "/tuio2/b3d",
+                "/tuio2/ptr",
+                "/tuio2/p3d",
+                "/tuio2/r3d",
+                "/tuio2/tok",
+                "/tuio2/alv",
+            ]
+        );
+
+        // FRM carries the sensor depth
+        if let OscPacket::Message(msg) = &bundle.content[0] {
+            assert_eq!(msg.args[4], rosc::OscType::Int(500));
+        }
+
+        // The untilted pointer points down onto the surface
+        if let OscPacket::Message(msg) = &bundle.content[7] {
+            assert_eq!(msg.args[8], rosc::OscType::Float(-1.0));
+        }
+    }
+
+    #[test]
+    fn test_create_tuio_bundle_3d_disabled() {
+        let mut tok = create_test_object();
+        tok.pose_3d = Some(Pose3D::new(0.1, 0.0, 0.0, 0.0));
+
+        let bundle = create_tuio_bundle(
+            1,
+            1000,
+            1920,
+            1080,
+            "test",
+            &[tok],
+            &EncoderOptions::default(),
+        );
+
+        assert_eq!(bundle.content.len(), 3); // FRM + TOK + ALV
+        if let OscPacket::Message(msg) = &bundle.content[0] {
+            assert_eq!(msg.args.len(), 4);
+        }
+    }
+
+    #[test]
+    fn test_tuio11_blob_bundle() {
+        let blob = TuioObject::new_blob(43, 0.3, 0.3, ObjectBounds::new(0.2, 0.2), 0);
+        let mut token = TuioObject::new(44, 1, 0, 0.5, 0.5, 0);
+        token.bounds = Some(ObjectBounds::new(0.1, 0.1));
+        let bundle = create_tuio11_bundle(1, "test", &[blob, token], &EncoderOptions::default());
+
+        let blob_sets: Vec<&OscMessage> = bundle
+            .content
+            .iter()
+            .filter_map(|packet| match packet {
+                OscPacket::Message(msg)
+                    if msg.addr == "/tuio/2Dblb"
+                        && msg.args[0] == rosc::OscType::String("set".to_string()) =>
+                {
+                    Some(msg)
+                }
+                _ => None,
+            })
+            .collect();
+
+        // Only the blob is sent on 2Dblb, the token with bounds is not
+        assert_eq!(blob_sets.len(), 1);
+        assert_eq!(blob_sets[0].args[1], rosc::OscType::Int(43));
+
+        // Blobs do not show up as objects
+        if let OscPacket::Message(msg) = &bundle.content[1] {
+            assert_eq!(msg.addr, "/tuio/2Dobj");
+            assert_eq!(
+                msg.args,
+                vec![
+                    rosc::OscType::String("alive".to_string()),
+                    rosc::OscType::Int(44)
+                ]
+            );
+        } else {
+            panic!("Expected 2Dobj alive message");
+        }
+    }
+
     #[test]
     fn test_encode_bundle() {
-        let bundle = create_tuio_bundle(1, 1000, 1920, 1080, "test", &[]);
+        let bundle =
+            create_tuio_bundle(1, 1000, 1920, 1080, "test", &[], &EncoderOptions::default());
         let encoded = encode_bundle(&bundle);
 
         assert!(encoded.is_ok());
@@ -217,7 +1007,16 @@ mod tests {
     #[test]
     fn test_create_and_encode_tuio_bundle() {
         let obj = create_test_object();
-        let result = create_and_encode_tuio_bundle(1, 1000, 1920, 1080, "test", &[obj]);
+        let result = create_and_encode_tuio_bundle(
+            ProtocolVersion::Tuio2,
+            1,
+            1000,
+            1920,
+            1080,
+            "test",
+            &[obj],
+            &EncoderOptions::default(),
+        );
 
         assert!(result.is_ok());
         let data = result.unwrap();
@@ -226,4 +1025,185 @@ mod tests {
         assert_eq!(&data[0..8], b"#bundle\0");
         assert!(data.len() > 8);
     }
+
+    #[test]
+    fn test_tuio2_token_layout() {
+        let mut obj = TuioObject::new(1, 2, 3, 0.5, 0.25, 0);
+        obj.x_vel = 1.0;
+        let bundle = create_tuio_bundle(
+            1,
+            1000,
+            1920,
+            1080,
+            "test",
+            &[obj],
+            &EncoderOptions::default(),
+        );
+        let data = encode_bundle(&bundle).unwrap();
+
+        #[rustfmt::skip]
+        let expected_tok: Vec<u8> = vec![
+            0, 0, 0, 60, // element size
+            b'/', b't', b'u', b'i', b'o', b'2', b'/', b't', b'o', b'k', 0, 0,
+            b',', b'i', b'i', b'i', b'f', b'f', b'f', b'f', b'f', b'f', 0, 0,
+            0, 0, 0, 1,             // session_id
+            0, 2, 0, 0,             // (type_id << 16) | user_id
+            0, 0, 0, 3,             // component_id
+            0x3f, 0x00, 0x00, 0x00, // x = 0.5
+            0x3e, 0x80, 0x00, 0x00, // y = 0.25
+            0x00, 0x00, 0x00, 0x00, // angle = 0.0
+            0x3f, 0x80, 0x00, 0x00, // x_vel = 1.0
+            0x00, 0x00, 0x00, 0x00, // y_vel = 0.0
+            0x00, 0x00, 0x00, 0x00, // angle_vel = 0.0
+        ];
+
+        // Bundle header (16 bytes) + FRM element (4 + 44 bytes)
+        let tok_start = 16 + 4 + 44;
+        assert_eq!(
+            &data[tok_start..tok_start + expected_tok.len()],
+            &expected_tok[..]
+        );
+    }
+
+    #[test]
+    fn test_create_tuio11_bundle_empty() {
+        let addrs = |bundle: &OscBundle| -> Vec<String> {
+            bundle
+                .content
+                .iter()
+                .map(|packet| match packet {
+                    OscPacket::Message(msg) => msg.addr.clone(),
+                    _ => panic!("Expected message"),
+                })
+                .collect()
+        };
+
+        // source + alive + fseq of the object and cursor profiles
+        let bundle = create_tuio11_bundle(1, "test", &[], &EncoderOptions::default());
+        let mut expected = vec!["/tuio/2Dobj"; 3];
+        expected.extend(["/tuio/2Dcur"; 3]);
+        assert_eq!(addrs(&bundle), expected);
+
+        // The blob profile is sent once more after its last blob
+        let options = EncoderOptions {
+            previous_profiles: HashSet::from([Profile::Blob]),
+            ..EncoderOptions::default()
+        };
+        let bundle = create_tuio11_bundle(1, "test", &[], &options);
+        expected.extend(["/tuio/2Dblb"; 3]);
+        assert_eq!(addrs(&bundle), expected);
+    }
+
+    #[test]
+    fn test_tuio11_object_layout() {
+        let obj = TuioObject::new(1, 2, 3, 0.5, 0.25, 0);
+        let bundle = create_tuio11_bundle(258, "sim", &[obj], &EncoderOptions::default());
+        let data = encode_bundle(&bundle).unwrap();
+
+        #[rustfmt::skip]
+        let expected: Vec<u8> = vec![
+            b'#', b'b', b'u', b'n', b'd', b'l', b'e', 0,
+            0, 0, 0, 0, 0, 0, 0, 1, // immediate timetag
+            // /tuio/2Dobj source sim
+            0, 0, 0, 28,
+            b'/', b't', b'u', b'i', b'o', b'/', b'2', b'D', b'o', b'b', b'j', 0,
+            b',', b's', b's', 0,
+            b's', b'o', b'u', b'r', b'c', b'e', 0, 0,
+            b's', b'i', b'm', 0,
+            // /tuio/2Dobj alive 1
+            0, 0, 0, 28,
+            b'/', b't', b'u', b'i', b'o', b'/', b'2', b'D', b'o', b'b', b'j', 0,
+            b',', b's', b'i', 0,
+            b'a', b'l', b'i', b'v', b'e', 0, 0, 0,
+            0, 0, 0, 1,
+            // /tuio/2Dobj set 1 3 0.5 0.25 0 0 0 0 0 0
+            0, 0, 0, 72,
+            b'/', b't', b'u', b'i', b'o', b'/', b'2', b'D', b'o', b'b', b'j', 0,
+            b',', b's', b'i', b'i', b'f', b'f', b'f', b'f', b'f', b'f', b'f', b'f', 0, 0, 0, 0,
+            b's', b'e', b't', 0,
+            0, 0, 0, 1,             // s
+            0, 0, 0, 3,             // i = component_id
+            0x3f, 0x00, 0x00, 0x00, // x = 0.5
+            0x3e, 0x80, 0x00, 0x00, // y = 0.25
+            0, 0, 0, 0,             // a
+            0, 0, 0, 0,             // X
+            0, 0, 0, 0,             // Y
+            0, 0, 0, 0,             // A
+            0, 0, 0, 0,             // m
+            0, 0, 0, 0,             // r
+            // /tuio/2Dobj fseq 258
+            0, 0, 0, 28,
+            b'/', b't', b'u', b'i', b'o', b'/', b'2', b'D', b'o', b'b', b'j', 0,
+            b',', b's', b'i', 0,
+            b'f', b's', b'e', b'q', 0, 0, 0, 0,
+            0, 0, 1, 2,
+        ];
+        assert_eq!(&data[..expected.len()], &expected[..]);
+    }
+
+    #[test]
+    fn test_tuio11_pointer_bundle() {
+        let obj = TuioObject::new_pointer(42, PointerType::Finger, 0.5, 0.5, 0);
+        let bundle = create_tuio11_bundle(1, "test", &[obj], &EncoderOptions::default());
+
+        // The object is sent as a cursor, 2Dobj carries no set and 2Dblb
+        // is left out
+        let cursor_set = bundle.content.iter().find_map(|packet| match packet {
+            OscPacket::Message(msg) if msg.addr == "/tuio/2Dcur" && msg.args.len() == 7 => {
+                Some(msg)
+            }
+            _ => None,
+        });
+        assert!(cursor_set.is_some());
+
+        let count = |addr: &str| {
+            bundle
+                .content
+                .iter()
+                .filter(|packet| matches!(packet, OscPacket::Message(msg) if msg.addr == addr))
+                .count()
+        };
+        assert_eq!(count("/tuio/2Dobj"), 3);
+        assert_eq!(count("/tuio/2Dcur"), 4);
+        assert_eq!(count("/tuio/2Dblb"), 0);
+    }
+
+    #[test]
+    fn test_tuio11_skip_tokens() {
+        let obj = TuioObject::new(1, 2, 3, 0.5, 0.25, 0);
+        let options = EncoderOptions {
+            skip_tokens: HashSet::from([1]),
+            ..EncoderOptions::default()
+        };
+        let bundle = create_tuio11_bundle(1, "test", &[obj], &options);
+
+        // source + alive + fseq of 2Dobj and 2Dcur, the token stays alive
+        // without a set
+        assert_eq!(bundle.content.len(), 6);
+        if let OscPacket::Message(msg) = &bundle.content[1] {
+            assert_eq!(msg.args[1], rosc::OscType::Int(1));
+        } else {
+            panic!("Expected 2Dobj alive message");
+        }
+    }
+
+    #[test]
+    fn test_create_and_encode_tuio11_bundle() {
+        let obj = create_test_object();
+        let data = create_and_encode_tuio_bundle(
+            ProtocolVersion::Tuio11,
+            1,
+            1000,
+            1920,
+            1080,
+            "test",
+            &[obj],
+            &EncoderOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(&data[0..8], b"#bundle\0");
+        assert!(data.windows(11).any(|w| w == b"/tuio/2Dobj"));
+        assert!(!data.windows(10).any(|w| w == b"/tuio2/frm"));
+    }
 }
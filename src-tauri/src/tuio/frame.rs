@@ -1,7 +1,8 @@
-use crate::state::{AppState, TuioObject};
-use crate::tuio::encoder::create_and_encode_tuio_bundle;
+use crate::state::{AppState, ControlSendMode, FrameMode, ObjectKind, TuioObject};
+use crate::tuio::encoder::{create_and_encode_tuio_bundle, EncoderOptions};
+use crate::tuio::tuio11::Profile;
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Calculate velocities for all objects based on position/angle deltas
 ///
@@ -9,6 +10,14 @@ use std::collections::HashMap;
 /// - x_vel = (current_x - last_x) / delta_time_seconds
 /// - y_vel = (current_y - last_y) / delta_time_seconds
 /// - angle_vel = (current_angle - last_angle) / delta_time_seconds
+/// - pressure_vel = (current_pressure - last_pressure) / delta_time_seconds
+///
+/// Accelerations are the change of speed over the same interval:
+/// - accel = (current_speed - last_speed) / delta_time_seconds
+/// - angle_accel = (current_angle_vel - last_angle_vel) / delta_time_seconds
+///
+/// Objects with a 3D pose additionally get z, roll and pitch velocities
+/// and accelerations of their 3D speed and rotation speed.
 pub fn calculate_velocities(objects: &mut HashMap<u32, TuioObject>, current_timestamp: i64) {
     for object in objects.values_mut() {
         let delta_time_ms = current_timestamp - object.last_update;
@@ -18,6 +27,10 @@ pub fn calculate_velocities(objects: &mut HashMap<u32, TuioObject>, current_time
         if delta_time_ms > 1 {
             let delta_time_seconds = delta_time_ms as f32 / 1000.0;
 
+            // Remember previous speeds for the acceleration
+            let last_speed = object.x_vel.hypot(object.y_vel);
+            let last_angle_vel = object.angle_vel;
+
             // Calculate position velocities
             let delta_x = object.x - object.last_x;
             let delta_y = object.y - object.last_y;
@@ -28,15 +41,47 @@ pub fn calculate_velocities(objects: &mut HashMap<u32, TuioObject>, current_time
             let delta_angle = object.angle - object.last_angle;
             object.angle_vel = delta_angle / delta_time_seconds;
 
+            // Calculate pressure velocity
+            let delta_pressure = object.pressure - object.last_pressure;
+            object.pressure_vel = delta_pressure / delta_time_seconds;
+
+            // Calculate accelerations
+            let speed = object.x_vel.hypot(object.y_vel);
+            object.accel = (speed - last_speed) / delta_time_seconds;
+            object.angle_accel = (object.angle_vel - last_angle_vel) / delta_time_seconds;
+
+            if let Some(pose) = object.pose_3d.as_mut() {
+                let last_speed = hypot3(last_speed, pose.z_vel, 0.0);
+                let last_rotation_speed = hypot3(pose.roll_vel, pose.pitch_vel, last_angle_vel);
+
+                pose.z_vel = (pose.z - pose.last_z) / delta_time_seconds;
+                pose.roll_vel = (pose.roll - pose.last_roll) / delta_time_seconds;
+                pose.pitch_vel = (pose.pitch - pose.last_pitch) / delta_time_seconds;
+
+                let speed = hypot3(speed, pose.z_vel, 0.0);
+                let rotation_speed = hypot3(pose.roll_vel, pose.pitch_vel, object.angle_vel);
+                pose.accel = (speed - last_speed) / delta_time_seconds;
+                pose.rotation_accel = (rotation_speed - last_rotation_speed) / delta_time_seconds;
+
+                pose.last_z = pose.z;
+                pose.last_roll = pose.roll;
+                pose.last_pitch = pose.pitch;
+            }
+
             // Update last known values
             object.last_x = object.x;
             object.last_y = object.y;
             object.last_angle = object.angle;
+            object.last_pressure = object.pressure;
             object.last_update = current_timestamp;
         }
     }
 }
 
+fn hypot3(x: f32, y: f32, z: f32) -> f32 {
+    (x * x + y * y + z * z).sqrt()
+}
+
 /// Generate a complete TUIO frame bundle
 ///
 /// This function:
@@ -44,8 +89,19 @@ pub fn calculate_velocities(objects: &mut HashMap<u32, TuioObject>, current_time
 /// 2. Gets the current timestamp
 /// 3. Collects all objects from state
 /// 4. Calculates velocities for all objects
-/// 5. Creates and encodes the OSC bundle
+/// 5. Drops unchanged controls and data in on-change mode
+/// 6. Drops TOK of unmoved tokens if enabled in on-change frame mode
+/// 7. Creates and encodes the OSC bundle
 pub fn generate_frame(state: &AppState) -> Result<Vec<u8>> {
+    encode_frame(state, false)
+}
+
+/// Generate a frame with the TOK of every token, used for keep-alives
+pub fn generate_full_frame(state: &AppState) -> Result<Vec<u8>> {
+    encode_frame(state, true)
+}
+
+fn encode_frame(state: &AppState, full: bool) -> Result<Vec<u8>> {
     let timestamp = chrono::Utc::now().timestamp_millis();
     let frame_id = state.increment_frame_counter();
 
@@ -54,6 +110,11 @@ pub fn generate_frame(state: &AppState) -> Result<Vec<u8>> {
     let width = config.width;
     let height = config.height;
     let source = config.source.clone();
+    let protocol_version = config.protocol_version;
+    let mut options = EncoderOptions::from_config(&config);
+    let control_send_mode = config.control_send_mode;
+    let skip_unmoved_tokens =
+        !full && config.frame_mode == FrameMode::OnChange && config.skip_unmoved_tokens;
     drop(config);
 
     // Get objects and calculate velocities
@@ -61,37 +122,83 @@ pub fn generate_frame(state: &AppState) -> Result<Vec<u8>> {
     calculate_velocities(&mut objects, timestamp);
 
     // Convert to vector for encoding
-    let objects_vec: Vec<TuioObject> = objects.values().cloned().collect();
+    let mut objects_vec: Vec<TuioObject> = objects.values().cloned().collect();
     drop(objects);
 
+    // Only send CTL/DAT for objects that changed since the last frame
+    let changed_controls = std::mem::take(&mut *state.changed_controls.lock());
+    if control_send_mode == ControlSendMode::OnChange {
+        for object in &mut objects_vec {
+            if !changed_controls.contains(&object.session_id) {
+                object.controls.clear();
+                object.data.clear();
+            }
+        }
+    }
+
+    // Remember where the tokens are sent, finding those that did not move
+    let unmoved_tokens = update_sent_tokens(state, &objects_vec);
+    if skip_unmoved_tokens {
+        options.skip_tokens = unmoved_tokens;
+    }
+    options.previous_profiles = update_sent_profiles(state, &objects_vec);
+
     // Create and encode bundle
-    create_and_encode_tuio_bundle(frame_id, timestamp, width, height, &source, &objects_vec)
+    create_and_encode_tuio_bundle(
+        protocol_version,
+        frame_id,
+        timestamp,
+        width,
+        height,
+        &source,
+        &objects_vec,
+        &options,
+    )
+}
+
+/// Store the position and angle of all tokens, returning those unchanged
+/// since the previous frame
+fn update_sent_tokens(state: &AppState, objects: &[TuioObject]) -> HashSet<u32> {
+    let mut sent_tokens = state.sent_tokens.lock();
+    let mut unmoved = HashSet::new();
+    let mut positions = HashMap::new();
+
+    for object in objects.iter().filter(|o| o.kind == ObjectKind::Token) {
+        let position = (object.x, object.y, object.angle);
+        if sent_tokens.get(&object.session_id) == Some(&position) {
+            unmoved.insert(object.session_id);
+        }
+        positions.insert(object.session_id, position);
+    }
+
+    *sent_tokens = positions;
+    unmoved
+}
+
+/// Store the TUIO 1.1 profiles used by the objects, returning those of
+/// the previous frame
+fn update_sent_profiles(state: &AppState, objects: &[TuioObject]) -> HashSet<Profile> {
+    let profiles = objects
+        .iter()
+        .map(|object| Profile::of(object.kind))
+        .collect();
+    std::mem::replace(&mut *state.sent_profiles.lock(), profiles)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::state::{ControlValue, ObjectBounds, PointerType, Pose3D};
+    use crate::tuio::encoder::ProtocolVersion;
     use std::thread::sleep;
     use std::time::Duration;
 
     fn create_test_object(session_id: u32, x: f32, y: f32, angle: f32) -> TuioObject {
         let timestamp = chrono::Utc::now().timestamp_millis();
-        TuioObject {
-            session_id,
-            type_id: 1,
-            user_id: 0,
-            component_id: 0,
-            x,
-            y,
-            angle,
-            x_vel: 0.0,
-            y_vel: 0.0,
-            angle_vel: 0.0,
-            last_x: x,
-            last_y: y,
-            last_angle: angle,
-            last_update: timestamp,
-        }
+        let mut object = TuioObject::new(session_id, 1, 0, x, y, timestamp);
+        object.angle = angle;
+        object.last_angle = angle;
+        object
     }
 
     #[test]
@@ -142,6 +249,70 @@ mod tests {
         assert!((obj.angle_vel - 15.7).abs() < 0.01);
     }
 
+    #[test]
+    fn test_calculate_pressure_velocity() {
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        let mut obj = TuioObject::new_pointer(1, PointerType::Stylus, 0.5, 0.5, timestamp - 100);
+        obj.pressure = 0.5; // Released from 1.0 to 0.5
+
+        let mut objects = HashMap::new();
+        objects.insert(1, obj);
+
+        calculate_velocities(&mut objects, timestamp);
+
+        let obj = objects.get(&1).unwrap();
+        // -0.5 / 0.1s = -5.0 per second
+        assert!((obj.pressure_vel + 5.0).abs() < 0.01);
+        assert_eq!(obj.last_pressure, 0.5);
+    }
+
+    #[test]
+    fn test_calculate_accelerations() {
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        let mut obj = create_test_object(1, 0.5, 0.5, 0.0);
+        obj.last_update = timestamp - 100;
+        obj.x_vel = 1.0; // Previously moving at 1.0 units/s
+        obj.x = 0.7; // Now moving at 2.0 units/s
+
+        let mut objects = HashMap::new();
+        objects.insert(1, obj);
+
+        calculate_velocities(&mut objects, timestamp);
+
+        let obj = objects.get(&1).unwrap();
+        // (2.0 - 1.0) / 0.1s = 10.0 units/s²
+        assert!((obj.accel - 10.0).abs() < 0.01);
+        assert_eq!(obj.angle_accel, 0.0);
+    }
+
+    #[test]
+    fn test_calculate_velocities_3d() {
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        let mut obj = create_test_object(1, 0.5, 0.5, 0.0);
+        obj.last_update = timestamp - 100;
+        obj.pose_3d = Some(Pose3D::new(0.0, 0.0, 0.0, 0.0));
+
+        let mut objects = HashMap::new();
+        objects.insert(1, obj);
+
+        {
+            let pose = objects.get_mut(&1).unwrap().pose_3d.as_mut().unwrap();
+            pose.z = 0.1; // Lifted 0.1 units
+            pose.pitch = 0.5; // Tilted by 0.5 rad
+        }
+
+        calculate_velocities(&mut objects, timestamp);
+
+        let pose = objects.get(&1).unwrap().pose_3d.unwrap();
+        // 0.1 / 0.1s = 1.0 units/s, accelerated from rest
+        assert!((pose.z_vel - 1.0).abs() < 0.01);
+        assert!((pose.accel - 10.0).abs() < 0.01);
+        // 0.5 / 0.1s = 5.0 rad/s
+        assert!((pose.pitch_vel - 5.0).abs() < 0.01);
+        assert!((pose.rotation_accel - 50.0).abs() < 0.1);
+        assert_eq!(pose.last_z, 0.1);
+    }
+
     #[test]
     fn test_calculate_velocities_multiple_objects() {
         let timestamp = chrono::Utc::now().timestamp_millis();
@@ -210,4 +381,75 @@ mod tests {
         // Should still be a valid bundle even with no objects
         assert_eq!(&data[0..8], b"#bundle\0");
     }
+
+    #[test]
+    fn test_generate_frame_controls_on_change() {
+        let state = AppState::new();
+        state.config.lock().control_send_mode = ControlSendMode::OnChange;
+
+        let mut obj = create_test_object(1, 0.5, 0.5, 0.0);
+        obj.controls = vec![ControlValue::Bool(true)];
+        state.objects.lock().insert(1, obj);
+        state.changed_controls.lock().insert(1);
+
+        let has_ctl = |data: &[u8]| data.windows(10).any(|w| w == b"/tuio2/ctl");
+
+        // Sent once after the change, then left out
+        assert!(has_ctl(&generate_frame(&state).unwrap()));
+        assert!(!has_ctl(&generate_frame(&state).unwrap()));
+
+        // Always sent in every-frame mode
+        state.config.lock().control_send_mode = ControlSendMode::EveryFrame;
+        assert!(has_ctl(&generate_frame(&state).unwrap()));
+    }
+
+    #[test]
+    fn test_generate_frame_skips_unmoved_tokens() {
+        let state = AppState::new();
+        {
+            let mut config = state.config.lock();
+            config.frame_mode = FrameMode::OnChange;
+            config.skip_unmoved_tokens = true;
+        }
+        state
+            .objects
+            .lock()
+            .insert(1, create_test_object(1, 0.5, 0.5, 0.0));
+
+        let has_tok = |data: &[u8]| data.windows(10).any(|w| w == b"/tuio2/tok");
+
+        // Sent while new, then left out until the token moves
+        assert!(has_tok(&generate_frame(&state).unwrap()));
+        assert!(!has_tok(&generate_frame(&state).unwrap()));
+        assert!(has_tok(&generate_full_frame(&state).unwrap()));
+
+        state.objects.lock().get_mut(&1).unwrap().x = 0.6;
+        assert!(has_tok(&generate_frame(&state).unwrap()));
+        assert!(!has_tok(&generate_frame(&state).unwrap()));
+    }
+
+    #[test]
+    fn test_generate_frame_tuio11_profiles() {
+        let state = AppState::new();
+        state.config.lock().protocol_version = ProtocolVersion::Tuio11;
+        state.objects.lock().insert(
+            1,
+            TuioObject::new_blob(1, 0.5, 0.5, ObjectBounds::new(0.1, 0.1), 0),
+        );
+
+        let has_obj = |data: &[u8]| data.windows(11).any(|w| w == b"/tuio/2Dobj");
+        let has_blb = |data: &[u8]| data.windows(11).any(|w| w == b"/tuio/2Dblb");
+
+        let data = generate_frame(&state).unwrap();
+        assert!(has_obj(&data));
+        assert!(has_blb(&data));
+
+        // The emptied blob profile is sent once more so clients see the
+        // removal, 2Dobj keeps its alive message in every frame
+        state.objects.lock().clear();
+        assert!(has_blb(&generate_frame(&state).unwrap()));
+        let data = generate_frame(&state).unwrap();
+        assert!(!has_blb(&data));
+        assert!(has_obj(&data));
+    }
 }
@@ -0,0 +1,173 @@
+/// Quaternion (w, x, y, z) for the given Euler angles in radians
+///
+/// Rotations are applied in yaw (z), pitch (y), roll (x) order, so yaw
+/// is the rotation on the surface that the 2D angle describes.
+pub fn euler_to_quaternion(roll: f32, pitch: f32, yaw: f32) -> [f32; 4] {
+    let (sr, cr) = (roll / 2.0).sin_cos();
+    let (sp, cp) = (pitch / 2.0).sin_cos();
+    let (sy, cy) = (yaw / 2.0).sin_cos();
+
+    [
+        cr * cp * cy + sr * sp * sy,
+        sr * cp * cy - cr * sp * sy,
+        cr * sp * cy + sr * cp * sy,
+        cr * cp * sy - sr * sp * cy,
+    ]
+}
+
+/// Rotation angle and unit axis of a quaternion
+///
+/// A rotation close to zero has no meaningful axis, so the surface
+/// normal (0, 0, 1) is returned for it.
+pub fn quaternion_to_axis_angle(q: [f32; 4]) -> (f32, [f32; 3]) {
+    let [w, x, y, z] = q;
+    let angle = 2.0 * w.clamp(-1.0, 1.0).acos();
+    let s = (1.0 - w * w).max(0.0).sqrt();
+
+    if s < 1e-6 {
+        (0.0, [0.0, 0.0, 1.0])
+    } else {
+        (angle, [x / s, y / s, z / s])
+    }
+}
+
+/// Rotate a vector by a quaternion
+pub fn rotate_vector(q: [f32; 4], v: [f32; 3]) -> [f32; 3] {
+    let [w, x, y, z] = q;
+
+    // t = 2 * cross(q.xyz, v)
+    let t = [
+        2.0 * (y * v[2] - z * v[1]),
+        2.0 * (z * v[0] - x * v[2]),
+        2.0 * (x * v[1] - y * v[0]),
+    ];
+
+    // v + w * t + cross(q.xyz, t)
+    [
+        v[0] + w * t[0] + (y * t[2] - z * t[1]),
+        v[1] + w * t[1] + (z * t[0] - x * t[2]),
+        v[2] + w * t[2] + (x * t[1] - y * t[0]),
+    ]
+}
+
+/// Convex hull of a point set, counter-clockwise starting at the lowest x
+///
+/// Uses Andrew's monotone chain. Collinear points on the hull are
+/// dropped, fewer than three points are returned as they are.
+pub fn convex_hull(points: &[[f32; 2]]) -> Vec<[f32; 2]> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a[0].total_cmp(&b[0]).then(a[1].total_cmp(&b[1])));
+    sorted.dedup();
+
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    let mut lower = half_hull(sorted.iter());
+    let mut upper = half_hull(sorted.iter().rev());
+
+    // The last point of each half is the first point of the other
+    lower.pop();
+    upper.pop();
+    lower.extend(upper);
+    lower
+}
+
+/// One half of a monotone chain hull, turning counter-clockwise only
+fn half_hull<'a>(points: impl Iterator<Item = &'a [f32; 2]>) -> Vec<[f32; 2]> {
+    fn cross(o: [f32; 2], a: [f32; 2], b: [f32; 2]) -> f32 {
+        (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0])
+    }
+
+    let mut hull: Vec<[f32; 2]> = Vec::new();
+    for &point in points {
+        while hull.len() >= 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0 {
+            hull.pop();
+        }
+        hull.push(point);
+    }
+    hull
+}
+
+/// Rotate points by the angle and move them to the position
+///
+/// Points are relative to the object center in normalized units.
+pub fn transform_points(points: &[[f32; 2]], x: f32, y: f32, angle: f32) -> Vec<[f32; 2]> {
+    let (sin, cos) = angle.sin_cos();
+    points
+        .iter()
+        .map(|[px, py]| [x + px * cos - py * sin, y + px * sin + py * cos])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_close(a: [f32; 3], b: [f32; 3]) {
+        for i in 0..3 {
+            assert!((a[i] - b[i]).abs() < 1e-5, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_identity_rotation() {
+        let q = euler_to_quaternion(0.0, 0.0, 0.0);
+        assert_eq!(q, [1.0, 0.0, 0.0, 0.0]);
+
+        let (angle, axis) = quaternion_to_axis_angle(q);
+        assert_eq!(angle, 0.0);
+        assert_eq!(axis, [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_yaw_is_rotation_about_z() {
+        let q = euler_to_quaternion(0.0, 0.0, FRAC_PI_2);
+
+        let (angle, axis) = quaternion_to_axis_angle(q);
+        assert!((angle - FRAC_PI_2).abs() < 1e-5);
+        assert_close(axis, [0.0, 0.0, 1.0]);
+
+        // The x axis is turned onto the y axis
+        assert_close(rotate_vector(q, [1.0, 0.0, 0.0]), [0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_pitch_tilts_normal() {
+        let q = euler_to_quaternion(0.0, FRAC_PI_2, 0.0);
+
+        // The surface normal is tilted onto the x axis
+        assert_close(rotate_vector(q, [0.0, 0.0, 1.0]), [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_convex_hull() {
+        // Square with an inner point and a collinear edge point
+        let points = [
+            [0.0, 0.0],
+            [1.0, 1.0],
+            [0.5, 0.5],
+            [1.0, 0.0],
+            [0.5, 0.0],
+            [0.0, 1.0],
+        ];
+
+        let hull = convex_hull(&points);
+        assert_eq!(hull, vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]);
+    }
+
+    #[test]
+    fn test_convex_hull_degenerate() {
+        assert!(convex_hull(&[]).is_empty());
+        assert_eq!(convex_hull(&[[0.5, 0.5], [0.5, 0.5]]), vec![[0.5, 0.5]]);
+    }
+
+    #[test]
+    fn test_transform_points() {
+        let points = transform_points(&[[0.1, 0.0]], 0.5, 0.5, FRAC_PI_2);
+
+        assert!((points[0][0] - 0.5).abs() < 1e-6);
+        assert!((points[0][1] - 0.6).abs() < 1e-6);
+    }
+}
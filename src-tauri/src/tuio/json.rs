@@ -0,0 +1,96 @@
+use anyhow::{anyhow, Result};
+use rosc::{OscPacket, OscTime, OscType};
+use serde_json::{json, Value};
+
+/// Decode an encoded OSC packet to JSON
+///
+/// Bundles become `{ timetag, elements }` and messages `{ address, args }`.
+/// Arguments are plain JSON values, blobs are arrays of bytes.
+pub fn decode_packet(data: &[u8]) -> Result<Value> {
+    let (_, packet) =
+        rosc::decoder::decode_udp(data).map_err(|e| anyhow!("Invalid OSC packet: {}", e))?;
+    Ok(packet_to_json(&packet))
+}
+
+fn packet_to_json(packet: &OscPacket) -> Value {
+    match packet {
+        OscPacket::Message(message) => json!({
+            "address": message.addr,
+            "args": message.args.iter().map(arg_to_json).collect::<Vec<_>>(),
+        }),
+        OscPacket::Bundle(bundle) => json!({
+            "timetag": time_to_json(&bundle.timetag),
+            "elements": bundle.content.iter().map(packet_to_json).collect::<Vec<_>>(),
+        }),
+    }
+}
+
+fn time_to_json(time: &OscTime) -> Value {
+    json!({ "seconds": time.seconds, "fractional": time.fractional })
+}
+
+fn arg_to_json(arg: &OscType) -> Value {
+    match arg {
+        OscType::Int(value) => json!(value),
+        OscType::Float(value) => json!(value),
+        OscType::String(value) => json!(value),
+        OscType::Blob(value) => json!(value),
+        OscType::Time(time) => time_to_json(time),
+        OscType::Long(value) => json!(value),
+        OscType::Double(value) => json!(value),
+        OscType::Char(value) => json!(value.to_string()),
+        OscType::Color(color) => json!({
+            "red": color.red,
+            "green": color.green,
+            "blue": color.blue,
+            "alpha": color.alpha,
+        }),
+        OscType::Midi(midi) => json!({
+            "port": midi.port,
+            "status": midi.status,
+            "data1": midi.data1,
+            "data2": midi.data2,
+        }),
+        OscType::Bool(value) => json!(value),
+        OscType::Array(array) => Value::Array(array.content.iter().map(arg_to_json).collect()),
+        OscType::Nil => Value::Null,
+        OscType::Inf => json!("inf"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::TuioObject;
+    use crate::tuio::encoder::{create_and_encode_tuio_bundle, EncoderOptions, ProtocolVersion};
+
+    #[test]
+    fn test_decode_bundle() {
+        let objects = vec![TuioObject::new(1, 5, 5, 0.5, 0.25, 0)];
+        let data = create_and_encode_tuio_bundle(
+            ProtocolVersion::Tuio2,
+            7,
+            0,
+            1920,
+            1080,
+            "test",
+            &objects,
+            &EncoderOptions::default(),
+        )
+        .unwrap();
+
+        let json = decode_packet(&data).unwrap();
+        let elements = json["elements"].as_array().unwrap();
+
+        assert_eq!(elements[0]["address"], "/tuio2/frm");
+        assert_eq!(elements[0]["args"][0], 7);
+        assert_eq!(elements[1]["address"], "/tuio2/tok");
+        assert_eq!(elements[1]["args"][3], 0.5);
+        assert_eq!(elements.last().unwrap()["address"], "/tuio2/alv");
+    }
+
+    #[test]
+    fn test_decode_invalid_packet() {
+        assert!(decode_packet(&[1, 2, 3]).is_err());
+    }
+}
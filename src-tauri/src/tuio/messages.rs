@@ -1,3 +1,4 @@
+use crate::state::ControlValue;
 use rosc::{OscMessage, OscTime, OscType};
 
 /// FRM (Frame) message - Opens the bundle
@@ -10,12 +11,14 @@ use rosc::{OscMessage, OscTime, OscType};
 ///    Width: bits 16-31, Height: bits 0-15
 ///    Encoding: (width << 16) | height
 /// 4. source (string) - Source identifier (e.g., "tuio-simulator")
+/// 5. depth (int32, optional) - Sensor depth, only sent for 3D sensors
 pub struct FrameMessage {
     pub frame_id: u32,
     pub timestamp: i64,
     pub width: u16,
     pub height: u16,
     pub source: String,
+    pub depth: Option<u16>,
 }
 
 impl FrameMessage {
@@ -26,9 +29,16 @@ impl FrameMessage {
             width,
             height,
             source,
+            depth: None,
         }
     }
 
+    /// Extend the sensor dimension by a depth for 3D sensors
+    pub fn with_depth(mut self, depth: u16) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
     /// Convert to OSC message
     pub fn to_osc(&self) -> OscMessage {
         // Encode dimensions: (width << 16) | height
@@ -40,14 +50,19 @@ impl FrameMessage {
         let fractional = (((self.timestamp % 1000) * u32::MAX as i64) / 1000) as u32;
         let timetag = OscTime { seconds, fractional };
 
+        let mut args = vec![
+            OscType::Int(self.frame_id as i32),
+            OscType::Time(timetag),
+            OscType::Int(dimension),
+            OscType::String(self.source.clone()),
+        ];
+        if let Some(depth) = self.depth {
+            args.push(OscType::Int(depth as i32));
+        }
+
         OscMessage {
             addr: "/tuio2/frm".to_string(),
-            args: vec![
-                OscType::Int(self.frame_id as i32),
-                OscType::Time(timetag),
-                OscType::Int(dimension),
-                OscType::String(self.source.clone()),
-            ],
+            args,
         }
     }
 }
@@ -150,6 +165,8 @@ impl TokenMessage {
 
 /// PTR (Pointer) message - Represents a pointing gesture (touch, stylus, cursor)
 /// OSC Address: /tuio2/ptr
+///
+/// Pressure ranges from -1.0 to 1.0, negative values mean hovering.
 pub struct PointerMessage {
     pub session_id: u32,
     pub type_id: u16,
@@ -177,8 +194,13 @@ impl PointerMessage {
         x: f32,
         y: f32,
         angle: f32,
+        shear: f32,
+        radius: f32,
+        pressure: f32,
         x_vel: f32,
         y_vel: f32,
+        pressure_vel: f32,
+        accel: f32,
     ) -> Self {
         Self {
             session_id,
@@ -188,13 +210,13 @@ impl PointerMessage {
             x,
             y,
             angle,
-            shear: 0.0,
-            radius: 0.0,
-            pressure: 1.0, // Positive pressure = touching
+            shear,
+            radius,
+            pressure,
             x_vel,
             y_vel,
-            pressure_vel: 0.0,
-            accel: 0.0,
+            pressure_vel,
+            accel,
         }
     }
 
@@ -224,6 +246,748 @@ impl PointerMessage {
     }
 }
 
+/// BND (Bounds) message - Represents an untagged object via oriented bounding box
+/// OSC Address: /tuio2/bnd
+///
+/// Can be sent alone for untagged blobs or after a TOK/PTR with the same
+/// session ID to describe the geometry of that object.
+pub struct BoundsMessage {
+    pub session_id: u32,
+    pub x: f32,
+    pub y: f32,
+    pub angle: f32,
+    pub width: f32,
+    pub height: f32,
+    pub area: f32,
+    pub x_vel: f32,
+    pub y_vel: f32,
+    pub angle_vel: f32,
+    pub accel: f32,
+    pub angle_accel: f32,
+}
+
+impl BoundsMessage {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        session_id: u32,
+        x: f32,
+        y: f32,
+        angle: f32,
+        width: f32,
+        height: f32,
+        area: f32,
+        x_vel: f32,
+        y_vel: f32,
+        angle_vel: f32,
+        accel: f32,
+        angle_accel: f32,
+    ) -> Self {
+        Self {
+            session_id,
+            x,
+            y,
+            angle,
+            width,
+            height,
+            area,
+            x_vel,
+            y_vel,
+            angle_vel,
+            accel,
+            angle_accel,
+        }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        OscMessage {
+            addr: "/tuio2/bnd".to_string(),
+            args: vec![
+                OscType::Int(self.session_id as i32),
+                OscType::Float(self.x),
+                OscType::Float(self.y),
+                OscType::Float(self.angle),
+                OscType::Float(self.width),
+                OscType::Float(self.height),
+                OscType::Float(self.area),
+                OscType::Float(self.x_vel),
+                OscType::Float(self.y_vel),
+                OscType::Float(self.angle_vel),
+                OscType::Float(self.accel),
+                OscType::Float(self.angle_accel),
+            ],
+        }
+    }
+}
+
+/// SYM (Symbol) message - Symbol data of a tagged object
+/// OSC Address: /tuio2/sym
+///
+/// Parameters:
+/// 1. session_id (int32)
+/// 2. type_user_id (int32) - (type_id << 16) | user_id
+/// 3. component_id (int32)
+/// 4. group (string) - Symbol group, e.g. "fidtrk/18", "qr" or "rfid"
+/// 5. data (string) - Symbol payload, e.g. a fiducial ID or tag UID
+pub struct SymbolMessage {
+    pub session_id: u32,
+    pub type_id: u16,
+    pub user_id: u16,
+    pub component_id: u16,
+    pub group: String,
+    pub data: String,
+}
+
+impl SymbolMessage {
+    pub fn new(
+        session_id: u32,
+        type_id: u16,
+        user_id: u16,
+        component_id: u16,
+        group: String,
+        data: String,
+    ) -> Self {
+        Self {
+            session_id,
+            type_id,
+            user_id,
+            component_id,
+            group,
+            data,
+        }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        // Encode type_user_id: (type_id << 16) | user_id
+        let type_user_id = ((self.type_id as i32) << 16) | (self.user_id as i32);
+
+        OscMessage {
+            addr: "/tuio2/sym".to_string(),
+            args: vec![
+                OscType::Int(self.session_id as i32),
+                OscType::Int(type_user_id),
+                OscType::Int(self.component_id as i32),
+                OscType::String(self.group.clone()),
+                OscType::String(self.data.clone()),
+            ],
+        }
+    }
+}
+
+/// Append the coordinates of a point list as x, y float pairs
+fn push_points(args: &mut Vec<OscType>, points: &[[f32; 2]]) {
+    for [x, y] in points {
+        args.push(OscType::Float(*x));
+        args.push(OscType::Float(*y));
+    }
+}
+
+/// CHG (Convex Hull Geometry) message - Convex hull of an object
+/// OSC Address: /tuio2/chg
+///
+/// Parameters:
+/// 1. session_id (int32)
+/// 2. x_p0, y_p0 ... x_pN, y_pN (float) - Hull points
+pub struct ConvexHullMessage {
+    pub session_id: u32,
+    pub points: Vec<[f32; 2]>,
+}
+
+impl ConvexHullMessage {
+    pub fn new(session_id: u32, points: Vec<[f32; 2]>) -> Self {
+        Self { session_id, points }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        let mut args = vec![OscType::Int(self.session_id as i32)];
+        push_points(&mut args, &self.points);
+
+        OscMessage {
+            addr: "/tuio2/chg".to_string(),
+            args,
+        }
+    }
+}
+
+/// OCG (Outer Contour Geometry) message - Outer contour of an object
+/// OSC Address: /tuio2/ocg
+///
+/// Parameters:
+/// 1. session_id (int32)
+/// 2. x_p0, y_p0 ... x_pN, y_pN (float) - Contour points
+pub struct OuterContourMessage {
+    pub session_id: u32,
+    pub points: Vec<[f32; 2]>,
+}
+
+impl OuterContourMessage {
+    pub fn new(session_id: u32, points: Vec<[f32; 2]>) -> Self {
+        Self { session_id, points }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        let mut args = vec![OscType::Int(self.session_id as i32)];
+        push_points(&mut args, &self.points);
+
+        OscMessage {
+            addr: "/tuio2/ocg".to_string(),
+            args,
+        }
+    }
+}
+
+/// ICG (Inner Contour Geometry) message - Inner contours of an object
+/// OSC Address: /tuio2/icg
+///
+/// Parameters:
+/// 1. session_id (int32)
+/// 2. x_p0, y_p0 ... x_pN, y_pN (float) - Contour points, with a
+///    boolean true between two contours
+pub struct InnerContourMessage {
+    pub session_id: u32,
+    pub contours: Vec<Vec<[f32; 2]>>,
+}
+
+impl InnerContourMessage {
+    pub fn new(session_id: u32, contours: Vec<Vec<[f32; 2]>>) -> Self {
+        Self {
+            session_id,
+            contours,
+        }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        let mut args = vec![OscType::Int(self.session_id as i32)];
+        for (i, contour) in self.contours.iter().enumerate() {
+            if i > 0 {
+                args.push(OscType::Bool(true));
+            }
+            push_points(&mut args, contour);
+        }
+
+        OscMessage {
+            addr: "/tuio2/icg".to_string(),
+            args,
+        }
+    }
+}
+
+/// SKG (Skeleton Geometry) message - Skeleton of an object
+/// OSC Address: /tuio2/skg
+///
+/// Parameters:
+/// 1. session_id (int32)
+/// 2. x_p0, y_p0 ... x_pN, y_pN (float) - Branch points, with a
+///    boolean true ending a branch when another one follows
+pub struct SkeletonMessage {
+    pub session_id: u32,
+    pub branches: Vec<Vec<[f32; 2]>>,
+}
+
+impl SkeletonMessage {
+    pub fn new(session_id: u32, branches: Vec<Vec<[f32; 2]>>) -> Self {
+        Self {
+            session_id,
+            branches,
+        }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        let mut args = vec![OscType::Int(self.session_id as i32)];
+        for (i, branch) in self.branches.iter().enumerate() {
+            if i > 0 {
+                args.push(OscType::Bool(true));
+            }
+            push_points(&mut args, branch);
+        }
+
+        OscMessage {
+            addr: "/tuio2/skg".to_string(),
+            args,
+        }
+    }
+}
+
+/// CTL (Control) message - Control values of a smart tangible
+/// OSC Address: /tuio2/ctl
+///
+/// Parameters:
+/// 1. session_id (int32)
+/// 2. c0 ... cN (bool or float) - Button states and slider or sensor
+///    values between -1.0 and 1.0
+pub struct ControlMessage {
+    pub session_id: u32,
+    pub controls: Vec<ControlValue>,
+}
+
+impl ControlMessage {
+    pub fn new(session_id: u32, controls: Vec<ControlValue>) -> Self {
+        Self {
+            session_id,
+            controls,
+        }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        let mut args = vec![OscType::Int(self.session_id as i32)];
+        args.extend(self.controls.iter().map(|control| match *control {
+            ControlValue::Bool(value) => OscType::Bool(value),
+            ControlValue::Float(value) => OscType::Float(value),
+        }));
+
+        OscMessage {
+            addr: "/tuio2/ctl".to_string(),
+            args,
+        }
+    }
+}
+
+/// DAT (Data) message - MIME-typed data of an object
+/// OSC Address: /tuio2/dat
+///
+/// Parameters:
+/// 1. session_id (int32)
+/// 2. mime (string) - MIME type of the data
+/// 3. data (string or blob) - A string for text/* types, a blob otherwise
+pub struct DataMessage {
+    pub session_id: u32,
+    pub mime: String,
+    pub data: Vec<u8>,
+}
+
+impl DataMessage {
+    pub fn new(session_id: u32, mime: String, data: Vec<u8>) -> Self {
+        Self {
+            session_id,
+            mime,
+            data,
+        }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        let data = if self.mime.starts_with("text/") {
+            OscType::String(String::from_utf8_lossy(&self.data).into_owned())
+        } else {
+            OscType::Blob(self.data.clone())
+        };
+
+        OscMessage {
+            addr: "/tuio2/dat".to_string(),
+            args: vec![
+                OscType::Int(self.session_id as i32),
+                OscType::String(self.mime.clone()),
+                data,
+            ],
+        }
+    }
+}
+
+/// ALA (Alive Associations) message - Objects with associations
+/// OSC Address: /tuio2/ala
+///
+/// Parameters:
+/// - Variable number of session_id (int32) values
+/// - Lists all session IDs that take part in a COA or LIA association
+pub struct AliveAssociationsMessage {
+    pub session_ids: Vec<u32>,
+}
+
+impl AliveAssociationsMessage {
+    pub fn new(session_ids: Vec<u32>) -> Self {
+        Self { session_ids }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        let args: Vec<OscType> = self
+            .session_ids
+            .iter()
+            .map(|&id| OscType::Int(id as i32))
+            .collect();
+
+        OscMessage {
+            addr: "/tuio2/ala".to_string(),
+            args,
+        }
+    }
+}
+
+/// COA (Container Association) message - Objects placed in a container
+/// OSC Address: /tuio2/coa
+///
+/// Parameters:
+/// 1. session_id (int32) - Container object
+/// 2. slot (int32) - Slot of the container
+/// 3. s_id0 ... s_idN (int32) - Contained objects
+pub struct ContainerAssociationMessage {
+    pub session_id: u32,
+    pub slot: u32,
+    pub contained_ids: Vec<u32>,
+}
+
+impl ContainerAssociationMessage {
+    pub fn new(session_id: u32, slot: u32, contained_ids: Vec<u32>) -> Self {
+        Self {
+            session_id,
+            slot,
+            contained_ids,
+        }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        let mut args = vec![
+            OscType::Int(self.session_id as i32),
+            OscType::Int(self.slot as i32),
+        ];
+        args.extend(self.contained_ids.iter().map(|&id| OscType::Int(id as i32)));
+
+        OscMessage {
+            addr: "/tuio2/coa".to_string(),
+            args,
+        }
+    }
+}
+
+/// LIA (Link Association) message - Links from an object to others
+/// OSC Address: /tuio2/lia
+///
+/// Parameters:
+/// 1. session_id (int32) - Linking object
+/// 2. physical (bool) - Physical (true) or logical (false) links
+/// 3. s_id0, l_id0 ... s_idN, l_idN (int32) - Linked objects and their
+///    link IDs, encoded as (out_port << 16) | in_port
+pub struct LinkAssociationMessage {
+    pub session_id: u32,
+    pub physical: bool,
+    /// Linked session ID, output port and input port
+    pub links: Vec<(u32, u16, u16)>,
+}
+
+impl LinkAssociationMessage {
+    pub fn new(session_id: u32, physical: bool, links: Vec<(u32, u16, u16)>) -> Self {
+        Self {
+            session_id,
+            physical,
+            links,
+        }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        let mut args = vec![
+            OscType::Int(self.session_id as i32),
+            OscType::Bool(self.physical),
+        ];
+        for &(target_id, out_port, in_port) in &self.links {
+            // Encode link_id: (out_port << 16) | in_port
+            let link_id = ((out_port as i32) << 16) | (in_port as i32);
+            args.push(OscType::Int(target_id as i32));
+            args.push(OscType::Int(link_id));
+        }
+
+        OscMessage {
+            addr: "/tuio2/lia".to_string(),
+            args,
+        }
+    }
+}
+
+/// T3D (3D Token) message - Tagged object with a 3D pose
+/// OSC Address: /tuio2/t3d
+///
+/// Parameters:
+/// - session_id (int32)
+/// - type_user_id (int32) - (type_id << 16) | user_id
+/// - component_id (int32)
+/// - x, y, z (float) - Position, z normalized to the sensor depth
+/// - angle (float) - Rotation angle around the axis in radians
+/// - x_ax, y_ax, z_ax (float) - Unit rotation axis
+/// - x_vel, y_vel, z_vel (float) - Movement velocity
+/// - rotation_vel (float) - Rotation speed in radians per second
+/// - accel (float) - Motion acceleration
+/// - rotation_accel (float) - Rotation acceleration
+pub struct Token3DMessage {
+    pub session_id: u32,
+    pub type_id: u16,
+    pub user_id: u16,
+    pub component_id: u16,
+    pub position: [f32; 3],
+    pub angle: f32,
+    pub axis: [f32; 3],
+    pub velocity: [f32; 3],
+    pub rotation_vel: f32,
+    pub accel: f32,
+    pub rotation_accel: f32,
+}
+
+impl Token3DMessage {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        session_id: u32,
+        type_id: u16,
+        user_id: u16,
+        component_id: u16,
+        position: [f32; 3],
+        angle: f32,
+        axis: [f32; 3],
+        velocity: [f32; 3],
+        rotation_vel: f32,
+        accel: f32,
+        rotation_accel: f32,
+    ) -> Self {
+        Self {
+            session_id,
+            type_id,
+            user_id,
+            component_id,
+            position,
+            angle,
+            axis,
+            velocity,
+            rotation_vel,
+            accel,
+            rotation_accel,
+        }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        // Encode type_user_id: (type_id << 16) | user_id
+        let type_user_id = ((self.type_id as i32) << 16) | (self.user_id as i32);
+
+        let mut args = vec![
+            OscType::Int(self.session_id as i32),
+            OscType::Int(type_user_id),
+            OscType::Int(self.component_id as i32),
+        ];
+        args.extend(self.position.map(OscType::Float));
+        args.push(OscType::Float(self.angle));
+        args.extend(self.axis.map(OscType::Float));
+        args.extend(self.velocity.map(OscType::Float));
+        args.push(OscType::Float(self.rotation_vel));
+        args.push(OscType::Float(self.accel));
+        args.push(OscType::Float(self.rotation_accel));
+
+        OscMessage {
+            addr: "/tuio2/t3d".to_string(),
+            args,
+        }
+    }
+}
+
+/// P3D (3D Pointer) message - Pointer with a 3D position and direction
+/// OSC Address: /tuio2/p3d
+///
+/// Parameters:
+/// - session_id (int32)
+/// - type_user_id (int32) - (type_id << 16) | user_id
+/// - component_id (int32)
+/// - x, y, z (float) - Position, z normalized to the sensor depth
+/// - x_ax, y_ax, z_ax (float) - Unit pointing direction
+/// - radius (float) - Pointer radius
+/// - pressure (float) - Pressure, negative values for hovering
+/// - x_vel, y_vel, z_vel (float) - Movement velocity
+/// - pressure_vel (float) - Pressure change per second
+/// - accel (float) - Motion acceleration
+pub struct Pointer3DMessage {
+    pub session_id: u32,
+    pub type_id: u16,
+    pub user_id: u16,
+    pub component_id: u16,
+    pub position: [f32; 3],
+    pub direction: [f32; 3],
+    pub radius: f32,
+    pub pressure: f32,
+    pub velocity: [f32; 3],
+    pub pressure_vel: f32,
+    pub accel: f32,
+}
+
+impl Pointer3DMessage {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        session_id: u32,
+        type_id: u16,
+        user_id: u16,
+        component_id: u16,
+        position: [f32; 3],
+        direction: [f32; 3],
+        radius: f32,
+        pressure: f32,
+        velocity: [f32; 3],
+        pressure_vel: f32,
+        accel: f32,
+    ) -> Self {
+        Self {
+            session_id,
+            type_id,
+            user_id,
+            component_id,
+            position,
+            direction,
+            radius,
+            pressure,
+            velocity,
+            pressure_vel,
+            accel,
+        }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        // Encode type_user_id: (type_id << 16) | user_id
+        let type_user_id = ((self.type_id as i32) << 16) | (self.user_id as i32);
+
+        let mut args = vec![
+            OscType::Int(self.session_id as i32),
+            OscType::Int(type_user_id),
+            OscType::Int(self.component_id as i32),
+        ];
+        args.extend(self.position.map(OscType::Float));
+        args.extend(self.direction.map(OscType::Float));
+        args.push(OscType::Float(self.radius));
+        args.push(OscType::Float(self.pressure));
+        args.extend(self.velocity.map(OscType::Float));
+        args.push(OscType::Float(self.pressure_vel));
+        args.push(OscType::Float(self.accel));
+
+        OscMessage {
+            addr: "/tuio2/p3d".to_string(),
+            args,
+        }
+    }
+}
+
+/// R3D (3D Rotation) message - Orientation of an object as Euler angles
+/// OSC Address: /tuio2/r3d
+///
+/// Carries the same orientation as the axis and angle of T3D/B3D for
+/// clients that prefer Euler angles.
+///
+/// Parameters:
+/// - session_id (int32)
+/// - roll, pitch, yaw (float) - Rotation about x, y and z in radians
+/// - roll_vel, pitch_vel, yaw_vel (float) - Rotation velocities
+/// - rotation_accel (float) - Rotation acceleration
+pub struct Rotation3DMessage {
+    pub session_id: u32,
+    pub rotation: [f32; 3],
+    pub rotation_vel: [f32; 3],
+    pub rotation_accel: f32,
+}
+
+impl Rotation3DMessage {
+    pub fn new(
+        session_id: u32,
+        rotation: [f32; 3],
+        rotation_vel: [f32; 3],
+        rotation_accel: f32,
+    ) -> Self {
+        Self {
+            session_id,
+            rotation,
+            rotation_vel,
+            rotation_accel,
+        }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        let mut args = vec![OscType::Int(self.session_id as i32)];
+        args.extend(self.rotation.map(OscType::Float));
+        args.extend(self.rotation_vel.map(OscType::Float));
+        args.push(OscType::Float(self.rotation_accel));
+
+        OscMessage {
+            addr: "/tuio2/r3d".to_string(),
+            args,
+        }
+    }
+}
+
+/// B3D (3D Bounds) message - Oriented 3D bounding box
+/// OSC Address: /tuio2/b3d
+///
+/// Parameters:
+/// - session_id (int32)
+/// - x, y, z (float) - Center position
+/// - angle (float) - Rotation angle around the axis in radians
+/// - x_ax, y_ax, z_ax (float) - Unit rotation axis
+/// - width, height, depth (float) - Box size
+/// - volume (float) - Covered volume, at most width * height * depth
+/// - x_vel, y_vel, z_vel (float) - Movement velocity
+/// - rotation_vel (float) - Rotation speed in radians per second
+/// - accel (float) - Motion acceleration
+/// - rotation_accel (float) - Rotation acceleration
+pub struct Bounds3DMessage {
+    pub session_id: u32,
+    pub position: [f32; 3],
+    pub angle: f32,
+    pub axis: [f32; 3],
+    pub size: [f32; 3],
+    pub volume: f32,
+    pub velocity: [f32; 3],
+    pub rotation_vel: f32,
+    pub accel: f32,
+    pub rotation_accel: f32,
+}
+
+impl Bounds3DMessage {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        session_id: u32,
+        position: [f32; 3],
+        angle: f32,
+        axis: [f32; 3],
+        size: [f32; 3],
+        volume: f32,
+        velocity: [f32; 3],
+        rotation_vel: f32,
+        accel: f32,
+        rotation_accel: f32,
+    ) -> Self {
+        Self {
+            session_id,
+            position,
+            angle,
+            axis,
+            size,
+            volume,
+            velocity,
+            rotation_vel,
+            accel,
+            rotation_accel,
+        }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        let mut args = vec![OscType::Int(self.session_id as i32)];
+        args.extend(self.position.map(OscType::Float));
+        args.push(OscType::Float(self.angle));
+        args.extend(self.axis.map(OscType::Float));
+        args.extend(self.size.map(OscType::Float));
+        args.push(OscType::Float(self.volume));
+        args.extend(self.velocity.map(OscType::Float));
+        args.push(OscType::Float(self.rotation_vel));
+        args.push(OscType::Float(self.accel));
+        args.push(OscType::Float(self.rotation_accel));
+
+        OscMessage {
+            addr: "/tuio2/b3d".to_string(),
+            args,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -280,7 +1044,9 @@ mod tests {
 
     #[test]
     fn test_pointer_message() {
-        let ptr = PointerMessage::new(42, 1, 0, 0, 0.5, 0.5, 0.0, 0.0, 0.0);
+        let ptr = PointerMessage::new(
+            42, 1, 0, 0, 0.5, 0.5, 0.0, 0.1, 0.02, 1.0, 0.0, 0.0, -0.5, 0.0,
+        );
         let osc = ptr.to_osc();
 
         assert_eq!(osc.addr, "/tuio2/ptr");
@@ -299,5 +1065,249 @@ mod tests {
         } else {
             panic!("Expected Float for pressure");
         }
+
+        // Verify shear, radius and pressure velocity are passed through
+        assert_eq!(osc.args[6], OscType::Float(0.1));
+        assert_eq!(osc.args[7], OscType::Float(0.02));
+        assert_eq!(osc.args[11], OscType::Float(-0.5));
+    }
+
+    #[test]
+    fn test_bounds_message() {
+        let bnd = BoundsMessage::new(42, 0.5, 0.5, 0.3, 0.2, 0.1, 0.015, 0.0, 0.0, 0.0, 0.0, 0.4);
+        let osc = bnd.to_osc();
+
+        assert_eq!(osc.addr, "/tuio2/bnd");
+        assert_eq!(osc.args.len(), 12);
+
+        // Verify the session ID is not combined with a type ID
+        assert_eq!(osc.args[0], OscType::Int(42));
+
+        // Verify width, height and area order
+        assert_eq!(osc.args[4], OscType::Float(0.2));
+        assert_eq!(osc.args[5], OscType::Float(0.1));
+        assert_eq!(osc.args[6], OscType::Float(0.015));
+
+        // Verify the motion block ends with the rotation acceleration
+        assert_eq!(osc.args[11], OscType::Float(0.4));
+    }
+
+    #[test]
+    fn test_symbol_message() {
+        let sym = SymbolMessage::new(42, 3, 1, 18, "fidtrk/18".to_string(), "18".to_string());
+        let osc = sym.to_osc();
+
+        assert_eq!(osc.addr, "/tuio2/sym");
+        assert_eq!(osc.args.len(), 5);
+
+        // Verify type_user_id encoding: (3 << 16) | 1 = 196609
+        assert_eq!(osc.args[1], OscType::Int(196609));
+        assert_eq!(osc.args[3], OscType::String("fidtrk/18".to_string()));
+        assert_eq!(osc.args[4], OscType::String("18".to_string()));
+    }
+
+    #[test]
+    fn test_frame_message_with_depth() {
+        let frm = FrameMessage::new(1, 0, 1920, 1080, "test".to_string()).with_depth(500);
+        let osc = frm.to_osc();
+
+        assert_eq!(osc.args.len(), 5);
+        assert_eq!(osc.args[4], OscType::Int(500));
+    }
+
+    #[test]
+    fn test_token_3d_message() {
+        let t3d = Token3DMessage::new(
+            42,
+            1,
+            0,
+            3,
+            [0.5, 0.5, 0.1],
+            0.3,
+            [0.0, 0.0, 1.0],
+            [0.0, 0.0, 0.2],
+            0.0,
+            0.0,
+            0.0,
+        );
+        let osc = t3d.to_osc();
+
+        assert_eq!(osc.addr, "/tuio2/t3d");
+        assert_eq!(osc.args.len(), 16);
+        assert_eq!(osc.args[1], OscType::Int(65536));
+        assert_eq!(osc.args[5], OscType::Float(0.1)); // z
+        assert_eq!(osc.args[6], OscType::Float(0.3)); // angle
+        assert_eq!(osc.args[9], OscType::Float(1.0)); // z_ax
+        assert_eq!(osc.args[12], OscType::Float(0.2)); // z_vel
+    }
+
+    #[test]
+    fn test_pointer_3d_message() {
+        let p3d = Pointer3DMessage::new(
+            42,
+            21,
+            0,
+            0,
+            [0.5, 0.5, 0.1],
+            [0.0, 0.0, -1.0],
+            0.02,
+            -0.5,
+            [0.0; 3],
+            0.0,
+            0.0,
+        );
+        let osc = p3d.to_osc();
+
+        assert_eq!(osc.addr, "/tuio2/p3d");
+        assert_eq!(osc.args.len(), 16);
+        assert_eq!(osc.args[8], OscType::Float(-1.0)); // z direction
+        assert_eq!(osc.args[9], OscType::Float(0.02)); // radius
+        assert_eq!(osc.args[10], OscType::Float(-0.5)); // pressure
+    }
+
+    #[test]
+    fn test_rotation_3d_message() {
+        let r3d = Rotation3DMessage::new(42, [0.1, 0.2, 0.3], [1.0, 0.0, 0.0], 0.0);
+        let osc = r3d.to_osc();
+
+        assert_eq!(osc.addr, "/tuio2/r3d");
+        assert_eq!(osc.args.len(), 8);
+        assert_eq!(osc.args[3], OscType::Float(0.3)); // yaw
+        assert_eq!(osc.args[4], OscType::Float(1.0)); // roll_vel
+    }
+
+    #[test]
+    fn test_bounds_3d_message() {
+        let b3d = Bounds3DMessage::new(
+            42,
+            [0.5, 0.5, 0.0],
+            0.0,
+            [0.0, 0.0, 1.0],
+            [0.2, 0.1, 0.05],
+            0.001,
+            [0.0; 3],
+            0.0,
+            0.0,
+            0.0,
+        );
+        let osc = b3d.to_osc();
+
+        assert_eq!(osc.addr, "/tuio2/b3d");
+        assert_eq!(osc.args.len(), 18);
+        assert_eq!(osc.args[0], OscType::Int(42));
+        assert_eq!(osc.args[10], OscType::Float(0.05)); // depth
+        assert_eq!(osc.args[11], OscType::Float(0.001)); // volume
+    }
+
+    #[test]
+    fn test_convex_hull_message() {
+        let chg = ConvexHullMessage::new(42, vec![[0.1, 0.2], [0.3, 0.4], [0.5, 0.6]]);
+        let osc = chg.to_osc();
+
+        assert_eq!(osc.addr, "/tuio2/chg");
+        assert_eq!(osc.args.len(), 7);
+        assert_eq!(osc.args[0], OscType::Int(42));
+        assert_eq!(osc.args[3], OscType::Float(0.3));
+        assert_eq!(osc.args[6], OscType::Float(0.6));
+    }
+
+    #[test]
+    fn test_outer_contour_message() {
+        let ocg = OuterContourMessage::new(42, vec![[0.1, 0.2], [0.3, 0.4]]);
+        let osc = ocg.to_osc();
+
+        assert_eq!(osc.addr, "/tuio2/ocg");
+        assert_eq!(osc.args.len(), 5);
+    }
+
+    #[test]
+    fn test_inner_contour_message() {
+        let icg = InnerContourMessage::new(
+            42,
+            vec![vec![[0.1, 0.1], [0.2, 0.1], [0.2, 0.2]], vec![[0.5, 0.5]]],
+        );
+        let osc = icg.to_osc();
+
+        assert_eq!(osc.addr, "/tuio2/icg");
+        // session ID + 3 points + separator + 1 point
+        assert_eq!(osc.args.len(), 1 + 6 + 1 + 2);
+        assert_eq!(osc.args[7], OscType::Bool(true));
+    }
+
+    #[test]
+    fn test_skeleton_message() {
+        let skg = SkeletonMessage::new(42, vec![vec![[0.1, 0.1], [0.2, 0.2]]]);
+        let osc = skg.to_osc();
+
+        assert_eq!(osc.addr, "/tuio2/skg");
+        // A single branch has no separator
+        assert_eq!(osc.args.len(), 5);
+    }
+
+    #[test]
+    fn test_control_message() {
+        let ctl = ControlMessage::new(
+            42,
+            vec![ControlValue::Bool(true), ControlValue::Float(-0.5)],
+        );
+        let osc = ctl.to_osc();
+
+        assert_eq!(osc.addr, "/tuio2/ctl");
+        assert_eq!(
+            osc.args,
+            vec![OscType::Int(42), OscType::Bool(true), OscType::Float(-0.5)]
+        );
+    }
+
+    #[test]
+    fn test_data_message() {
+        let text = DataMessage::new(42, "text/plain".to_string(), b"hello".to_vec());
+        let osc = text.to_osc();
+
+        assert_eq!(osc.addr, "/tuio2/dat");
+        assert_eq!(osc.args[1], OscType::String("text/plain".to_string()));
+        assert_eq!(osc.args[2], OscType::String("hello".to_string()));
+
+        // Binary data is sent as a blob
+        let binary = DataMessage::new(42, "application/octet-stream".to_string(), vec![0, 255]);
+        assert_eq!(binary.to_osc().args[2], OscType::Blob(vec![0, 255]));
+    }
+
+    #[test]
+    fn test_alive_associations_message() {
+        let ala = AliveAssociationsMessage::new(vec![1, 2]);
+        let osc = ala.to_osc();
+
+        assert_eq!(osc.addr, "/tuio2/ala");
+        assert_eq!(osc.args, vec![OscType::Int(1), OscType::Int(2)]);
+    }
+
+    #[test]
+    fn test_container_association_message() {
+        let coa = ContainerAssociationMessage::new(42, 1, vec![43, 44]);
+        let osc = coa.to_osc();
+
+        assert_eq!(osc.addr, "/tuio2/coa");
+        assert_eq!(
+            osc.args,
+            vec![
+                OscType::Int(42),
+                OscType::Int(1),
+                OscType::Int(43),
+                OscType::Int(44)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_link_association_message() {
+        let lia = LinkAssociationMessage::new(42, true, vec![(43, 1, 2)]);
+        let osc = lia.to_osc();
+
+        assert_eq!(osc.addr, "/tuio2/lia");
+        assert_eq!(osc.args.len(), 4);
+        assert_eq!(osc.args[1], OscType::Bool(true));
+        // (1 << 16) | 2 = 65538
+        assert_eq!(osc.args[3], OscType::Int(65538));
     }
 }
@@ -1,3 +1,7 @@
+pub mod decoder;
 pub mod encoder;
 pub mod frame;
+pub mod geometry;
+pub mod json;
 pub mod messages;
+pub mod tuio11;
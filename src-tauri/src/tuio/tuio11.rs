@@ -0,0 +1,450 @@
+use crate::state::ObjectKind;
+use rosc::{OscMessage, OscType};
+use std::f32::consts::TAU;
+
+/// TUIO 1.1 profile
+///
+/// Each profile has its own OSC address and carries its own
+/// source, alive, set and fseq messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Profile {
+    Object, // 2Dobj - Tagged objects (fiducials)
+    Cursor, // 2Dcur - Touch points
+    Blob,   // 2Dblb - Untagged objects
+}
+
+impl Profile {
+    /// All profiles in the order they are sent
+    pub const ALL: [Profile; 3] = [Profile::Object, Profile::Cursor, Profile::Blob];
+
+    /// Profile objects of the given kind are sent on
+    pub fn of(kind: ObjectKind) -> Self {
+        match kind {
+            ObjectKind::Token => Profile::Object,
+            ObjectKind::Pointer => Profile::Cursor,
+            ObjectKind::Blob => Profile::Blob,
+        }
+    }
+
+    /// OSC address of this profile
+    pub fn address(&self) -> &'static str {
+        match self {
+            Profile::Object => "/tuio/2Dobj",
+            Profile::Cursor => "/tuio/2Dcur",
+            Profile::Blob => "/tuio/2Dblb",
+        }
+    }
+
+    /// Whether the profile is left out of frames without its objects
+    ///
+    /// 2Dobj and 2Dcur are always sent, as clients rely on the repeated
+    /// alive message to recover from lost packets.
+    pub fn is_optional(&self) -> bool {
+        *self == Profile::Blob
+    }
+}
+
+/// Convert an angular value in radians to full rotations, as used by
+/// the TUIO 1.1 rotation velocity and acceleration fields
+fn rotations(radians: f32) -> f32 {
+    radians / TAU
+}
+
+/// source message - Identifies the sender of the following messages
+/// OSC Address: /tuio/[profile] source
+///
+/// Parameters:
+/// 1. "source" (string)
+/// 2. source (string) - Source identifier, usually name@address
+pub struct SourceMessage {
+    pub profile: Profile,
+    pub source: String,
+}
+
+impl SourceMessage {
+    pub fn new(profile: Profile, source: String) -> Self {
+        Self { profile, source }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        OscMessage {
+            addr: self.profile.address().to_string(),
+            args: vec![
+                OscType::String("source".to_string()),
+                OscType::String(self.source.clone()),
+            ],
+        }
+    }
+}
+
+/// alive message - Lists all active session IDs of a profile
+/// OSC Address: /tuio/[profile] alive
+///
+/// Parameters:
+/// 1. "alive" (string)
+/// - Variable number of session_id (int32) values
+pub struct AliveMessage {
+    pub profile: Profile,
+    pub session_ids: Vec<u32>,
+}
+
+impl AliveMessage {
+    pub fn new(profile: Profile, session_ids: Vec<u32>) -> Self {
+        Self {
+            profile,
+            session_ids,
+        }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        let mut args = vec![OscType::String("alive".to_string())];
+        args.extend(self.session_ids.iter().map(|&id| OscType::Int(id as i32)));
+
+        OscMessage {
+            addr: self.profile.address().to_string(),
+            args,
+        }
+    }
+}
+
+/// fseq message - Closes the messages of a profile for one frame
+/// OSC Address: /tuio/[profile] fseq
+///
+/// Parameters:
+/// 1. "fseq" (string)
+/// 2. frame_id (int32) - Sequential frame counter
+pub struct FseqMessage {
+    pub profile: Profile,
+    pub frame_id: u32,
+}
+
+impl FseqMessage {
+    pub fn new(profile: Profile, frame_id: u32) -> Self {
+        Self { profile, frame_id }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        OscMessage {
+            addr: self.profile.address().to_string(),
+            args: vec![
+                OscType::String("fseq".to_string()),
+                OscType::Int(self.frame_id as i32),
+            ],
+        }
+    }
+}
+
+/// 2Dobj set message - Represents a tagged object
+/// OSC Address: /tuio/2Dobj set
+///
+/// Parameters: s i x y a X Y A m r
+/// - s: session_id (int32)
+/// - i: class_id (int32) - Fiducial ID
+/// - x, y: position (float, 0-1)
+/// - a: angle (float, radians 0-2π)
+/// - X, Y: velocity (float, units per second)
+/// - A: rotation velocity (float, rotations per second)
+/// - m: motion acceleration (float)
+/// - r: rotation acceleration (float, rotations per second²)
+pub struct ObjectSetMessage {
+    pub session_id: u32,
+    pub class_id: u16,
+    pub x: f32,
+    pub y: f32,
+    pub angle: f32,
+    pub x_vel: f32,
+    pub y_vel: f32,
+    pub angle_vel: f32,
+    pub accel: f32,
+    pub angle_accel: f32,
+}
+
+impl ObjectSetMessage {
+    /// Angular values are given in radians and converted on encoding
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        session_id: u32,
+        class_id: u16,
+        x: f32,
+        y: f32,
+        angle: f32,
+        x_vel: f32,
+        y_vel: f32,
+        angle_vel: f32,
+        accel: f32,
+        angle_accel: f32,
+    ) -> Self {
+        Self {
+            session_id,
+            class_id,
+            x,
+            y,
+            angle,
+            x_vel,
+            y_vel,
+            angle_vel,
+            accel,
+            angle_accel,
+        }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        OscMessage {
+            addr: Profile::Object.address().to_string(),
+            args: vec![
+                OscType::String("set".to_string()),
+                OscType::Int(self.session_id as i32),
+                OscType::Int(self.class_id as i32),
+                OscType::Float(self.x),
+                OscType::Float(self.y),
+                OscType::Float(self.angle.rem_euclid(TAU)),
+                OscType::Float(self.x_vel),
+                OscType::Float(self.y_vel),
+                OscType::Float(rotations(self.angle_vel)),
+                OscType::Float(self.accel),
+                OscType::Float(rotations(self.angle_accel)),
+            ],
+        }
+    }
+}
+
+/// 2Dcur set message - Represents a touch point
+/// OSC Address: /tuio/2Dcur set
+///
+/// Parameters: s x y X Y m
+/// - s: session_id (int32)
+/// - x, y: position (float, 0-1)
+/// - X, Y: velocity (float, units per second)
+/// - m: motion acceleration (float)
+pub struct CursorSetMessage {
+    pub session_id: u32,
+    pub x: f32,
+    pub y: f32,
+    pub x_vel: f32,
+    pub y_vel: f32,
+    pub accel: f32,
+}
+
+impl CursorSetMessage {
+    pub fn new(session_id: u32, x: f32, y: f32, x_vel: f32, y_vel: f32, accel: f32) -> Self {
+        Self {
+            session_id,
+            x,
+            y,
+            x_vel,
+            y_vel,
+            accel,
+        }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        OscMessage {
+            addr: Profile::Cursor.address().to_string(),
+            args: vec![
+                OscType::String("set".to_string()),
+                OscType::Int(self.session_id as i32),
+                OscType::Float(self.x),
+                OscType::Float(self.y),
+                OscType::Float(self.x_vel),
+                OscType::Float(self.y_vel),
+                OscType::Float(self.accel),
+            ],
+        }
+    }
+}
+
+/// 2Dblb set message - Represents an untagged object
+/// OSC Address: /tuio/2Dblb set
+///
+/// Parameters: s x y a w h f X Y A m r
+/// - s: session_id (int32)
+/// - x, y: position (float, 0-1)
+/// - a: angle (float, radians 0-2π)
+/// - w, h: width and height (float, 0-1)
+/// - f: area (float, 0-1)
+/// - X, Y: velocity (float, units per second)
+/// - A: rotation velocity (float, rotations per second)
+/// - m: motion acceleration (float)
+/// - r: rotation acceleration (float, rotations per second²)
+pub struct BlobSetMessage {
+    pub session_id: u32,
+    pub x: f32,
+    pub y: f32,
+    pub angle: f32,
+    pub width: f32,
+    pub height: f32,
+    pub area: f32,
+    pub x_vel: f32,
+    pub y_vel: f32,
+    pub angle_vel: f32,
+    pub accel: f32,
+    pub angle_accel: f32,
+}
+
+impl BlobSetMessage {
+    /// Angular values are given in radians and converted on encoding
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        session_id: u32,
+        x: f32,
+        y: f32,
+        angle: f32,
+        width: f32,
+        height: f32,
+        area: f32,
+        x_vel: f32,
+        y_vel: f32,
+        angle_vel: f32,
+        accel: f32,
+        angle_accel: f32,
+    ) -> Self {
+        Self {
+            session_id,
+            x,
+            y,
+            angle,
+            width,
+            height,
+            area,
+            x_vel,
+            y_vel,
+            angle_vel,
+            accel,
+            angle_accel,
+        }
+    }
+
+    /// Convert to OSC message
+    pub fn to_osc(&self) -> OscMessage {
+        OscMessage {
+            addr: Profile::Blob.address().to_string(),
+            args: vec![
+                OscType::String("set".to_string()),
+                OscType::Int(self.session_id as i32),
+                OscType::Float(self.x),
+                OscType::Float(self.y),
+                OscType::Float(self.angle.rem_euclid(TAU)),
+                OscType::Float(self.width),
+                OscType::Float(self.height),
+                OscType::Float(self.area),
+                OscType::Float(self.x_vel),
+                OscType::Float(self.y_vel),
+                OscType::Float(rotations(self.angle_vel)),
+                OscType::Float(self.accel),
+                OscType::Float(rotations(self.angle_accel)),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rosc::{encoder, OscPacket};
+
+    fn encode(msg: OscMessage) -> Vec<u8> {
+        encoder::encode(&OscPacket::Message(msg)).unwrap()
+    }
+
+    #[test]
+    fn test_source_message() {
+        let msg = SourceMessage::new(Profile::Object, "sim".to_string()).to_osc();
+
+        assert_eq!(msg.addr, "/tuio/2Dobj");
+        assert_eq!(msg.args.len(), 2);
+        assert_eq!(msg.args[0], OscType::String("source".to_string()));
+    }
+
+    #[test]
+    fn test_alive_message_layout() {
+        let bytes = encode(AliveMessage::new(Profile::Object, vec![7]).to_osc());
+
+        #[rustfmt::skip]
+        let expected: Vec<u8> = vec![
+            b'/', b't', b'u', b'i', b'o', b'/', b'2', b'D', b'o', b'b', b'j', 0,
+            b',', b's', b'i', 0,
+            b'a', b'l', b'i', b'v', b'e', 0, 0, 0,
+            0, 0, 0, 7,
+        ];
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn test_fseq_message_layout() {
+        let bytes = encode(FseqMessage::new(Profile::Cursor, 258).to_osc());
+
+        #[rustfmt::skip]
+        let expected: Vec<u8> = vec![
+            b'/', b't', b'u', b'i', b'o', b'/', b'2', b'D', b'c', b'u', b'r', 0,
+            b',', b's', b'i', 0,
+            b'f', b's', b'e', b'q', 0, 0, 0, 0,
+            0, 0, 1, 2,
+        ];
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn test_object_set_message_layout() {
+        let set = ObjectSetMessage::new(1, 5, 0.5, 0.25, 0.0, 1.0, 0.0, TAU, 2.0, 0.0);
+        let bytes = encode(set.to_osc());
+
+        #[rustfmt::skip]
+        let expected: Vec<u8> = vec![
+            b'/', b't', b'u', b'i', b'o', b'/', b'2', b'D', b'o', b'b', b'j', 0,
+            b',', b's', b'i', b'i', b'f', b'f', b'f', b'f', b'f', b'f', b'f', b'f', 0, 0, 0, 0,
+            b's', b'e', b't', 0,
+            0, 0, 0, 1,             // s
+            0, 0, 0, 5,             // i
+            0x3f, 0x00, 0x00, 0x00, // x = 0.5
+            0x3e, 0x80, 0x00, 0x00, // y = 0.25
+            0x00, 0x00, 0x00, 0x00, // a = 0.0
+            0x3f, 0x80, 0x00, 0x00, // X = 1.0
+            0x00, 0x00, 0x00, 0x00, // Y = 0.0
+            0x3f, 0x80, 0x00, 0x00, // A = 1 rotation per second
+            0x40, 0x00, 0x00, 0x00, // m = 2.0
+            0x00, 0x00, 0x00, 0x00, // r = 0.0
+        ];
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn test_cursor_set_message_layout() {
+        let set = CursorSetMessage::new(3, 0.5, 0.5, 0.0, -2.0, 0.0);
+        let bytes = encode(set.to_osc());
+
+        #[rustfmt::skip]
+        let expected: Vec<u8> = vec![
+            b'/', b't', b'u', b'i', b'o', b'/', b'2', b'D', b'c', b'u', b'r', 0,
+            b',', b's', b'i', b'f', b'f', b'f', b'f', b'f', 0, 0, 0, 0,
+            b's', b'e', b't', 0,
+            0, 0, 0, 3,             // s
+            0x3f, 0x00, 0x00, 0x00, // x = 0.5
+            0x3f, 0x00, 0x00, 0x00, // y = 0.5
+            0x00, 0x00, 0x00, 0x00, // X = 0.0
+            0xc0, 0x00, 0x00, 0x00, // Y = -2.0
+            0x00, 0x00, 0x00, 0x00, // m = 0.0
+        ];
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn test_blob_set_message() {
+        let set = BlobSetMessage::new(9, 0.5, 0.5, -1.0, 0.2, 0.1, 0.02, 0.0, 0.0, 0.0, 0.0, 0.0);
+        let msg = set.to_osc();
+
+        assert_eq!(msg.addr, "/tuio/2Dblb");
+        assert_eq!(msg.args.len(), 13);
+
+        // Negative angles are wrapped into [0, 2π)
+        if let OscType::Float(angle) = msg.args[4] {
+            assert!((angle - (TAU - 1.0)).abs() < 1e-6);
+        } else {
+            panic!("Expected Float for angle");
+        }
+    }
+}
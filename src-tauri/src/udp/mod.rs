@@ -0,0 +1,286 @@
+use crate::state::Config;
+use crate::transport::{SinkError, SinkKind, TransportSink};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+
+/// A resolved UDP destination
+#[derive(Debug, Clone)]
+pub struct UdpTarget {
+    /// Target as configured by the user (e.g. "localhost:3333")
+    pub label: String,
+    pub addr: SocketAddr,
+}
+
+/// Send failure for a single UDP target
+#[derive(Debug, Clone)]
+pub struct UdpSendError {
+    pub target: String,
+    pub message: String,
+}
+
+/// Sockets used for sending, one per address family
+struct UdpSockets {
+    v4: Option<Arc<UdpSocket>>,
+    v6: Option<Arc<UdpSocket>>,
+}
+
+/// UDP sender state
+///
+/// Sends every encoded TUIO bundle as a single datagram to a list of
+/// host:port targets, as expected by reacTIVision-compatible clients.
+pub struct UdpSender {
+    sockets: Arc<Mutex<Option<UdpSockets>>>,
+    targets: Arc<Mutex<Vec<UdpTarget>>>,
+    failing_targets: Arc<Mutex<HashSet<String>>>,
+}
+
+impl UdpSender {
+    /// Create a new UDP sender without any targets
+    pub fn new() -> Self {
+        Self {
+            sockets: Arc::new(Mutex::new(None)),
+            targets: Arc::new(Mutex::new(Vec::new())),
+            failing_targets: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    /// Get the number of configured targets
+    pub fn get_target_count(&self) -> usize {
+        self.targets.lock().len()
+    }
+
+    /// Check whether the sender has been started
+    pub fn is_running(&self) -> bool {
+        self.sockets.lock().is_some()
+    }
+
+    /// Resolve and replace the list of targets
+    ///
+    /// Takes effect immediately if the sender is already running.
+    pub async fn set_targets(&self, targets: &[String]) -> Result<()> {
+        let resolved = resolve_targets(targets).await?;
+
+        *self.targets.lock() = resolved;
+        self.failing_targets.lock().clear();
+
+        Ok(())
+    }
+
+    /// Start the UDP sender for the given targets
+    ///
+    /// Binds an ephemeral IPv4 socket and, if available, an IPv6 socket.
+    pub async fn start(&self, targets: &[String]) -> Result<()> {
+        // Stop any existing sender first
+        self.stop();
+
+        self.set_targets(targets).await?;
+
+        let v4 = UdpSocket::bind("0.0.0.0:0").await?;
+        // Allow sending to broadcast addresses such as 192.168.1.255
+        v4.set_broadcast(true)?;
+
+        // IPv6 may be unavailable on this host, which is fine as long as
+        // no IPv6 targets are configured
+        let v6 = UdpSocket::bind("[::]:0").await.ok();
+
+        println!(
+            "UDP sender started with {} target(s)",
+            self.get_target_count()
+        );
+
+        let mut sockets = self.sockets.lock();
+        *sockets = Some(UdpSockets {
+            v4: Some(Arc::new(v4)),
+            v6: v6.map(Arc::new),
+        });
+
+        Ok(())
+    }
+
+    /// Stop the UDP sender
+    pub fn stop(&self) {
+        let mut sockets = self.sockets.lock();
+        if sockets.take().is_some() {
+            println!("UDP sender stopped");
+        }
+
+        self.failing_targets.lock().clear();
+    }
+
+    /// Send binary data to all configured targets
+    ///
+    /// Returns the errors of targets that started failing with this send.
+    /// A target that keeps failing is only reported again after a
+    /// successful send in between, so a missing client does not produce
+    /// an error on every frame.
+    pub async fn send(&self, data: &[u8]) -> Vec<UdpSendError> {
+        let (v4, v6) = match &*self.sockets.lock() {
+            Some(sockets) => (sockets.v4.clone(), sockets.v6.clone()),
+            None => return Vec::new(),
+        };
+        let targets = self.targets.lock().clone();
+
+        let mut errors = Vec::new();
+
+        for target in targets {
+            let socket = if target.addr.is_ipv4() {
+                v4.as_ref()
+            } else {
+                v6.as_ref()
+            };
+
+            let result = match socket {
+                Some(socket) => socket.send_to(data, target.addr).await.map(|_| ()),
+                None => Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "IPv6 is not available",
+                )),
+            };
+
+            match result {
+                Ok(()) => {
+                    self.failing_targets.lock().remove(&target.label);
+                }
+                Err(e) => {
+                    let newly_failing = self.failing_targets.lock().insert(target.label.clone());
+                    if newly_failing {
+                        errors.push(UdpSendError {
+                            target: target.label,
+                            message: e.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+impl Default for UdpSender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl TransportSink for UdpSender {
+    fn kind(&self) -> SinkKind {
+        SinkKind::Udp
+    }
+
+    async fn start(&self, config: &Config) -> Result<()> {
+        UdpSender::start(self, &config.udp_targets).await
+    }
+
+    async fn stop(&self) {
+        UdpSender::stop(self)
+    }
+
+    async fn send(&self, data: &[u8]) -> Vec<SinkError> {
+        UdpSender::send(self, data)
+            .await
+            .into_iter()
+            .map(|e| SinkError {
+                kind: SinkKind::Udp,
+                target: e.target,
+                message: e.message,
+            })
+            .collect()
+    }
+
+    fn is_running(&self) -> bool {
+        UdpSender::is_running(self)
+    }
+
+    fn get_connections(&self) -> usize {
+        self.get_target_count()
+    }
+}
+
+/// Resolve a list of host:port strings to socket addresses
+///
+/// Host names are resolved once here rather than on every frame.
+pub async fn resolve_targets(targets: &[String]) -> Result<Vec<UdpTarget>> {
+    let mut resolved = Vec::with_capacity(targets.len());
+
+    for target in targets {
+        let addr = tokio::net::lookup_host(target.as_str())
+            .await
+            .map_err(|e| anyhow!("{}: {}", target, e))?
+            .next()
+            .ok_or_else(|| anyhow!("{}: no address found", target))?;
+
+        resolved.push(UdpTarget {
+            label: target.clone(),
+            addr,
+        });
+    }
+
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_udp_sender_creation() {
+        let sender = UdpSender::new();
+        assert_eq!(sender.get_target_count(), 0);
+        assert!(!sender.is_running());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_targets() {
+        let targets = vec!["127.0.0.1:3333".to_string()];
+        let resolved = resolve_targets(&targets).await.unwrap();
+
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].label, "127.0.0.1:3333");
+        assert_eq!(resolved[0].addr.port(), 3333);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_targets_invalid() {
+        let targets = vec!["not a target".to_string()];
+        assert!(resolve_targets(&targets).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_send_before_start() {
+        let sender = UdpSender::new();
+        sender
+            .set_targets(&["127.0.0.1:3333".to_string()])
+            .await
+            .unwrap();
+
+        // Not running, so nothing is sent and nothing fails
+        let errors = sender.send(&[1, 2, 3]).await;
+        assert!(errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_send_to_target() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let target = receiver.local_addr().unwrap().to_string();
+
+        let sender = UdpSender::new();
+        sender.start(&[target]).await.unwrap();
+
+        let errors = sender.send(&[1, 2, 3]).await;
+        assert!(errors.is_empty());
+
+        let mut buf = [0u8; 16];
+        let (len, _) = receiver.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..len], &[1, 2, 3]);
+
+        sender.stop();
+        assert!(!sender.is_running());
+    }
+}
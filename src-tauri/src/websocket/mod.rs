@@ -1,11 +1,20 @@
+use crate::state::Config;
+use crate::transport::bind::bind_listener;
+use crate::transport::clients::ClientRegistry;
+use crate::transport::{SinkError, SinkKind, TransportSink};
 use anyhow::Result;
+use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
 use parking_lot::Mutex;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::broadcast;
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc};
 use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::header::{
+    HeaderValue, SEC_WEBSOCKET_PROTOCOL, USER_AGENT,
+};
 use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::WebSocketStream;
 
@@ -15,20 +24,22 @@ pub type BroadcastReceiver = broadcast::Receiver<Vec<u8>>;
 /// WebSocket server state
 pub struct WebSocketServer {
     broadcast_tx: BroadcastSender,
-    connected_clients: Arc<Mutex<usize>>,
+    clients: Arc<ClientRegistry>,
+    local_addr: Mutex<Option<SocketAddr>>,
     server_task: Arc<Mutex<Option<JoinHandle<()>>>>,
 }
 
 impl WebSocketServer {
-    /// Create a new WebSocket server
-    pub fn new() -> Self {
+    /// Create a new WebSocket server registering its clients in the registry
+    pub fn new(clients: Arc<ClientRegistry>) -> Self {
         // Create broadcast channel for sending messages to all clients
         // Buffer size of 100 messages
         let (broadcast_tx, _) = broadcast::channel(100);
 
         Self {
             broadcast_tx,
-            connected_clients: Arc::new(Mutex::new(0)),
+            clients,
+            local_addr: Mutex::new(None),
             server_task: Arc::new(Mutex::new(None)),
         }
     }
@@ -41,27 +52,31 @@ impl WebSocketServer {
 
     /// Get the current number of connected clients
     pub fn get_connected_clients(&self) -> usize {
-        *self.connected_clients.lock()
+        self.clients.count(SinkKind::WebSocket)
     }
 
-    /// Start the WebSocket server on the specified port
+    /// Get the address the server is listening on
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        *self.local_addr.lock()
+    }
+
+    /// Start the WebSocket server on the specified address
     ///
     /// This function spawns a tokio task that listens for incoming connections
     /// and handles them asynchronously.
-    pub async fn start(
-        &self,
-        port: u16,
-    ) -> Result<()> {
+    pub async fn start(&self, addr: SocketAddr) -> Result<()> {
         // Stop any existing server first
         self.stop().await;
 
-        let addr: SocketAddr = format!("127.0.0.1:{}", port).parse()?;
-        let listener = TcpListener::bind(&addr).await?;
+        let listener = bind_listener(addr).await?;
+        let local_addr = listener.local_addr()?;
+
+        println!("WebSocket server listening on: {}", local_addr);
 
-        println!("WebSocket server listening on: {}", addr);
+        *self.local_addr.lock() = Some(local_addr);
 
         let broadcast_tx = self.broadcast_tx.clone();
-        let connected_clients = self.connected_clients.clone();
+        let clients = self.clients.clone();
 
         // Spawn task to accept connections
         let task = tokio::spawn(async move {
@@ -70,25 +85,15 @@ impl WebSocketServer {
                     Ok((stream, addr)) => {
                         println!("New WebSocket connection from: {}", addr);
 
-                        // Increment connected clients
-                        {
-                            let mut count = connected_clients.lock();
-                            *count += 1;
-                        }
-
                         let broadcast_rx = broadcast_tx.subscribe();
-                        let clients = connected_clients.clone();
+                        let clients = clients.clone();
 
                         // Spawn task to handle this client
                         tokio::spawn(async move {
-                            if let Err(e) = handle_connection(stream, broadcast_rx).await {
-                                eprintln!("Error handling connection from {}: {}", addr, e);
-                            }
-
-                            // Decrement connected clients on disconnect
+                            if let Err(e) =
+                                handle_connection(stream, addr, broadcast_rx, clients).await
                             {
-                                let mut count = clients.lock();
-                                *count = count.saturating_sub(1);
+                                eprintln!("Error handling connection from {}: {}", addr, e);
                             }
 
                             println!("Client disconnected: {}", addr);
@@ -119,11 +124,10 @@ impl WebSocketServer {
             println!("WebSocket server stopped");
         }
 
-        // Reset connected clients count
-        {
-            let mut count = self.connected_clients.lock();
-            *count = 0;
-        }
+        *self.local_addr.lock() = None;
+
+        // Close the connections of all clients
+        self.clients.disconnect_sink(SinkKind::WebSocket);
     }
 
     /// Broadcast binary data to all connected clients
@@ -137,34 +141,107 @@ impl WebSocketServer {
 
 impl Default for WebSocketServer {
     fn default() -> Self {
-        Self::new()
+        Self::new(Arc::default())
+    }
+}
+
+#[async_trait]
+impl TransportSink for WebSocketServer {
+    fn kind(&self) -> SinkKind {
+        SinkKind::WebSocket
+    }
+
+    async fn start(&self, config: &Config) -> Result<()> {
+        let addr = SocketAddr::new(config.bind_address.ip(), config.port);
+        WebSocketServer::start(self, addr).await
+    }
+
+    async fn stop(&self) {
+        WebSocketServer::stop(self).await
+    }
+
+    async fn send(&self, data: &[u8]) -> Vec<SinkError> {
+        // Broadcasting never fails, lagging clients are handled per connection
+        let _ = self.broadcast(data.to_vec()).await;
+        Vec::new()
+    }
+
+    fn is_running(&self) -> bool {
+        self.server_task.lock().is_some()
+    }
+
+    fn get_connections(&self) -> usize {
+        self.get_connected_clients()
+    }
+
+    fn local_addrs(&self) -> Vec<SocketAddr> {
+        self.local_addr().into_iter().collect()
     }
 }
 
 /// Handle a single WebSocket connection
 ///
 /// This function:
-/// 1. Upgrades the TCP connection to WebSocket
-/// 2. Listens for broadcast messages
-/// 3. Sends broadcast messages to the client
-/// 4. Handles client disconnection
+/// - Upgrades the TCP connection to WebSocket, recording the handshake
+/// - Registers the client in the registry
+/// - Sends broadcast messages to the client
+/// - Forwards text messages to the control channel and sends back the replies
+/// - Closes the connection when the client leaves or a disconnect is requested
 async fn handle_connection(
     stream: TcpStream,
+    addr: SocketAddr,
     mut broadcast_rx: BroadcastReceiver,
+    clients: Arc<ClientRegistry>,
 ) -> Result<()> {
-    // Upgrade to WebSocket
+    let mut user_agent = None;
+    let mut subprotocol = None;
+
+    // Upgrade to WebSocket, accepting the first subprotocol the client offers
+    // (the error type is dictated by tungstenite)
+    #[allow(clippy::result_large_err)]
+    let callback = |request: &Request, mut response: Response| -> Result<Response, ErrorResponse> {
+        let header = |name| {
+            request
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        };
+        user_agent = header(USER_AGENT).map(str::to_string);
+        subprotocol = header(SEC_WEBSOCKET_PROTOCOL)
+            .and_then(|protocols| protocols.split(',').next())
+            .map(|protocol| protocol.trim().to_string());
+
+        if let Some(value) = subprotocol
+            .as_deref()
+            .and_then(|protocol| HeaderValue::from_str(protocol).ok())
+        {
+            response.headers_mut().insert(SEC_WEBSOCKET_PROTOCOL, value);
+        }
+        Ok(response)
+    };
     let ws_stream: WebSocketStream<TcpStream> =
-        tokio_tungstenite::accept_async(stream).await?;
+        tokio_tungstenite::accept_hdr_async(stream, callback).await?;
+
+    let (client, mut disconnect_rx) =
+        clients.register(SinkKind::WebSocket, addr, user_agent, subprotocol);
 
     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
+    let (reply_tx, mut reply_rx) = mpsc::unbounded_channel();
+    let client_id = client.id().to_string();
 
-    // Spawn task to receive messages from client (we don't expect any, but need to handle them)
-    let receive_task = tokio::spawn(async move {
+    // Spawn task to receive messages from client, forwarding text messages
+    // as control requests
+    let mut receive_task = tokio::spawn(async move {
         while let Some(msg) = ws_receiver.next().await {
             match msg {
                 Ok(Message::Close(_)) => {
                     break;
                 }
+                Ok(Message::Text(text)) => {
+                    if let Some(reply) = clients.forward_message(&client_id, text).await {
+                        let _ = reply_tx.send(reply);
+                    }
+                }
                 Ok(Message::Ping(data)) => {
                     // Pongs are handled automatically by tungstenite
                     println!("Received ping: {:?}", data);
@@ -182,27 +259,47 @@ async fn handle_connection(
 
     // Main loop: receive from broadcast and send to client
     loop {
-        match broadcast_rx.recv().await {
-            Ok(data) => {
-                // Send binary message to client
-                if let Err(e) = ws_sender.send(Message::Binary(data)).await {
+        tokio::select! {
+            msg = broadcast_rx.recv() => match msg {
+                Ok(data) => {
+                    let size = data.len();
+
+                    // Send binary message to client
+                    if let Err(e) = ws_sender.send(Message::Binary(data)).await {
+                        eprintln!("Error sending to client: {}", e);
+                        break;
+                    }
+                    client.record_sent(size);
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    eprintln!("Client lagged, skipped {} messages", skipped);
+                    client.record_lag(skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => {
+                    // Broadcast channel closed, exit
+                    break;
+                }
+            },
+            Some(reply) = reply_rx.recv() => {
+                // Replies go to this client only
+                if let Err(e) = ws_sender.send(Message::Text(reply)).await {
                     eprintln!("Error sending to client: {}", e);
                     break;
                 }
             }
-            Err(broadcast::error::RecvError::Lagged(skipped)) => {
-                eprintln!("Client lagged, skipped {} messages", skipped);
-                // Continue anyway
+            _ = &mut disconnect_rx => {
+                // Disconnect requested, close the connection cleanly
+                let _ = ws_sender.send(Message::Close(None)).await;
+                break;
             }
-            Err(broadcast::error::RecvError::Closed) => {
-                // Broadcast channel closed, exit
+            _ = &mut receive_task => {
+                // Client closed the connection
                 break;
             }
         }
     }
 
-    // Wait for receive task to complete
-    let _ = receive_task.await;
+    receive_task.abort();
 
     Ok(())
 }
@@ -210,16 +307,18 @@ async fn handle_connection(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 
     #[test]
     fn test_websocket_server_creation() {
-        let server = WebSocketServer::new();
+        let server = WebSocketServer::default();
         assert_eq!(server.get_connected_clients(), 0);
     }
 
     #[test]
     fn test_get_broadcaster() {
-        let server = WebSocketServer::new();
+        let server = WebSocketServer::default();
         let broadcaster = server.get_broadcaster();
 
         // Should be able to send messages
@@ -231,8 +330,50 @@ mod tests {
 
     #[tokio::test]
     async fn test_broadcast() {
-        let server = WebSocketServer::new();
+        let server = WebSocketServer::default();
         let result = server.broadcast(vec![1, 2, 3]).await;
         assert!(result.is_ok());
     }
+
+    async fn wait_for_clients(server: &WebSocketServer, count: usize) {
+        for _ in 0..100 {
+            if server.get_connected_clients() == count {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("Expected {} connected clients", count);
+    }
+
+    #[tokio::test]
+    async fn test_client_registry() {
+        let server = WebSocketServer::default();
+        server.start("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let addr = server.local_addr().unwrap();
+
+        let mut request = format!("ws://{}", addr).into_client_request().unwrap();
+        let headers = request.headers_mut();
+        headers.insert(USER_AGENT, HeaderValue::from_static("tuio-test"));
+        headers.insert(
+            SEC_WEBSOCKET_PROTOCOL,
+            HeaderValue::from_static("tuio2, osc"),
+        );
+        let (mut client, response) = tokio_tungstenite::connect_async(request).await.unwrap();
+        assert_eq!(response.headers()[SEC_WEBSOCKET_PROTOCOL], "tuio2");
+        wait_for_clients(&server, 1).await;
+
+        let info = server.clients.list().remove(0);
+        assert_eq!(info.sink, SinkKind::WebSocket);
+        assert_eq!(info.user_agent.as_deref(), Some("tuio-test"));
+        assert_eq!(info.subprotocol.as_deref(), Some("tuio2"));
+
+        server.broadcast(vec![1, 2, 3]).await.unwrap();
+        let msg = client.next().await.unwrap().unwrap();
+        assert_eq!(msg, Message::Binary(vec![1, 2, 3]));
+
+        // Stopping the server closes the connections
+        server.stop().await;
+        assert!(matches!(client.next().await, Some(Ok(Message::Close(_)))));
+        wait_for_clients(&server, 0).await;
+    }
 }
@@ -1,8 +1,34 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { ServerStatus } from "../types/tuio";
+import type {
+  ActiveMotion,
+  BindAddress,
+  ClientInfo,
+  ControlSendMode,
+  ControlValue,
+  FrameMode,
+  LoadMode,
+  Motion,
+  ObjectBounds,
+  ObjectData,
+  ObjectGeometry,
+  ObjectSymbol,
+  PlaybackStatus,
+  PointerType,
+  Pose3DInput,
+  ProtocolVersion,
+  RecordingInfo,
+  RecordingStatus,
+  ServerStatus,
+  SinkKind,
+  TcpFraming,
+} from "../types/tuio";
 
-export async function startServer(port: number): Promise<void> {
-  await invoke("start_server", { port });
+/** Rejects with a StartError */
+export async function startServer(
+  port: number,
+  bindAddress?: BindAddress
+): Promise<void> {
+  await invoke("start_server", { port, bindAddress: bindAddress ?? null });
 }
 
 export async function stopServer(): Promise<void> {
@@ -21,6 +47,32 @@ export async function addObject(
   });
 }
 
+export async function addPointer(
+  pointerType: PointerType,
+  x: number,
+  y: number
+): Promise<number> {
+  return await invoke<number>("add_pointer", {
+    pointerType,
+    x,
+    y,
+  });
+}
+
+export async function addBlob(
+  x: number,
+  y: number,
+  width: number,
+  height: number
+): Promise<number> {
+  return await invoke<number>("add_blob", {
+    x,
+    y,
+    width,
+    height,
+  });
+}
+
 export async function updateObject(
   sessionId: number,
   x: number,
@@ -35,6 +87,103 @@ export async function updateObject(
   });
 }
 
+export async function updatePointer(
+  sessionId: number,
+  x: number,
+  y: number,
+  angle: number,
+  shear: number,
+  radius: number,
+  pressure: number
+): Promise<void> {
+  await invoke("update_pointer", {
+    sessionId,
+    x,
+    y,
+    angle,
+    shear,
+    radius,
+    pressure,
+  });
+}
+
+export async function setObjectBounds(
+  sessionId: number,
+  bounds: ObjectBounds | null
+): Promise<void> {
+  await invoke("set_object_bounds", { sessionId, bounds });
+}
+
+export async function setObjectSymbol(
+  sessionId: number,
+  symbol: ObjectSymbol | null
+): Promise<void> {
+  await invoke("set_object_symbol", { sessionId, symbol });
+}
+
+export async function setObjectControls(
+  sessionId: number,
+  controls: ControlValue[]
+): Promise<void> {
+  await invoke("set_object_controls", { sessionId, controls });
+}
+
+export async function setObjectData(
+  sessionId: number,
+  data: ObjectData[]
+): Promise<void> {
+  await invoke("set_object_data", { sessionId, data });
+}
+
+export async function setObjectGeometry(
+  sessionId: number,
+  geometry: ObjectGeometry | null
+): Promise<void> {
+  await invoke("set_object_geometry", { sessionId, geometry });
+}
+
+export async function setObjectPose3D(
+  sessionId: number,
+  pose: Pose3DInput | null
+): Promise<void> {
+  await invoke("set_object_pose_3d", { sessionId, pose });
+}
+
+export async function associateContainer(
+  sessionId: number,
+  containerId: number,
+  slot: number
+): Promise<void> {
+  await invoke("associate_container", { sessionId, containerId, slot });
+}
+
+export async function dissociateContainer(sessionId: number): Promise<void> {
+  await invoke("dissociate_container", { sessionId });
+}
+
+export async function linkObjects(
+  sessionId: number,
+  targetId: number,
+  physical: boolean,
+  outPort: number,
+  inPort: number
+): Promise<void> {
+  await invoke("link_objects", {
+    sessionId,
+    targetId,
+    physical,
+    outPort,
+    inPort,
+  });
+}
+
+export async function unlinkObjects(
+  sessionId: number,
+  targetId: number
+): Promise<void> {
+  await invoke("unlink_objects", { sessionId, targetId });
+}
+
 export async function removeObject(sessionId: number): Promise<void> {
   await invoke("remove_object", { sessionId });
 }
@@ -43,13 +192,209 @@ export async function setFrameRate(fps: number): Promise<void> {
   await invoke("set_frame_rate", { fps });
 }
 
+export async function setFrameMode(
+  mode: FrameMode,
+  minIntervalMs: number,
+  keepAliveMs: number,
+  skipUnmovedTokens: boolean
+): Promise<void> {
+  await invoke("set_frame_mode", {
+    mode,
+    minIntervalMs,
+    keepAliveMs,
+    skipUnmovedTokens,
+  });
+}
+
+export async function pauseFrames(): Promise<void> {
+  await invoke("pause_frames");
+}
+
+export async function resumeFrames(): Promise<void> {
+  await invoke("resume_frames");
+}
+
+export async function stepFrame(count = 1): Promise<void> {
+  await invoke("step_frame", { count });
+}
+
+/** Send the last frame again, e.g. for clients that connected while paused */
+export async function resendFrame(): Promise<void> {
+  await invoke("resend_frame");
+}
+
 export async function getServerStatus(): Promise<ServerStatus> {
   return await invoke<ServerStatus>("get_server_status");
 }
 
+export async function listClients(): Promise<ClientInfo[]> {
+  return await invoke<ClientInfo[]>("list_clients");
+}
+
+export async function disconnectClient(clientId: string): Promise<void> {
+  await invoke("disconnect_client", { clientId });
+}
+
 export async function setCanvasDimensions(
   width: number,
   height: number
 ): Promise<void> {
   await invoke("set_canvas_dimensions", { width, height });
 }
+
+export async function setProtocolVersion(
+  version: ProtocolVersion
+): Promise<void> {
+  await invoke("set_protocol_version", { version });
+}
+
+export async function set3DOutput(
+  enabled: boolean,
+  depth: number
+): Promise<void> {
+  await invoke("set_3d_output", { enabled, depth });
+}
+
+export async function setControlSendMode(
+  mode: ControlSendMode
+): Promise<void> {
+  await invoke("set_control_send_mode", { mode });
+}
+
+/** Require WebSocket control clients to authenticate, null to allow all */
+export async function setControlToken(token: string | null): Promise<void> {
+  await invoke("set_control_token", { token });
+}
+
+export async function setHttpApi(enabled: boolean, port: number): Promise<void> {
+  await invoke("set_http_api", { enabled, port });
+}
+
+export async function setSinkEnabled(
+  kind: SinkKind,
+  enabled: boolean
+): Promise<void> {
+  await invoke("set_sink_enabled", { kind, enabled });
+}
+
+export async function setUdpTargets(targets: string[]): Promise<void> {
+  await invoke("set_udp_targets", { targets });
+}
+
+export async function setTcpOptions(
+  port: number,
+  framing: TcpFraming
+): Promise<void> {
+  await invoke("set_tcp_options", { port, framing });
+}
+
+export async function setFileSinkPath(path: string | null): Promise<void> {
+  await invoke("set_file_sink_path", { path });
+}
+
+export async function saveScene(path: string): Promise<void> {
+  await invoke("save_scene", { path });
+}
+
+/** Returns the session IDs of the loaded objects */
+export async function loadScene(
+  path: string,
+  mode: LoadMode = "replace"
+): Promise<number[]> {
+  return await invoke<number[]>("load_scene", { path, mode });
+}
+
+/** Limits are optional, the recording stops by itself once one is reached */
+export async function startRecording(
+  path: string,
+  maxBytes?: number,
+  maxDurationMs?: number
+): Promise<void> {
+  await invoke("start_recording", { path, maxBytes, maxDurationMs });
+}
+
+/** Returns the final status, or null if nothing was being recorded */
+export async function stopRecording(): Promise<RecordingStatus | null> {
+  return await invoke<RecordingStatus | null>("stop_recording");
+}
+
+export async function getRecordingStatus(): Promise<RecordingStatus | null> {
+  return await invoke<RecordingStatus | null>("get_recording_status");
+}
+
+export async function getRecordingInfo(path: string): Promise<RecordingInfo> {
+  return await invoke<RecordingInfo>("get_recording_info", { path });
+}
+
+/** Speed ranges from 0.1 to 10, the canvas is restored once playback ends */
+export async function startPlayback(
+  path: string,
+  speed?: number,
+  looping?: boolean
+): Promise<PlaybackStatus> {
+  return await invoke<PlaybackStatus>("start_playback", {
+    path,
+    speed,
+    looping,
+  });
+}
+
+/** Returns the final status, or null if nothing was being played */
+export async function stopPlayback(): Promise<PlaybackStatus | null> {
+  return await invoke<PlaybackStatus | null>("stop_playback");
+}
+
+export async function pausePlayback(): Promise<PlaybackStatus> {
+  return await invoke<PlaybackStatus>("pause_playback");
+}
+
+export async function resumePlayback(): Promise<PlaybackStatus> {
+  return await invoke<PlaybackStatus>("resume_playback");
+}
+
+export async function seekPlayback(positionMs: number): Promise<PlaybackStatus> {
+  return await invoke<PlaybackStatus>("seek_playback", { positionMs });
+}
+
+export async function setPlaybackSpeed(speed: number): Promise<PlaybackStatus> {
+  return await invoke<PlaybackStatus>("set_playback_speed", { speed });
+}
+
+export async function setPlaybackLooping(
+  looping: boolean
+): Promise<PlaybackStatus> {
+  return await invoke<PlaybackStatus>("set_playback_looping", { looping });
+}
+
+export async function getPlaybackStatus(): Promise<PlaybackStatus | null> {
+  return await invoke<PlaybackStatus | null>("get_playback_status");
+}
+
+/** Replaces the motion already attached to the object */
+export async function attachMotion(
+  sessionId: number,
+  motion: Motion
+): Promise<void> {
+  await invoke("attach_motion", { sessionId, motion });
+}
+
+export async function detachMotion(sessionId: number): Promise<void> {
+  await invoke("detach_motion", { sessionId });
+}
+
+export async function listMotions(): Promise<ActiveMotion[]> {
+  return await invoke<ActiveMotion[]>("list_motions");
+}
+
+/**
+ * Runs a Rhai script file until it ends, resolving with what it printed.
+ * Errors carry the file name, line and column.
+ */
+export async function runScript(path: string): Promise<string[]> {
+  return await invoke<string[]>("run_script", { path });
+}
+
+/** Stops all running scripts, their runScript calls reject */
+export async function stopScripts(): Promise<void> {
+  await invoke("stop_scripts");
+}
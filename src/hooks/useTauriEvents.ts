@@ -1,19 +1,30 @@
 import { useEffect } from "react";
 import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type {
+  ClientInfo,
+  PlaybackStatus,
+  RecordingStatus,
+} from "../types/tuio";
 
-export interface ClientConnectedEvent {
-  client_id: string;
-}
+export type ClientConnectedEvent = ClientInfo;
 
-export interface ClientDisconnectedEvent {
-  client_id: string;
-}
+/** Carries the final statistics of the client */
+export type ClientDisconnectedEvent = ClientInfo;
 
 export interface FrameSentEvent {
   frame_id: number;
   object_count: number;
 }
 
+export interface ObjectChangedEvent {
+  session_id: number;
+  change: "added" | "updated" | "removed";
+}
+
+export type RecordingProgressEvent = RecordingStatus;
+
+export type PlaybackProgressEvent = PlaybackStatus;
+
 export interface ServerStatusEvent {
   running: boolean;
   connected_clients: number;
@@ -24,6 +35,9 @@ export interface TauriEventHandlers {
   onClientDisconnected?: (event: ClientDisconnectedEvent) => void;
   onFrameSent?: (event: FrameSentEvent) => void;
   onServerStatus?: (event: ServerStatusEvent) => void;
+  onObjectChanged?: (event: ObjectChangedEvent) => void;
+  onRecordingProgress?: (event: RecordingProgressEvent) => void;
+  onPlaybackProgress?: (event: PlaybackProgressEvent) => void;
 }
 
 /**
@@ -72,6 +86,36 @@ export function useTauriEvents(handlers: TauriEventHandlers) {
         );
         unlisteners.push(unlisten);
       }
+
+      if (handlers.onObjectChanged) {
+        const unlisten = await listen<ObjectChangedEvent>(
+          "object_changed",
+          (event) => {
+            handlers.onObjectChanged?.(event.payload);
+          }
+        );
+        unlisteners.push(unlisten);
+      }
+
+      if (handlers.onRecordingProgress) {
+        const unlisten = await listen<RecordingProgressEvent>(
+          "recording_progress",
+          (event) => {
+            handlers.onRecordingProgress?.(event.payload);
+          }
+        );
+        unlisteners.push(unlisten);
+      }
+
+      if (handlers.onPlaybackProgress) {
+        const unlisten = await listen<PlaybackProgressEvent>(
+          "playback_progress",
+          (event) => {
+            handlers.onPlaybackProgress?.(event.payload);
+          }
+        );
+        unlisteners.push(unlisten);
+      }
     };
 
     setupListeners();
@@ -28,18 +28,34 @@ export function useTuioObjects(): UseTuioObjects {
     // Add to local state
     const newObject: TuioObject = {
       session_id: sessionId,
+      kind: "token",
       type_id: componentId, // Use componentId as type_id for color mapping
       user_id: 0,
       component_id: componentId,
       x,
       y,
       angle: 0,
+      shear: 0,
+      radius: 0,
+      pressure: 0,
+      bounds: null,
+      symbol: null,
+      geometry: null,
+      controls: [],
+      data: [],
+      container: null,
+      links: [],
+      pose_3d: null,
       x_vel: 0,
       y_vel: 0,
       angle_vel: 0,
+      pressure_vel: 0,
+      accel: 0,
+      angle_accel: 0,
       last_x: x,
       last_y: y,
       last_angle: 0,
+      last_pressure: 0,
       last_update: Date.now(),
     };
 
@@ -73,8 +89,16 @@ export function useTuioObjects(): UseTuioObjects {
   const removeObject = useCallback(async (sessionId: number): Promise<void> => {
     await commands.removeObject(sessionId);
 
-    // Remove from local state
-    setObjects((prev) => prev.filter((obj) => obj.session_id !== sessionId));
+    // Remove from local state, dropping associations like the backend
+    setObjects((prev) =>
+      prev
+        .filter((obj) => obj.session_id !== sessionId)
+        .map((obj) => ({
+          ...obj,
+          container: obj.container?.container_id === sessionId ? null : obj.container,
+          links: obj.links.filter((link) => link.target_id !== sessionId),
+        }))
+    );
 
     // Remove from selection
     setSelectedObjects((prev) => {
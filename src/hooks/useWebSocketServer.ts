@@ -1,6 +1,6 @@
 import { useState, useCallback, useEffect } from "react";
 import * as commands from "../api/commands";
-import type { ServerStatus } from "../types/tuio";
+import type { BindAddress, ServerStatus } from "../types/tuio";
 
 export interface UseWebSocketServerProps {
   initialPort?: number;
@@ -14,7 +14,7 @@ export interface UseWebSocketServer {
   frameCount: number;
   fps: number;
   objectCount: number;
-  startServer: (port: number) => Promise<void>;
+  startServer: (port: number, bindAddress?: BindAddress) => Promise<void>;
   stopServer: () => Promise<void>;
   setFps: (fps: number) => Promise<void>;
   refreshStatus: () => Promise<void>;
@@ -36,6 +36,12 @@ export function useWebSocketServer({
     connected_clients: 0,
     frame_count: 0,
     object_count: 0,
+    sinks: [],
+    paused: false,
+    pending_steps: 0,
+    achieved_fps: 0,
+    frame_jitter_ms: 0,
+    http_api_address: null,
   });
 
   const refreshStatus = useCallback(async () => {
@@ -44,8 +50,8 @@ export function useWebSocketServer({
   }, []);
 
   const startServer = useCallback(
-    async (port: number) => {
-      await commands.startServer(port);
+    async (port: number, bindAddress?: BindAddress) => {
+      await commands.startServer(port, bindAddress);
       await refreshStatus();
     },
     [refreshStatus]
@@ -1,26 +1,176 @@
+export type ObjectKind = "token" | "pointer" | "blob";
+
+export type PointerType = "finger" | "stylus" | "mouse";
+
+export interface ObjectBounds {
+  width: number;
+  height: number;
+  area: number;
+}
+
+export interface ObjectSymbol {
+  type_id: number;
+  group: string;
+  data: string;
+}
+
+/** Button state or slider/sensor value between -1 and 1 */
+export type ControlValue = boolean | number;
+
+export interface ObjectData {
+  mime: string;
+  data: number[];
+}
+
+export type ControlSendMode = "every_frame" | "on_change";
+
+/** Continuous frames at the frame rate, or only after changes plus keep-alives */
+export type FrameMode = "continuous" | "on_change";
+
+/** Replace the current scene or add the loaded objects to it */
+export type LoadMode = "replace" | "merge";
+
+export interface ContainerSlot {
+  container_id: number;
+  slot: number;
+}
+
+export interface ObjectLink {
+  target_id: number;
+  physical: boolean;
+  out_port: number;
+  in_port: number;
+}
+
+/** Points are [x, y] relative to the object center */
+export interface ObjectGeometry {
+  outline: [number, number][];
+  inner_contours: [number, number][][];
+  skeleton: [number, number][][];
+}
+
+export interface Pose3D {
+  z: number;
+  roll: number;
+  pitch: number;
+  depth: number;
+  z_vel: number;
+  roll_vel: number;
+  pitch_vel: number;
+  accel: number;
+  rotation_accel: number;
+  last_z: number;
+  last_roll: number;
+  last_pitch: number;
+}
+
+/** Editable part of a 3D pose, the motion is derived by the backend */
+export type Pose3DInput = Pick<Pose3D, "z" | "roll" | "pitch" | "depth">;
+
 export interface TuioObject {
   session_id: number;
+  kind: ObjectKind;
   type_id: number;
   user_id: number;
   component_id: number;
   x: number;
   y: number;
   angle: number;
+  shear: number;
+  radius: number;
+  pressure: number;
+  bounds: ObjectBounds | null;
+  symbol: ObjectSymbol | null;
+  geometry: ObjectGeometry | null;
+  controls: ControlValue[];
+  data: ObjectData[];
+  container: ContainerSlot | null;
+  links: ObjectLink[];
+  pose_3d: Pose3D | null;
   x_vel: number;
   y_vel: number;
   angle_vel: number;
+  pressure_vel: number;
+  accel: number;
+  angle_accel: number;
   last_x: number;
   last_y: number;
   last_angle: number;
+  last_pressure: number;
   last_update: number;
 }
 
+export type SinkKind = "websocket" | "udp" | "tcp" | "file";
+
+export type TcpFraming = "slip" | "length_prefix";
+
+export interface SinkStatus {
+  kind: SinkKind;
+  enabled: boolean;
+  running: boolean;
+  connections: number;
+  /** "host:port" addresses clients can connect to */
+  addresses: string[];
+}
+
+/** A client connected to the WebSocket or TCP server */
+export interface ClientInfo {
+  client_id: string;
+  sink: SinkKind;
+  peer_addr: string;
+  /** Milliseconds since the Unix epoch */
+  connected_at: number;
+  frames_sent: number;
+  bytes_sent: number;
+  lag_events: number;
+  skipped_frames: number;
+  user_agent: string | null;
+  subprotocol: string | null;
+}
+
+export type BindAddress =
+  | { type: "localhost" }
+  | { type: "all_interfaces" }
+  | { type: "address"; address: string };
+
+/** Error returned by startServer */
+export interface StartError {
+  kind: "already_running" | "port_in_use" | "address_unavailable" | "failed";
+  sink: SinkKind | null;
+  address: string | null;
+  message: string;
+}
+
+export type ProtocolVersion = "1.1" | "2.0";
+
 export interface Config {
   port: number;
+  bind_address: BindAddress;
   fps: number;
   width: number;
   height: number;
   source: string;
+  protocol_version: ProtocolVersion;
+  enabled_sinks: SinkKind[];
+  udp_targets: string[];
+  tcp_port: number;
+  tcp_framing: TcpFraming;
+  file_sink_path: string | null;
+  enable_3d: boolean;
+  depth: number;
+  control_send_mode: ControlSendMode;
+  /** Token WebSocket clients must send with "authenticate" to change the scene */
+  control_token: string | null;
+  /** Serve the REST API on 127.0.0.1 */
+  http_api_enabled: boolean;
+  http_api_port: number;
+  frame_mode: FrameMode;
+  /** Minimum time between two frames in on-change mode */
+  min_frame_interval_ms: number;
+  /** Time without changes after which a full frame is sent in on-change mode */
+  keep_alive_ms: number;
+  /** Leave out TOK for tokens that did not move in on-change mode */
+  skip_unmoved_tokens: boolean;
 }
 
 export interface ServerStatus {
@@ -30,4 +180,84 @@ export interface ServerStatus {
   connected_clients: number;
   frame_count: number;
   object_count: number;
+  sinks: SinkStatus[];
+  /** Frame generation is held, connections stay open */
+  paused: boolean;
+  /** Frames still to be generated while paused */
+  pending_steps: number;
+  /** Frame rate measured over the last frames */
+  achieved_fps: number;
+  /** Standard deviation of the frame intervals in milliseconds */
+  frame_jitter_ms: number;
+  /** Address of the HTTP API while it is running */
+  http_api_address: string | null;
+}
+
+export interface RecordingStatus {
+  path: string;
+  active: boolean;
+  frame_count: number;
+  /** Size of the session file so far */
+  bytes: number;
+  duration_ms: number;
+  /** Frames left out as the writer could not keep up */
+  dropped_frames: number;
+  /** The recording stopped as it reached one of its limits */
+  limit_reached: boolean;
+  /** The recording stopped as writing failed */
+  error: string | null;
+}
+
+export interface RecordingInfo {
+  path: string;
+  frame_count: number;
+  /** Offset of the last frame */
+  duration_ms: number;
+}
+
+export interface PlaybackStatus {
+  path: string;
+  /** False once the playback was stopped or reached the end */
+  active: boolean;
+  paused: boolean;
+  position_ms: number;
+  duration_ms: number;
+  frame_count: number;
+  speed: number;
+  looping: boolean;
+  /** Number of times the playback wrapped around to the start */
+  loop_count: number;
+}
+
+/** Path an object follows, in normalized coordinates */
+export type MotionPattern =
+  | {
+      type: "circular";
+      center: [number, number];
+      radius: number;
+      /** Revolutions per second, negative for clockwise */
+      speed: number;
+    }
+  | { type: "linear"; to: [number, number] }
+  | { type: "bezier"; points: [number, number][] }
+  /** Speed is the distance per second */
+  | { type: "random_walk"; speed: number };
+
+export interface MotionStep {
+  pattern: MotionPattern;
+  duration_ms: number;
+}
+
+/** Chain of patterns, each starting where the previous one ended */
+export interface Motion {
+  steps: MotionStep[];
+  looping?: boolean;
+}
+
+export interface ActiveMotion {
+  session_id: number;
+  motion: Motion;
+  /** Index of the current step */
+  step: number;
+  loop_count: number;
 }